@@ -2,6 +2,8 @@ use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use grib_build::grib2_codeflag_csv::OptArg;
+
 fn main() {
     let out_dir = env::var_os("OUT_DIR").unwrap();
 
@@ -13,6 +15,10 @@ fn main() {
         db.load(path).unwrap();
         println!("cargo:rerun-if-changed={}", file_name);
     }
+    // GRIB master tables versions published in Common Code Table C-0
+    if let Err(e) = db.ensure_covers(0, 28) {
+        panic!("{}", e);
+    }
     fs::write(&output_path, format!("{}", db)).unwrap();
 
     let input_file_names = [
@@ -37,6 +43,10 @@ fn main() {
         db.load(path).unwrap();
         println!("cargo:rerun-if-changed={}", file_name);
     }
+    // GRIB local tables version numbers in Code Table 1.1
+    if let Err(e) = db.ensure_covers((1, 1, OptArg::None), 254) {
+        panic!("{}", e);
+    }
     fs::write(&output_path, format!("{}", db)).unwrap();
 
     println!("cargo:rerun-if-changed=build.rs");
@@ -0,0 +1,44 @@
+use std::env;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::time::Instant;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // This example compares the time taken to decode all surfaces in a GRIB2 file
+    // sequentially and in parallel. Files with many submessages (20 or more) show
+    // the difference well.
+
+    // Take the first argument as an input file path.
+    let mut args = env::args().skip(1);
+    if let Some(file_name) = args.next() {
+        let path = Path::new(&file_name);
+        compare(path)
+    } else {
+        panic!("Usage: decode_all_rayon <path>");
+    }
+}
+
+fn compare(path: &Path) -> Result<(), Box<dyn Error>> {
+    let start = Instant::now();
+    let f = BufReader::new(File::open(path)?);
+    let grib2 = grib::from_reader(f)?;
+    let num_submessages = grib2.submessages().count();
+    for i in 0..num_submessages {
+        grib2.get_values(i)?;
+    }
+    let sequential = start.elapsed();
+
+    let start = Instant::now();
+    let values = grib::decode_all_rayon(path)?;
+    let parallel = start.elapsed();
+    assert_eq!(values.len(), num_submessages);
+
+    println!("submessages: {}", num_submessages);
+    println!("threads:     {}", rayon::current_num_threads());
+    println!("sequential:  {:?}", sequential);
+    println!("parallel:    {:?}", parallel);
+
+    Ok(())
+}
@@ -24,7 +24,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 
 fn decode_surface(path: &Path, index: usize) -> Result<(), Box<dyn Error>> {
     // Open the input file in a normal way.
-    let f = File::open(&path)?;
+    let f = File::open(path)?;
     let f = BufReader::new(f);
 
     // Read with the reader.
@@ -24,7 +24,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 
 fn find_surfaces(path: &Path, forecast_time_hours: u32) -> Result<(), Box<dyn Error>> {
     // Open the input file in a normal way.
-    let f = File::open(&path)?;
+    let f = File::open(path)?;
     let f = BufReader::new(f);
 
     // Read with the reader.
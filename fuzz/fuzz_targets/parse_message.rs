@@ -0,0 +1,22 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Computing coordinates is skipped for larger grids to avoid running out of
+// memory, which is not what this target is interested in.
+const MAX_NUM_POINTS: u32 = 1 << 20;
+
+fuzz_target!(|data: &[u8]| {
+    // Any input must result in either successfully parsed data or an error,
+    // never a panic.
+    if let Ok(grib2) = grib::parse_message(data) {
+        for submessage in grib2.iter() {
+            let _ = submessage.describe();
+            let _ = submessage.valid_time();
+            if submessage.grid_def().num_points() <= MAX_NUM_POINTS {
+                let _ = submessage.grid().map(|grid| grid.latlons());
+            }
+        }
+        let _ = grib2.inventory();
+    }
+});
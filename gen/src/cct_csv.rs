@@ -108,6 +108,15 @@ impl CodeDB {
     pub fn get(&self, id: u8) -> Option<&CodeTable> {
         self.data.get(&id)
     }
+
+    /// Checks that the table `id` has been loaded and defines all codes from
+    /// 0 to `max_code`, which guards against stale or sparse input files
+    /// silently producing incomplete tables.
+    pub fn ensure_covers(&self, id: u8, max_code: usize) -> Result<(), String> {
+        self.get(id)
+            .ok_or_else(|| format!("{} is not loaded", self.get_variable_name(id)))?
+            .ensure_covers(max_code)
+    }
 }
 
 impl fmt::Display for CodeDB {
@@ -241,6 +250,36 @@ const COMMON_CODE_TABLE_11: &[& str] = &[
         );
     }
 
+    #[test]
+    fn ensure_covers() {
+        let mut db = CodeDB::new();
+        assert_eq!(
+            db.ensure_covers(0, 3),
+            Err("COMMON_CODE_TABLE_00 is not loaded".to_owned())
+        );
+        db.load(PathBuf::from(PATH_STR_C00)).unwrap();
+        assert_eq!(db.ensure_covers(0, 3), Ok(()));
+        assert_eq!(
+            db.ensure_covers(0, 4),
+            Err(
+                "Common Code Table C-0 defines codes up to 3 while codes up to 4 are expected; \
+                 the input file may be stale or have gaps"
+                    .to_owned()
+            )
+        );
+
+        db.load(PathBuf::from(PATH_STR_C11)).unwrap();
+        assert_eq!(db.ensure_covers(11, 1), Ok(()));
+        assert_eq!(
+            db.ensure_covers(11, 3),
+            Err(
+                "Common Code Table C-11 does not define code 2 while codes up to 3 are expected; \
+                 the input file may have gaps"
+                    .to_owned()
+            )
+        );
+    }
+
     #[test]
     fn codetable_to_vec() {
         let mut db = CodeDB::new();
@@ -111,6 +111,15 @@ impl CodeDB {
     pub fn get(&self, id: (u8, u8, OptArg)) -> Option<&CodeTable> {
         self.data.get(&id)
     }
+
+    /// Checks that the table `id` has been loaded and defines all codes from
+    /// 0 to `max_code`, which guards against stale or sparse input files
+    /// silently producing incomplete tables.
+    pub fn ensure_covers(&self, id: (u8, u8, OptArg), max_code: usize) -> Result<(), String> {
+        self.get(id)
+            .ok_or_else(|| format!("{} is not loaded", self.get_variable_name(id)))?
+            .ensure_covers(max_code)
+    }
 }
 
 impl fmt::Display for CodeDB {
@@ -201,6 +210,25 @@ mod tests {
         assert_eq!(category, OptArg::L2(0, 0));
     }
 
+    #[test]
+    fn ensure_covers() {
+        let mut db = CodeDB::new();
+        assert_eq!(
+            db.ensure_covers((0, 0, OptArg::None), 1),
+            Err("CODE_TABLE_0_0 is not loaded".to_owned())
+        );
+        db.load(PathBuf::from(PATH_STR_0)).unwrap();
+        assert_eq!(db.ensure_covers((0, 0, OptArg::None), 1), Ok(()));
+        assert_eq!(
+            db.ensure_covers((0, 0, OptArg::None), 2),
+            Err(
+                "Foo defines codes up to 1 while codes up to 2 are expected; \
+                 the input file may be stale or have gaps"
+                    .to_owned()
+            )
+        );
+    }
+
     #[test]
     fn parse_file_no_subtitle() {
         let path = PathBuf::from(PATH_STR_0);
@@ -87,6 +87,28 @@ const {}: &[& str] = &{:#?};",
         )
     }
 
+    /// Checks that the table defines all codes from 0 to `max_code`.
+    fn ensure_covers(&self, max_code: usize) -> Result<(), String> {
+        let codes = self.to_vec();
+        if codes.len() <= max_code {
+            return Err(format!(
+                "{} defines codes up to {} while codes up to {} are expected; \
+                 the input file may be stale or have gaps",
+                self.desc,
+                codes.len() as isize - 1,
+                max_code
+            ));
+        }
+        match codes[..=max_code].iter().position(|s| s.is_empty()) {
+            None => Ok(()),
+            Some(code) => Err(format!(
+                "{} does not define code {} while codes up to {} are expected; \
+                 the input file may have gaps",
+                self.desc, code, max_code
+            )),
+        }
+    }
+
     fn to_vec(&self) -> Vec<String> {
         let mut output = Vec::new();
 
@@ -41,7 +41,7 @@ impl From<ParseIntError> for CliError {
 }
 
 pub fn grib(path: &PathBuf) -> Result<Grib2<SeekableGrib2Reader<BufReader<File>>>, CliError> {
-    let f = File::open(&path).map_err(|e| CliError::IO(e, path.display().to_string()))?;
+    let f = File::open(path).map_err(|e| CliError::IO(e, path.display().to_string()))?;
     let f = BufReader::new(f);
     Ok(grib::from_reader(f)?)
 }
@@ -1,8 +1,10 @@
 use clap::{arg, ArgMatches, Command};
 use std::fs::File;
-use std::io::{BufWriter, Write};
+use std::io::{self, BufWriter, Write};
 use std::path::PathBuf;
 
+use grib::grid::{Grid, GridTemplate1, GridTemplate2, GridTemplate3};
+
 use crate::cli;
 
 pub fn cli() -> Command<'static> {
@@ -11,16 +13,50 @@ pub fn cli() -> Command<'static> {
         .arg(arg!(<FILE> "Target file").value_parser(clap::value_parser!(PathBuf)))
         .arg(arg!(<INDEX> "Submessage index").value_parser(clap::value_parser!(usize)))
         .arg(
-            arg!(-b --"big-endian" <OUT_FILE> "Export as a big-endian flat binary file")
+            arg!(-b --"big-endian" <OUT_FILE> "Export as a big-endian flat binary file (\"-\" for stdout)")
                 .required(false) // There is no syntax yet for optional options.
                 .value_parser(clap::value_parser!(PathBuf)),
         )
         .arg(
-            arg!(-l --"little-endian" <OUT_FILE> "Export as a little-endian flat binary file")
+            arg!(-l --"little-endian" <OUT_FILE> "Export as a little-endian flat binary file (\"-\" for stdout)")
                 .required(false) // There is no syntax yet for optional options.
                 .value_parser(clap::value_parser!(PathBuf))
                 .conflicts_with("big-endian"),
         )
+        .arg(arg!(-m --meta "Show the grid shape and the bounding box to stderr"))
+}
+
+fn write_meta(grid: &Grid) {
+    let (ni, nj) = match grid {
+        Grid::LatLon(tmpl) => (tmpl.ni.to_string(), tmpl.nj),
+        Grid::ReducedLatLon(tmpl) => ("variable".to_owned(), tmpl.template.nj),
+        Grid::RotatedLatLon(GridTemplate1 { lat_lon, .. })
+        | Grid::StretchedLatLon(GridTemplate2 { lat_lon, .. })
+        | Grid::StretchedRotatedLatLon(GridTemplate3 { lat_lon, .. }) => {
+            (lat_lon.ni.to_string(), lat_lon.nj)
+        }
+    };
+    let latlons = grid.latlons();
+    let (lat_min, lat_max, lon_min, lon_max) = latlons.iter().fold(
+        (f32::MAX, f32::MIN, f32::MAX, f32::MIN),
+        |(lat_min, lat_max, lon_min, lon_max), (lat, lon)| {
+            (
+                lat_min.min(*lat),
+                lat_max.max(*lat),
+                lon_min.min(*lon),
+                lon_max.max(*lon),
+            )
+        },
+    );
+    eprint!(
+        "\
+Ni:                                     {}
+Nj:                                     {}
+Latitude range:                         {} - {}
+Longitude range:                        {} - {}
+",
+        ni, nj, lat_min, lat_max, lon_min, lon_max
+    );
 }
 
 fn write_output(
@@ -28,14 +64,23 @@ fn write_output(
     values: &[f32],
     to_bytes: fn(&f32) -> [u8; 4],
 ) -> Result<(), cli::CliError> {
-    File::create(out_path)
-        .and_then(|f| {
+    let write_all = |stream: &mut dyn Write| {
+        values
+            .iter()
+            .try_for_each(|f| stream.write_all(&to_bytes(f)))
+            .and_then(|_| stream.flush())
+    };
+
+    if out_path.as_os_str() == "-" {
+        let mut stream = BufWriter::new(io::stdout().lock());
+        write_all(&mut stream)
+    } else {
+        File::create(out_path).and_then(|f| {
             let mut stream = BufWriter::new(f);
-            values
-                .iter()
-                .try_for_each(|f| stream.write_all(&to_bytes(f)))
+            write_all(&mut stream)
         })
-        .map_err(|e| cli::CliError::IO(e, out_path.to_string_lossy().to_string()))
+    }
+    .map_err(|e| cli::CliError::IO(e, out_path.to_string_lossy().to_string()))
 }
 
 pub fn exec(args: &ArgMatches) -> Result<(), cli::CliError> {
@@ -44,6 +89,14 @@ pub fn exec(args: &ArgMatches) -> Result<(), cli::CliError> {
     let index = args.get_one::<usize>("INDEX").unwrap();
     let values = grib.get_values(*index)?;
 
+    if args.contains_id("meta") {
+        let submessage = grib
+            .iter()
+            .nth(*index)
+            .ok_or(grib::error::GribError::InternalDataError)?;
+        write_meta(&submessage.grid()?);
+    }
+
     if args.contains_id("big-endian") {
         let out_path = args.get_one::<PathBuf>("big-endian").unwrap();
         write_output(out_path, &values, |f| f.to_be_bytes())
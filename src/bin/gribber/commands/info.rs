@@ -63,7 +63,10 @@ Type of processed data:                 {}
             self.identification.local_table_version(),
             CodeTable1_1.lookup(self.identification.local_table_version() as usize),
             CodeTable1_2.lookup(self.identification.ref_time_significance() as usize),
-            self.identification.ref_time(),
+            self.identification
+                .ref_time()
+                .map(|t| t.to_string())
+                .unwrap_or_else(|| "missing".to_owned()),
             CodeTable1_3.lookup(self.identification.prod_status() as usize),
             CodeTable1_4.lookup(self.identification.data_type() as usize)
         )
@@ -4,7 +4,7 @@ use std::fmt::{self, Display, Formatter};
 use std::path::PathBuf;
 
 use grib::codetables::{CodeTable4_2, CodeTable4_3, Lookup};
-use grib::context::SubmessageIterator;
+use grib::context::SubMessage;
 
 use crate::cli;
 
@@ -12,6 +12,16 @@ pub fn cli() -> Command<'static> {
     Command::new("list")
         .about("List surfaces contained in the data")
         .arg(arg!(-d --dump "Show details of each data"))
+        .arg(
+            arg!(--param <PARAM> "Show only data of the parameter specified by the name or \"<category>.<number>\"")
+                .required(false),
+        )
+        .arg(
+            arg!(--level <LEVEL> "Show only data whose 1st fixed surface value equals to the specified one, optionally with a unit of \"hPa\", \"Pa\" or \"m\" (e.g. \"850 hPa\")")
+                .required(false)
+                .allow_hyphen_values(true)
+                .value_parser(parse_level),
+        )
         .arg(arg!(<FILE> "Target file").value_parser(clap::value_parser!(PathBuf)))
 }
 
@@ -24,7 +34,16 @@ pub fn exec(args: &ArgMatches) -> Result<(), cli::CliError> {
     } else {
         ListViewMode::OneLine
     };
-    let view = ListView::new(grib.submessages(), mode);
+    let filter = SubmessageFilter {
+        param: args.get_one::<String>("param").cloned(),
+        level: args.get_one::<f64>("level").copied(),
+    };
+    let data = grib
+        .submessages()
+        .enumerate()
+        .filter(|(_, submessage)| filter.matches(submessage))
+        .collect();
+    let view = ListView::new(data, mode);
 
     let user_attended = console::user_attended();
 
@@ -43,13 +62,76 @@ pub fn exec(args: &ArgMatches) -> Result<(), cli::CliError> {
     Ok(())
 }
 
+/// Parses a level with an optional unit into the value in the units of
+/// fixed surface values, i.e. Pa for pressure and m for height.
+fn parse_level(s: &str) -> Result<f64, String> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| c.is_ascii_alphabetic() && c != 'e' && c != 'E')
+        .unwrap_or(s.len());
+    let (value, unit) = s.split_at(split);
+    let value = value
+        .trim()
+        .parse::<f64>()
+        .map_err(|e| format!("invalid level value: {}", e))?;
+    let scale = match unit.trim() {
+        "" | "Pa" | "m" => 1.,
+        "hPa" => 100.,
+        unit => return Err(format!("unknown unit of level: {}", unit)),
+    };
+    Ok(value * scale)
+}
+
+struct SubmessageFilter {
+    param: Option<String>,
+    level: Option<f64>,
+}
+
+impl SubmessageFilter {
+    fn matches(&self, submessage: &SubMessage) -> bool {
+        let prod_def = submessage.prod_def();
+        if let Some(param) = &self.param {
+            let matched = prod_def
+                .parameter_category()
+                .zip(prod_def.parameter_number())
+                .map(|(c, n)| {
+                    param == &format!("{}.{}", c, n)
+                        || parameter_name(submessage, c, n).eq_ignore_ascii_case(param)
+                })
+                .unwrap_or(false);
+            if !matched {
+                return false;
+            }
+        }
+        if let Some(level) = self.level {
+            let matched = prod_def
+                .fixed_surfaces()
+                .map(|(first, _)| {
+                    let value = first.value();
+                    (value - level).abs() <= f64::EPSILON * value.abs().max(level.abs())
+                })
+                .unwrap_or(false);
+            if !matched {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn parameter_name(submessage: &SubMessage, category: u8, number: u8) -> String {
+    CodeTable4_2::new(submessage.indicator().discipline, category)
+        .lookup(usize::from(number))
+        .to_string()
+}
+
 struct ListView<'i> {
-    data: SubmessageIterator<'i>,
+    data: Vec<(usize, SubMessage<'i>)>,
     mode: ListViewMode,
 }
 
 impl<'i> ListView<'i> {
-    fn new(data: SubmessageIterator<'i>, mode: ListViewMode) -> Self {
+    fn new(data: Vec<(usize, SubMessage<'i>)>, mode: ListViewMode) -> Self {
         Self { data, mode }
     }
 
@@ -57,13 +139,12 @@ impl<'i> ListView<'i> {
         match self.mode {
             ListViewMode::OneLine => {
                 let header_height = 1;
-                let (len, _) = self.data.size_hint();
-                header_height + len
+                header_height + self.data.len()
             }
             ListViewMode::Dump => {
                 let unit_height = 8; // lines of output from SubMessage.describe(), hard-coded as of now
-                let (len, _) = self.data.size_hint();
-                (unit_height + 2) * len - 1
+                let len = self.data.len();
+                ((unit_height + 2) * len).saturating_sub(1)
             }
         }
     }
@@ -85,16 +166,12 @@ impl<'i> Display for ListView<'i> {
                 let style = Style::new().bold();
                 write!(f, "{}", style.apply_to(header))?;
 
-                for (i, submessage) in self.data.clone().enumerate() {
+                for (i, submessage) in self.data.iter() {
                     let prod_def = submessage.prod_def();
                     let category = prod_def
                         .parameter_category()
                         .zip(prod_def.parameter_number())
-                        .map(|(c, n)| {
-                            CodeTable4_2::new(submessage.indicator().discipline, c)
-                                .lookup(usize::from(n))
-                                .to_string()
-                        })
+                        .map(|(c, n)| parameter_name(submessage, c, n))
                         .unwrap_or_default();
                     let generating_process = prod_def
                         .generating_process()
@@ -118,7 +195,7 @@ impl<'i> Display for ListView<'i> {
                 }
             }
             ListViewMode::Dump => {
-                for (i, submessage) in self.data.clone().enumerate() {
+                for (i, submessage) in self.data.iter() {
                     write!(f, "{}\n{}\n", i, submessage.describe())?;
                 }
             }
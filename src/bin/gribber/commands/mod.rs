@@ -3,7 +3,13 @@ use clap::{ArgMatches, Command};
 use crate::cli;
 
 pub fn cli() -> Vec<Command<'static>> {
-    vec![decode::cli(), info::cli(), inspect::cli(), list::cli()]
+    vec![
+        decode::cli(),
+        info::cli(),
+        inspect::cli(),
+        list::cli(),
+        point::cli(),
+    ]
 }
 
 pub fn dispatch(matches: ArgMatches) -> Result<(), cli::CliError> {
@@ -12,6 +18,7 @@ pub fn dispatch(matches: ArgMatches) -> Result<(), cli::CliError> {
         Some(("info", args)) => info::exec(args),
         Some(("inspect", args)) => inspect::exec(args),
         Some(("list", args)) => list::exec(args),
+        Some(("point", args)) => point::exec(args),
         _ => unreachable!(),
     }
 }
@@ -20,3 +27,4 @@ pub mod decode;
 pub mod info;
 pub mod inspect;
 pub mod list;
+pub mod point;
@@ -0,0 +1,101 @@
+use clap::{arg, ArgMatches, Command};
+use console::Style;
+use std::path::PathBuf;
+
+use grib::codetables::{CodeTable4_2, Lookup};
+use grib::error::GribError;
+
+use crate::cli;
+
+pub fn cli() -> Command<'static> {
+    Command::new("point")
+        .about("Show values at the grid point nearest to the specified location")
+        .arg(
+            arg!(--lat <LAT> "Latitude of the location in degrees")
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(f32)),
+        )
+        .arg(
+            arg!(--lon <LON> "Longitude of the location in degrees")
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(f32)),
+        )
+        .arg(arg!(<FILE> "Target file").value_parser(clap::value_parser!(PathBuf)))
+}
+
+pub fn exec(args: &ArgMatches) -> Result<(), cli::CliError> {
+    let file_name = args.get_one::<PathBuf>("FILE").unwrap();
+    let grib = cli::grib(file_name)?;
+    let lat = *args.get_one::<f32>("lat").unwrap();
+    let lon = *args.get_one::<f32>("lon").unwrap();
+
+    if console::user_attended() {
+        console::set_colors_enabled(true);
+    }
+
+    let header = format!(
+        "{:>5} │ {:<31} {:>17} {:<23} {:>9} {:>9} {:>14}\n",
+        "id", "Parameter", "1st fixed surface", "Valid time", "Latitude", "Longitude", "Value"
+    );
+    let style = Style::new().bold();
+    print!("{}", style.apply_to(header));
+
+    for (i, submessage) in grib.iter().enumerate() {
+        let prod_def = submessage.prod_def();
+        let parameter = prod_def
+            .parameter_category()
+            .zip(prod_def.parameter_number())
+            .map(|(c, n)| {
+                CodeTable4_2::new(submessage.indicator().discipline, c)
+                    .lookup(usize::from(n))
+                    .to_string()
+            })
+            .unwrap_or_default();
+        let surface = prod_def
+            .fixed_surfaces()
+            .map(|(first, _)| first.value().to_string())
+            .unwrap_or_default();
+        let valid_time = submessage
+            .valid_time()
+            .map(|t| t.to_string())
+            .unwrap_or_default();
+
+        // submessages whose grids or values cannot be handled are shown
+        // without values instead of aborting the whole command
+        let (point_lat, point_lon, value) = match submessage.grid() {
+            Ok(grid) => match grid
+                .nearest(lat, lon)
+                .and_then(|index| Some((index, grid.latlon(index)?)))
+            {
+                Some((index, (point_lat, point_lon))) => {
+                    let value = match grib.get_values(i) {
+                        Ok(values) => values[index].to_string(),
+                        Err(e) => error_label(i, e),
+                    };
+                    (point_lat.to_string(), point_lon.to_string(), value)
+                }
+                None => (String::new(), String::new(), String::new()),
+            },
+            Err(e) => (String::new(), String::new(), error_label(i, e)),
+        };
+
+        println!(
+            "{:>5} │ {:<31} {:>17} {:<23} {:>9} {:>9} {:>14}",
+            i, parameter, surface, valid_time, point_lat, point_lon, value
+        );
+    }
+
+    Ok(())
+}
+
+/// Returns the text shown in place of a value that cannot be obtained.
+/// Errors other than unsupported features are reported to stderr.
+fn error_label(i: usize, e: GribError) -> String {
+    match e {
+        GribError::Unsupported(_) => "unsupported".to_owned(),
+        e => {
+            eprintln!("submessage {}: {}", i, e);
+            "error".to_owned()
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use num_enum::{IntoPrimitive, TryFromPrimitive};
+use num_enum::{FromPrimitive, IntoPrimitive, TryFromPrimitive};
+use std::fmt;
+
+use crate::codetables::{CodeTable0_0, Lookup};
 
 #[derive(Debug, Eq, PartialEq, TryFromPrimitive, IntoPrimitive)]
 #[repr(u8)]
@@ -38,6 +41,37 @@ impl Table4_4 {
     }
 }
 
+/// Discipline of processed data (see Code Table 0.0)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum Discipline {
+    Meteorological = 0,
+    Hydrological,
+    LandSurface,
+    SatelliteRemoteSensing,
+    SpaceWeather,
+    Oceanographic = 10,
+    HealthAndSocioeconomicImpacts = 20,
+    #[num_enum(catch_all)]
+    Unknown(u8),
+}
+
+impl Discipline {
+    pub fn from_u8(code: u8) -> Self {
+        Self::from(code)
+    }
+}
+
+impl fmt::Display for Discipline {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let code = u8::from(*self);
+        match self {
+            Self::Unknown(_) => write!(f, "unknown discipline {}", code),
+            _ => write!(f, "{}", CodeTable0_0.lookup(usize::from(code))),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -57,6 +91,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn discipline_conversion() {
+        assert_eq!(Discipline::from_u8(0), Discipline::Meteorological);
+        assert_eq!(Discipline::from_u8(10), Discipline::Oceanographic);
+        assert_eq!(Discipline::from_u8(5), Discipline::Unknown(5));
+        assert_eq!(u8::from(Discipline::Unknown(5)), 5);
+        assert_eq!(
+            Discipline::Meteorological.to_string(),
+            "Meteorological products"
+        );
+        assert_eq!(
+            Discipline::Oceanographic.to_string(),
+            "Oceanographic products"
+        );
+        assert_eq!(Discipline::Unknown(5).to_string(), "unknown discipline 5");
+    }
+
     #[test]
     fn num_lookup_result_conversion() {
         assert_eq!(Code::from(Table4_4::try_from(1u8)), Name(Table4_4::Hour));
@@ -44,6 +44,23 @@ impl ArrayLookup for CommonCodeTable11 {
     }
 }
 
+/// Returns the name of the originating/generating centre with `id` in
+/// Common Code Table C-11, or `None` if `id` is not assigned.
+pub fn lookup_centre(id: u16) -> Option<&'static str> {
+    lookup_assigned(COMMON_CODE_TABLE_11, usize::from(id))
+}
+
+/// Returns the name of the type of processed data `code` in Code Table 1.4,
+/// or `None` if `code` is not assigned.
+pub fn lookup_data_type(code: u8) -> Option<&'static str> {
+    lookup_assigned(CODE_TABLE_1_4, usize::from(code))
+}
+
+// Generated tables have empty strings for unassigned codes in the middle.
+fn lookup_assigned(table: &'static [&'static str], code: usize) -> Option<&'static str> {
+    table.get(code).copied().filter(|name| !name.is_empty())
+}
+
 pub struct CodeTable0_0;
 
 impl ArrayLookup for CodeTable0_0 {
@@ -207,6 +224,12 @@ impl ArrayLookup for CodeTable4_3 {
     }
 }
 
+/// Returns the name of the type of generating process `code` in Code Table
+/// 4.3, or `None` if `code` is not assigned.
+pub fn lookup_generating_process(code: u8) -> Option<&'static str> {
+    lookup_assigned(CODE_TABLE_4_3, usize::from(code))
+}
+
 pub struct CodeTable4_4;
 
 impl ArrayLookup for CodeTable4_4 {
@@ -223,6 +246,12 @@ impl ArrayLookup for CodeTable4_5 {
     }
 }
 
+/// Returns the name of the type of fixed surface `code` in Code Table 4.5,
+/// or `None` if `code` is not assigned.
+pub fn lookup_fixed_surface(code: u8) -> Option<&'static str> {
+    lookup_assigned(CODE_TABLE_4_5, usize::from(code))
+}
+
 pub struct CodeTable5_0;
 
 impl ArrayLookup for CodeTable5_0 {
@@ -256,3 +285,61 @@ pub(crate) const SUPPORTED_PROD_DEF_TEMPLATE_NUMBERS: [u16; 71] = [
     43, 44, 45, 46, 47, 48, 49, 51, 53, 54, 55, 56, 57, 58, 59, 60, 61, 62, 63, 67, 68, 70, 71, 72,
     73, 76, 77, 78, 79, 80, 81, 82, 83, 84, 85, 86, 87, 88, 91, 254, 1000, 1001, 1002, 1100, 1101,
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn centre_lookup() {
+        assert_eq!(lookup_centre(0), Some("WMO Secretariat"));
+        assert_eq!(
+            lookup_centre(98),
+            Some("European Centre for Medium Range Weather Forecasts (ECMWF) (RSMC)")
+        );
+        assert_eq!(
+            lookup_centre(34),
+            Some("Tokyo (RSMC), Japan Meteorological Agency")
+        );
+        assert_eq!(lookup_centre(u16::MAX), None);
+    }
+
+    #[test]
+    fn master_table_version_lookup() {
+        assert_eq!(CommonCodeTable00.lookup(5).to_string(), "4 November 2009");
+        assert_eq!(CommonCodeTable00.lookup(28).to_string(), "15 November 2021");
+    }
+
+    #[test]
+    fn local_table_version_lookup() {
+        assert_eq!(
+            CodeTable1_1.lookup(5).to_string(),
+            "Number of local tables version used"
+        );
+    }
+
+    #[test]
+    fn fixed_surface_lookup() {
+        assert_eq!(lookup_fixed_surface(1), Some("Ground or water surface"));
+        assert_eq!(lookup_fixed_surface(100), Some("Isobaric surface"));
+        assert_eq!(
+            lookup_fixed_surface(103),
+            Some("Specified height level above ground")
+        );
+        assert_eq!(lookup_fixed_surface(105), Some("Hybrid level"));
+        assert_eq!(lookup_fixed_surface(0), None);
+        assert_eq!(lookup_fixed_surface(255), None);
+    }
+
+    #[test]
+    fn generating_process_lookup() {
+        assert_eq!(lookup_generating_process(0), Some("Analysis"));
+        assert_eq!(lookup_generating_process(1), Some("Initialization"));
+        assert_eq!(lookup_generating_process(2), Some("Forecast"));
+        assert_eq!(lookup_generating_process(4), Some("Ensemble forecast"));
+        assert_eq!(lookup_generating_process(5), Some("Probability forecast"));
+        assert_eq!(lookup_generating_process(7), Some("Analysis error"));
+        assert_eq!(lookup_generating_process(8), Some("Observation"));
+        assert_eq!(lookup_generating_process(255), None);
+    }
+}
@@ -1,17 +1,28 @@
+use chrono::{DateTime, Utc};
 use std::cell::RefCell;
-use std::collections::HashSet;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{self, Display, Formatter};
-use std::io::{Cursor, Read, Seek};
+use std::hash::{Hash, Hasher};
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
 use std::result::Result;
+#[cfg(feature = "rayon")]
+use std::{fs::File, io::BufReader, path::Path};
 
+use crate::codetables::grib2::Discipline;
 use crate::codetables::{
     CodeTable3_1, CodeTable4_0, CodeTable4_1, CodeTable4_2, CodeTable4_3, CodeTable5_0, Lookup,
 };
 use crate::datatypes::*;
-use crate::decoders;
+use crate::decoders::{self, DecodeOptions, OutputFormat};
 use crate::error::*;
+use crate::field::{CornerValues, DecodedField, FieldStack, GridValue, ParameterInfo};
+use crate::grid::{Grid, SpectralTruncation, START_OF_GRID_TEMPLATE};
 use crate::parser::Grib2SubmessageIndexStream;
-use crate::reader::{Grib2Read, Grib2SectionStream, SeekableGrib2Reader, SECT8_ES_SIZE};
+use crate::reader::{
+    Grib2Read, Grib2SectionStream, SeekableGrib2Reader, DEFAULT_MAX_MESSAGE_SIZE, SECT8_ES_SIZE,
+};
 
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct SectionInfo {
@@ -19,6 +30,9 @@ pub struct SectionInfo {
     pub offset: usize,
     pub size: usize,
     pub body: Option<SectionBody>,
+    /// CRC-32 of the raw bytes of the section, set only when sections are
+    /// read with [`ReadOptions::compute_checksums`].
+    pub(crate) checksum: Option<u32>,
 }
 
 impl SectionInfo {
@@ -33,6 +47,118 @@ impl SectionInfo {
             offset,
             size: SECT8_ES_SIZE,
             body: None,
+            checksum: None,
+        }
+    }
+
+    /// Returns the CRC-32 of the raw bytes of the section including its
+    /// header, if it has been computed.
+    pub fn checksum(&self) -> Option<u32> {
+        self.checksum
+    }
+
+    /// Reads the raw bytes of the section including its header from `reader`
+    /// and returns a dump of them in hex and ASCII, with offsets in the data,
+    /// for debugging.
+    ///
+    /// For Section 1, the dump is followed by values of fields annotated with
+    /// their octet numbers, such as "octets 6-7: centre_id = 34".
+    pub fn hexdump<R: Read + Seek>(&self, reader: &mut R) -> Result<String, GribError> {
+        let mut bytes = vec![0; self.size];
+        reader
+            .seek(SeekFrom::Start(self.offset as u64))
+            .and_then(|_| reader.read_exact(&mut bytes))
+            .map_err(ParseError::from)?;
+
+        let mut dump = String::new();
+        for (i, line) in bytes.chunks(16).enumerate() {
+            let hex = line
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let ascii = line
+                .iter()
+                .map(|b| {
+                    if b.is_ascii_graphic() || *b == b' ' {
+                        char::from(*b)
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>();
+            dump.push_str(&format!(
+                "{:08x}  {:<47}  |{}|\n",
+                self.offset + i * 16,
+                hex,
+                ascii
+            ));
+        }
+
+        let fields: &[(usize, usize, &str)] = match self.num {
+            1 => &SECT1_FIELDS,
+            _ => &[],
+        };
+        for (start, end, name) in fields {
+            let octets = match bytes.get(start - 1..*end) {
+                Some(octets) => octets,
+                None => break,
+            };
+            let value = octets
+                .iter()
+                .fold(0_u64, |acc, b| (acc << 8) | u64::from(*b));
+            let octets = if start == end {
+                format!("octet {}", start)
+            } else {
+                format!("octets {}-{}", start, end)
+            };
+            dump.push_str(&format!("{}: {} = {}\n", octets, name, value));
+        }
+        Ok(dump)
+    }
+}
+
+/// Octet numbers (first and last) and names of fields in Section 1
+const SECT1_FIELDS: [(usize, usize, &str); 15] = [
+    (1, 4, "section_length"),
+    (5, 5, "section_number"),
+    (6, 7, "centre_id"),
+    (8, 9, "subcentre_id"),
+    (10, 10, "master_table_version"),
+    (11, 11, "local_table_version"),
+    (12, 12, "ref_time_significance"),
+    (13, 14, "year"),
+    (15, 15, "month"),
+    (16, 16, "day"),
+    (17, 17, "hour"),
+    (18, 18, "minute"),
+    (19, 19, "second"),
+    (20, 20, "prod_status"),
+    (21, 21, "data_type"),
+];
+
+/// Options controlling how sections are read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadOptions {
+    /// Computes a CRC-32 of the raw bytes of each section while reading,
+    /// which is available via [`SectionInfo::checksum`]. This requires
+    /// reading payloads of Section 7, which are otherwise skipped.
+    pub compute_checksums: bool,
+    /// Upper limit of the total length of a message declared in Section 0.
+    /// Defaults to [`DEFAULT_MAX_MESSAGE_SIZE`].
+    pub max_message_size: u64,
+    /// Checks each grid definition with [`Grid::validate`] after reading,
+    /// failing with [`GribError::GridError`] on inconsistent grids. Grids
+    /// whose templates are not supported are not checked.
+    pub validate_grids: bool,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self {
+            compute_checksums: false,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            validate_grids: false,
         }
     }
 }
@@ -86,11 +212,381 @@ pub fn from_reader<SR: Read + Seek>(
     Grib2::<SeekableGrib2Reader<SR>>::read_with_seekable(reader)
 }
 
+/// Reads GRIB2 data like [`from_reader`] with options.
+pub fn from_reader_with_options<SR: Read + Seek>(
+    reader: SR,
+    opts: &ReadOptions,
+) -> Result<Grib2<SeekableGrib2Reader<SR>>, GribError> {
+    Grib2::read_with_options(SeekableGrib2Reader::new(reader), opts)
+}
+
 pub fn from_slice(bytes: &[u8]) -> Result<Grib2<SeekableGrib2Reader<Cursor<&[u8]>>>, GribError> {
     let reader = Cursor::new(bytes);
     Grib2::<SeekableGrib2Reader<Cursor<&[u8]>>>::read_with_seekable(reader)
 }
 
+/// Reads only the GRIB2 message starting at `offset` in `reader`, which is
+/// useful for random access to messages whose offsets are known in advance,
+/// e.g. from an index.
+///
+/// Octets before `offset` and after the end of the message are not read.
+/// [`ParseError::NotGRIB`] is returned if no message starts at `offset`.
+pub fn message_at_offset<SR: Read + Seek>(
+    mut reader: SR,
+    offset: u64,
+) -> Result<Grib2<SeekableGrib2Reader<SR>>, GribError> {
+    reader
+        .seek(SeekFrom::Start(offset))
+        .map_err(ParseError::from)?;
+    let start_offset = usize::try_from(offset)
+        .map_err(|_| ParseError::ReadError(format!("offset out of range: {}", offset)))?;
+    let mut sect_stream =
+        Grib2SectionStream::new(SeekableGrib2Reader::new(reader)).with_start_offset(start_offset);
+
+    // stops right after the end of the first message
+    let mut end_of_message = false;
+    let sect_iter = std::iter::from_fn(|| {
+        if end_of_message {
+            return None;
+        }
+        let sect = sect_stream.next()?;
+        end_of_message = !matches!(sect, Ok(SectionInfo { num: 0..=7, .. }));
+        Some(sect)
+    });
+
+    let mut parser = Grib2SubmessageIndexStream::new(sect_iter).with_cacher();
+    let submessages = parser.by_ref().collect::<Result<Vec<_>, _>>()?;
+    let sections = parser.into_cached_sections();
+    Ok(Grib2 {
+        reader: RefCell::new(sect_stream.into_reader()),
+        sections: sections.into_boxed_slice(),
+        submessages,
+    })
+}
+
+/// Lazily reads GRIB2 data from a reader and iterates over submessages,
+/// yielding an error at the point it occurs instead of failing the whole read
+/// as [`from_reader`] does.
+///
+/// Submessages of a message are yielded once Section 8 of the message has
+/// been read. Since the reader cannot be resynchronized after invalid data,
+/// the iteration ends with the first error, and the submessages of the
+/// message in which it occurred are not yielded.
+///
+/// Only sections of the message being read are kept in memory, so memory
+/// usage does not grow with the number of messages.
+///
+/// # Example
+/// ```
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let f = std::fs::File::open(
+///         "testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+///     )?;
+///     let f = std::io::BufReader::new(f);
+///     let submessages = grib::iter_submessages(f)
+///         .filter_map(Result::ok)
+///         .collect::<Vec<_>>();
+///     assert_eq!(submessages.len(), 1);
+///     Ok(())
+/// }
+/// ```
+pub fn iter_submessages<SR: Read + Seek>(
+    reader: SR,
+) -> FallibleSubmessageIterator<SeekableGrib2Reader<SR>> {
+    let sect_stream = Grib2SectionStream::new(SeekableGrib2Reader::new(reader));
+    FallibleSubmessageIterator {
+        parser: Grib2SubmessageIndexStream::new(sect_stream).with_cacher(),
+        pending: Vec::new(),
+        ready: Vec::new().into_iter(),
+        num_released_sections: 0,
+    }
+}
+
+/// Decodes values of all submessages in the file at `path` in parallel using
+/// the global rayon thread pool.
+///
+/// Since a file handle cannot be shared between threads, each worker opens
+/// the file for itself and decodes the submessages assigned to it. Decoding
+/// is done sequentially if the pool has only one thread.
+#[cfg(feature = "rayon")]
+pub fn decode_all_rayon<P: AsRef<Path>>(path: P) -> Result<Vec<Box<[f32]>>, GribError> {
+    use rayon::prelude::*;
+
+    let path = path.as_ref();
+    let open = || -> Result<BufReader<File>, GribError> {
+        let f = File::open(path).map_err(ParseError::from)?;
+        Ok(BufReader::new(f))
+    };
+
+    let grib2 = from_reader(open()?)?;
+    if rayon::current_num_threads() == 1 {
+        return grib2.decode_all();
+    }
+
+    let submessages = grib2.into_iter().collect::<Vec<_>>();
+    submessages
+        .par_iter()
+        .enumerate()
+        .map_init(open, |reader, (i, submessage)| match reader {
+            Ok(reader) => submessage
+                .decode(reader)
+                .map_err(|e| with_submessage_index(e, i)),
+            Err(e) => Err(e.clone()),
+        })
+        .collect()
+}
+
+/// Adds the index of the submessage to decoding errors.
+fn with_submessage_index(e: GribError, i: usize) -> GribError {
+    match e {
+        GribError::DecodeError(e) => GribError::DecodeError(e.with_submessage_index(i)),
+        e => e,
+    }
+}
+
+/// Reads GRIB2 data from a reader without [`Seek`] support, such as a
+/// decompressor or a network stream.
+///
+/// Since decoding needs random access to sections, the whole stream is
+/// first read into memory, while [`from_reader`] reads only section
+/// headers when scanning and reads payloads on demand.
+///
+/// Padding with zeros or ASCII whitespace after the end of a message, which
+/// is common in piped input, is skipped. Reading stops before any octets
+/// other than padding that do not start with "GRIB". Offsets of sections
+/// are those in the stream, including the padding skipped.
+pub fn from_stream<R: Read>(
+    mut reader: R,
+) -> Result<Grib2<SeekableGrib2Reader<Cursor<Vec<u8>>>>, GribError> {
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf).map_err(ParseError::from)?;
+    let messages = message_ranges(&buf);
+    match messages.as_slice() {
+        [] => return from_reader(Cursor::new(buf)),
+        [range] if *range == (0..buf.len()) => return from_reader(Cursor::new(buf)),
+        _ => {}
+    }
+
+    // Sections are parsed in the data with padding removed, and then their
+    // offsets are mapped back to those in the stream.
+    let compacted = messages
+        .iter()
+        .flat_map(|range| buf[range.clone()].iter().copied())
+        .collect();
+    let mut grib2 = from_reader(Cursor::new(compacted))?;
+    for sect in grib2.sections.iter_mut() {
+        sect.offset = offset_in_stream(&messages, sect.offset);
+    }
+    grib2.reader = RefCell::new(SeekableGrib2Reader::new(Cursor::new(buf)));
+    Ok(grib2)
+}
+
+/// Converts an offset in the concatenation of `ranges` into the offset in
+/// the data from which the ranges are taken.
+fn offset_in_stream(ranges: &[Range<usize>], offset: usize) -> usize {
+    let mut start_in_concatenation = 0;
+    for range in ranges {
+        if offset < start_in_concatenation + range.len() {
+            return range.start + offset - start_in_concatenation;
+        }
+        start_in_concatenation += range.len();
+    }
+    offset
+}
+
+/// Number of values written at a time by [`Grib2::decode_to_writer`].
+pub const DECODE_TO_WRITER_BATCH_SIZE: usize = 4096;
+
+/// Maximum number of octets before "GRIB" skipped by [`skip_to_grib`].
+pub const SKIP_TO_GRIB_SCAN_LIMIT: usize = 65536;
+
+/// Reads GRIB2 data from a reader like [`from_stream`], skipping octets
+/// before the first "GRIB", such as HTTP headers, byte order marks or other
+/// headers prepended by delivery systems.
+///
+/// "GRIB" is searched for in the first [`SKIP_TO_GRIB_SCAN_LIMIT`] octets,
+/// and [`ParseError::GribMagicNotFound`] is returned if it is not found.
+/// Offsets of sections are reported relative to the start of "GRIB".
+pub fn skip_to_grib<R: Read>(
+    mut reader: R,
+) -> Result<Grib2<SeekableGrib2Reader<Cursor<Vec<u8>>>>, GribError> {
+    let mut buf = Vec::new();
+    reader
+        .by_ref()
+        .take((SKIP_TO_GRIB_SCAN_LIMIT + 4) as u64)
+        .read_to_end(&mut buf)
+        .map_err(ParseError::from)?;
+    let start = buf
+        .windows(4)
+        .position(|window| window == b"GRIB")
+        .ok_or(ParseError::GribMagicNotFound)?;
+    buf.drain(..start);
+    reader.read_to_end(&mut buf).map_err(ParseError::from)?;
+    from_stream(Cursor::new(buf))
+}
+
+/// Returns ranges of consecutive messages in `bytes` separated by padding
+/// with zeros or ASCII whitespace. A message extending beyond the end of
+/// `bytes` is included as it is, so that it is reported when parsed.
+fn message_ranges(bytes: &[u8]) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut pos = 0;
+    while let Some(offset) = bytes[pos..]
+        .iter()
+        .position(|b| *b != 0 && !b.is_ascii_whitespace())
+    {
+        let start = pos + offset;
+        let total_length = match bytes.get(start + 8..start + 16) {
+            Some(octets) if bytes[start..].starts_with(b"GRIB") => {
+                u64::from_be_bytes(octets.try_into().unwrap())
+            }
+            _ => break,
+        };
+        let end = usize::try_from(total_length)
+            .ok()
+            .and_then(|len| start.checked_add(len))
+            .filter(|end| *end > start && *end <= bytes.len());
+        match end {
+            Some(end) => {
+                ranges.push(start..end);
+                pos = end;
+            }
+            None => {
+                ranges.push(start..bytes.len());
+                break;
+            }
+        }
+    }
+    ranges
+}
+
+/// Framing around GRIB2 messages used in some real-time dissemination
+/// systems.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FrameFormat {
+    /// Each frame starts with its length in octets, excluding the prefix
+    /// itself, as a 4-octet big-endian integer.
+    LengthPrefixU32Be,
+}
+
+/// Reads GRIB2 data wrapped in frames of the format `frame`.
+///
+/// Frames are read until the end of the stream and their contents are
+/// concatenated in memory after stripping the framing, as in
+/// [`from_stream`]. Offsets of sections are those in the concatenated
+/// contents, not in the framed stream.
+pub fn from_framed_reader<R: Read>(
+    mut reader: R,
+    frame: FrameFormat,
+) -> Result<Grib2<SeekableGrib2Reader<Cursor<Vec<u8>>>>, GribError> {
+    let mut buf = Vec::new();
+    match frame {
+        FrameFormat::LengthPrefixU32Be => loop {
+            let mut prefix = [0; 4];
+            let size = reader.read(&mut prefix).map_err(ParseError::from)?;
+            if size == 0 {
+                break;
+            }
+            reader
+                .read_exact(&mut prefix[size..])
+                .map_err(ParseError::from)?;
+            let len = u64::from(u32::from_be_bytes(prefix));
+            let start = buf.len();
+            reader
+                .by_ref()
+                .take(len)
+                .read_to_end(&mut buf)
+                .map_err(ParseError::from)?;
+            if (buf.len() - start) as u64 != len {
+                return Err(ParseError::UnexpectedEndOfData(buf.len()).into());
+            }
+        },
+    }
+    from_reader(Cursor::new(buf))
+}
+
+/// Data of a single GRIB2 message parsed from a byte slice, returned by
+/// [`parse_message`].
+pub type ParsedMessage<'a> = Grib2<SeekableGrib2Reader<Cursor<&'a [u8]>>>;
+
+/// Parses the first GRIB2 message in `bytes`.
+///
+/// Unlike [`from_slice`], octets following the end of the first message are
+/// ignored. This function is designed to return an error rather than panic on
+/// arbitrary input, so that it can be used as an entry point for fuzzing.
+/// Values can be decoded against the same slice using
+/// [`Grib2::get_values`].
+pub fn parse_message(bytes: &[u8]) -> Result<ParsedMessage<'_>, GribError> {
+    let message = match bytes.get(8..16) {
+        Some(octets) if bytes.starts_with(b"GRIB") => {
+            let total_length = u64::from_be_bytes(octets.try_into().unwrap());
+            let total_length = usize::try_from(total_length).unwrap_or(usize::MAX);
+            &bytes[..total_length.min(bytes.len())]
+        }
+        _ => bytes,
+    };
+    from_slice(message)
+}
+
+/// Known pairings of templates which cannot appear together in a submessage:
+/// a template, and the range of the other template numbers it requires.
+const TEMPLATE_REQUIREMENTS: [(TemplateInfo, u8, Range<u16>); 11] = [
+    // spectral data need a spherical harmonic grid, and vice versa
+    (TemplateInfo(5, 50), 3, 50..54),
+    (TemplateInfo(5, 51), 3, 50..54),
+    (TemplateInfo(3, 50), 5, 50..52),
+    (TemplateInfo(3, 51), 5, 50..52),
+    (TemplateInfo(3, 52), 5, 50..52),
+    (TemplateInfo(3, 53), 5, 50..52),
+    // cross-section and Hovmoller diagram products need grids of their own
+    (TemplateInfo(4, 1000), 3, 1000..1001),
+    (TemplateInfo(4, 1001), 3, 1000..1001),
+    (TemplateInfo(4, 1002), 3, 1000..1001),
+    (TemplateInfo(4, 1100), 3, 1100..1101),
+    (TemplateInfo(4, 1101), 3, 1100..1101),
+];
+
+/// Checks that the templates of Sections 3, 4 and 5 of `sub` are not a known
+/// incompatible combination, such as spectral data on a grid of points,
+/// which indicates a corrupted or mis-tagged submessage.
+pub fn validate_submessage(sub: &SubMessage) -> Result<(), ParseError> {
+    let templates = [
+        TemplateInfo(3, sub.grid_def().grid_tmpl_num()),
+        TemplateInfo(4, sub.prod_def().prod_tmpl_num()),
+        TemplateInfo(5, sub.repr_def().repr_tmpl_num()),
+    ];
+    for (required_by, sect_num, range) in TEMPLATE_REQUIREMENTS.iter() {
+        if !templates.contains(required_by) {
+            continue;
+        }
+        if let Some(found) = templates
+            .iter()
+            .find(|tmpl| tmpl.0 == *sect_num && !range.contains(&tmpl.1))
+        {
+            return Err(ParseError::IncompatibleTemplates(
+                required_by.clone(),
+                found.clone(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Checks grid definitions in `sections` with [`Grid::validate`], skipping
+/// those whose templates are not supported.
+fn validate_grids(sections: &[SectionInfo]) -> Result<(), GribError> {
+    for sect in sections {
+        if let Some(SectionBody::Section3(grid_def)) = &sect.body {
+            match Grid::from_grid_def(grid_def) {
+                Ok(grid) => grid.validate()?,
+                Err(GribError::Unsupported(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+    Ok(())
+}
+
 pub struct Grib2<R> {
     pub(crate) reader: RefCell<R>,
     pub(crate) sections: Box<[SectionInfo]>,
@@ -99,10 +595,19 @@ pub struct Grib2<R> {
 
 impl<R: Grib2Read> Grib2<R> {
     pub fn read(r: R) -> Result<Self, GribError> {
-        let mut sect_stream = Grib2SectionStream::new(r);
-        let mut cacher = Vec::new();
-        let parser = Grib2SubmessageIndexStream::new(sect_stream.by_ref()).with_cacher(&mut cacher);
-        let submessages = parser.collect::<Result<Vec<_>, _>>()?;
+        Self::read_with_options(r, &ReadOptions::default())
+    }
+
+    pub fn read_with_options(r: R, opts: &ReadOptions) -> Result<Self, GribError> {
+        let mut sect_stream = Grib2SectionStream::new(r)
+            .with_checksums(opts.compute_checksums)
+            .with_max_message_size(opts.max_message_size);
+        let mut parser = Grib2SubmessageIndexStream::new(sect_stream.by_ref()).with_cacher();
+        let submessages = parser.by_ref().collect::<Result<Vec<_>, _>>()?;
+        let sections = parser.into_cached_sections();
+        if opts.validate_grids {
+            validate_grids(&sections)?;
+        }
         // tentatively extract only submessages in the first message
         let submessages = submessages
             .into_iter()
@@ -110,7 +615,7 @@ impl<R: Grib2Read> Grib2<R> {
             .collect::<Vec<_>>();
         Ok(Self {
             reader: RefCell::new(sect_stream.into_reader()),
-            sections: cacher.into_boxed_slice(),
+            sections: sections.into_boxed_slice(),
             submessages,
         })
     }
@@ -123,7 +628,7 @@ impl<R: Grib2Read> Grib2<R> {
     }
 
     pub fn info(&self) -> Result<(&Indicator, &Identification), GribError> {
-        match (self.sections.get(0), self.sections.get(1)) {
+        match (self.sections.first(), self.sections.get(1)) {
             (
                 Some(SectionInfo {
                     body: Some(SectionBody::Section0(sect0_body)),
@@ -138,44 +643,424 @@ impl<R: Grib2Read> Grib2<R> {
         }
     }
 
+    /// Iterates over headers of messages, which are read from Section 0 and
+    /// Section 1 without touching the other sections.
+    pub fn headers(&self) -> impl Iterator<Item = MessageHeader<'_>> {
+        self.sections.windows(2).filter_map(|pair| match pair {
+            [SectionInfo {
+                body: Some(SectionBody::Section0(indicator)),
+                ..
+            }, SectionInfo {
+                body: Some(SectionBody::Section1(identification)),
+                ..
+            }] => Some(MessageHeader {
+                edition: 2,
+                discipline: indicator.discipline,
+                identification,
+            }),
+            _ => None,
+        })
+    }
+
+    /// Returns the discipline (see Code Table 0.0) read from Section 0 of the
+    /// first message.
+    pub fn discipline(&self) -> Result<u8, GribError> {
+        let (indicator, _) = self.info()?;
+        Ok(indicator.discipline)
+    }
+
+    /// Returns the discipline like [`Grib2::discipline`] as a [`Discipline`].
+    pub fn discipline_enum(&self) -> Result<Discipline, GribError> {
+        self.discipline().map(Discipline::from_u8)
+    }
+
     /// Iterates over submessages.
     #[inline]
-    pub fn iter(&self) -> SubmessageIterator {
+    pub fn iter(&self) -> SubmessageIterator<'_> {
         self.submessages()
     }
 
-    pub fn submessages(&self) -> SubmessageIterator {
+    pub fn submessages(&self) -> SubmessageIterator<'_> {
         SubmessageIterator::new(&self.submessages, &self.sections)
     }
 
+    /// Consumes `self` and returns the reader along with submessages which
+    /// own their section data, so that the reader can be moved elsewhere,
+    /// e.g. to another thread for decoding, while the submessages are kept
+    /// for inspecting metadata.
+    ///
+    /// Values of the submessages can be decoded with the returned reader
+    /// using [`OwnedSubMessage::decode`].
+    pub fn into_submessages_owned(self) -> (R, Box<[OwnedSubMessage]>) {
+        let Self {
+            reader,
+            sections,
+            submessages,
+        } = self;
+        let owned = OwnedSubmessageIterator {
+            indices: submessages.into_iter(),
+            sections,
+        };
+        (reader.into_inner(), owned.collect())
+    }
+
     /// Decodes grid values of a surface specified by the index `i`.
     pub fn get_values(&self, i: usize) -> Result<Box<[f32]>, GribError> {
-        let (sect5, sect6, sect7) = self
+        self.get_values_with_options(i, &DecodeOptions::default())
+    }
+
+    /// Decodes grid values of all surfaces.
+    ///
+    /// Decoding errors are wrapped in
+    /// [`DecodeError::SubmessageDecodeError`](decoders::DecodeError::SubmessageDecodeError)
+    /// with the index of the submessage which failed to be decoded.
+    pub fn decode_all(&self) -> Result<Vec<Box<[f32]>>, GribError> {
+        (0..self.submessages.len())
+            .map(|i| self.get_values(i).map_err(|e| with_submessage_index(e, i)))
+            .collect()
+    }
+
+    /// Decodes grid values of a surface specified by the index `i` with
+    /// options such as the value filled at grid points without valid data.
+    pub fn get_values_with_options(
+        &self,
+        i: usize,
+        opts: &DecodeOptions,
+    ) -> Result<Box<[f32]>, GribError> {
+        let (num_grid_points, sect5, sect6, sect7) = self.decoding_sections(i)?;
+
+        let reader = self.reader.borrow_mut();
+        decoders::decode_on_grid(sect5, sect6, sect7, reader, opts, num_grid_points)
+    }
+
+    /// Decodes grid values of a surface specified by the index `i` into
+    /// `out`, which must have the same length as the number of grid points.
+    ///
+    /// Reusing one buffer avoids allocating memory for each of many fields
+    /// of the same size. See [`decoders::dispatch_into`] for details.
+    pub fn decode_into(&self, i: usize, out: &mut [f32]) -> Result<(), GribError> {
+        self.decode_into_with_options(i, out, &DecodeOptions::default())
+    }
+
+    /// Decodes grid values like [`Grib2::decode_into`] with options such as
+    /// the size of blocks in which Section 7 is read.
+    pub fn decode_into_with_options(
+        &self,
+        i: usize,
+        out: &mut [f32],
+        opts: &DecodeOptions,
+    ) -> Result<(), GribError> {
+        let (num_grid_points, sect5, sect6, sect7) = self.decoding_sections(i)?;
+
+        decoders::check_output_len(out, num_grid_points)?;
+        let reader = self.reader.borrow_mut();
+        decoders::decode_on_grid_into(sect5, sect6, sect7, reader, opts, out)
+    }
+
+    /// Decodes spherical harmonic coefficients of a spectral field specified
+    /// by the index `i`, with the truncation read from Section 3.
+    ///
+    /// See [`decoders::decode_spectral`] for the order of values. Transform
+    /// of coefficients into grid point values is not supported.
+    pub fn spectral_coefficients(&self, i: usize) -> Result<Box<[f32]>, GribError> {
+        let submessage = self
+            .submessages()
+            .nth(i)
+            .ok_or(GribError::InternalDataError)?;
+        let truncation = SpectralTruncation::from_grid_def(submessage.grid_def())?;
+        let (_, sect5, sect6, sect7) = self.decoding_sections(i)?;
+
+        let reader = self.reader.borrow_mut();
+        decoders::decode_spectral(&truncation, sect5, sect6, sect7, reader)
+    }
+
+    /// Returns the number of grid points and Sections 5, 6 and 7 of the
+    /// submessage specified by the index `i`.
+    fn decoding_sections(
+        &self,
+        i: usize,
+    ) -> Result<(usize, &SectionInfo, &SectionInfo, &SectionInfo), GribError> {
+        let submsg = self
+            .submessages
+            .get(i)
+            .ok_or(GribError::InternalDataError)?;
+        let get = |index| self.sections.get(index).ok_or(GribError::InternalDataError);
+        let num_grid_points = match &get(submsg.sections.3)?.body {
+            Some(SectionBody::Section3(body)) => body.num_points() as usize,
+            _ => return Err(GribError::InternalDataError),
+        };
+        Ok((
+            num_grid_points,
+            get(submsg.sections.5)?,
+            get(submsg.sections.6)?,
+            get(submsg.sections.7)?,
+        ))
+    }
+
+    /// Decodes grid values of a surface specified by the index `i` along with
+    /// the grid and other metadata.
+    pub fn decode_field(&self, i: usize, opts: &DecodeOptions) -> Result<DecodedField, GribError> {
+        let submessage = self
+            .submessages()
+            .nth(i)
+            .ok_or(GribError::InternalDataError)?;
+        let data = self.get_values_with_options(i, opts)?;
+        Ok(DecodedField {
+            data,
+            grid: submessage.grid()?,
+            ref_time: submessage.identification().ref_time(),
+            valid_time: submessage.valid_time(),
+            parameter: submessage.parameter(),
+            fill_value: opts.fill_value,
+        })
+    }
+
+    /// Decodes grid values of a surface specified by the index `i` and returns
+    /// them along with latitudes and longitudes of grid points.
+    ///
+    /// Grid points without valid data are excluded.
+    pub fn decode_with_coordinates(&self, i: usize) -> Result<Vec<GridValue>, GribError> {
+        let field = self.decode_field(i, &DecodeOptions::default())?;
+        let values = field
+            .grid
+            .latlons()
+            .into_iter()
+            .zip(field.data.iter())
+            .filter(|(_, value)| !value.is_nan())
+            .map(|((lat, lon), value)| (f64::from(lat), f64::from(lon), *value))
+            .collect();
+        Ok(values)
+    }
+
+    /// Returns values and coordinates at the four corners of the grid of a
+    /// surface specified by the index `i`, which is a quick check of the
+    /// orientation of decoded values.
+    ///
+    /// Quasi-regular grids are not supported.
+    pub fn corner_values(&self, i: usize) -> Result<CornerValues, GribError> {
+        let field = self.decode_field(i, &DecodeOptions::default())?;
+        let (ni, nj) = field.grid.regular_shape().ok_or_else(|| {
+            GribError::Unsupported("corner values of quasi-regular grids".to_owned())
+        })?;
+        if ni == 0 || nj == 0 {
+            return Err(GribError::GridError(GridError::EmptyGrid));
+        }
+        let latlons = field.grid.latlons();
+        let corner = |i, j| -> Result<GridValue, GribError> {
+            let index = field
+                .grid
+                .linear_index(i, j)
+                .ok_or(GribError::InternalDataError)?;
+            let (lat, lon) = latlons.get(index).ok_or(GribError::InternalDataError)?;
+            let value = field.data.get(index).ok_or(GribError::InternalDataError)?;
+            Ok((f64::from(*lat), f64::from(*lon), *value))
+        };
+        Ok(CornerValues {
+            first: corner(0, 0)?,
+            first_row_last: corner(ni - 1, 0)?,
+            last_row_first: corner(0, nj - 1)?,
+            last: corner(ni - 1, nj - 1)?,
+        })
+    }
+
+    /// Returns the mean of grid values of a surface specified by the index
+    /// `i`, weighted by [`Grid::area_weights`].
+    ///
+    /// Grid points without valid data are excluded. `NaN` is returned if
+    /// there are no valid data.
+    pub fn weighted_mean(&self, i: usize) -> Result<f32, GribError> {
+        let field = self.decode_field(i, &DecodeOptions::default())?;
+        let (sum, weight_sum) = field
+            .grid
+            .area_weights()
+            .into_iter()
+            .zip(field.data.iter())
+            .filter(|(_, value)| !value.is_nan())
+            .fold((0_f64, 0_f64), |(sum, weight_sum), (weight, value)| {
+                let weight = f64::from(weight);
+                (sum + weight * f64::from(*value), weight_sum + weight)
+            });
+        Ok((sum / weight_sum) as f32)
+    }
+
+    /// Returns the packed integers of a surface specified by the index `i`
+    /// without converting them into floating-point values.
+    ///
+    /// See [`decoders::decode_raw_integers`] for supported templates.
+    pub fn get_raw_integers(&self, i: usize) -> Result<Box<[u32]>, GribError> {
+        let (sect5, sect7) = self
             .submessages
             .get(i)
             .and_then(|submsg| {
                 Some((
                     self.sections.get(submsg.sections.5)?,
-                    self.sections.get(submsg.sections.6)?,
                     self.sections.get(submsg.sections.7)?,
                 ))
             })
             .ok_or(GribError::InternalDataError)?;
 
         let reader = self.reader.borrow_mut();
-        let values = decoders::dispatch(sect5, sect6, sect7, reader)?;
-        Ok(values)
+        decoders::decode_raw_integers(sect5, sect7, reader)
+    }
+
+    /// Decodes grid values of a surface specified by the index `i` and writes
+    /// them to `writer` in `format` in batches of
+    /// [`DECODE_TO_WRITER_BATCH_SIZE`] values.
+    ///
+    /// Values packed with simple packing (Template 5.0) are decoded while
+    /// Section 7 is read in blocks, so that the whole field is never held in
+    /// memory. Values packed with the other templates are decoded as a whole
+    /// before being written.
+    pub fn decode_to_writer<W: Write>(
+        &self,
+        i: usize,
+        mut writer: W,
+        format: OutputFormat,
+    ) -> Result<(), GribError> {
+        let (num_grid_points, sect5, sect6, sect7) = self.decoding_sections(i)?;
+
+        let reader = self.reader.borrow_mut();
+        let mut write_batch = |values: &[f32]| {
+            values
+                .iter()
+                .try_for_each(|value| match format {
+                    OutputFormat::F32Le => writer.write_all(&value.to_le_bytes()),
+                    OutputFormat::F32Be => writer.write_all(&value.to_be_bytes()),
+                    OutputFormat::Text => writeln!(writer, "{}", value),
+                })
+                .map_err(|e| GribError::WriteError(e.to_string()))
+        };
+        decoders::decode_on_grid_in_batches(
+            sect5,
+            sect6,
+            sect7,
+            reader,
+            &DecodeOptions::default(),
+            num_grid_points,
+            DECODE_TO_WRITER_BATCH_SIZE,
+            &mut write_batch,
+        )?;
+        writer
+            .flush()
+            .map_err(|e| GribError::WriteError(e.to_string()))
     }
 
+    /// Returns all the sections read, in the order of appearance, which is
+    /// useful for debugging and iterating over sections in custom ways.
     pub fn sections(&self) -> &[SectionInfo] {
         &self.sections
     }
 
+    /// Returns the section at the index `i` in [`Grib2::sections`], or `None`
+    /// if the index is out of range.
+    pub fn section_by_index(&self, i: usize) -> Option<&SectionInfo> {
+        self.sections.get(i)
+    }
+
+    /// Decodes submessages for which `select` returns `true` and stacks their
+    /// values into a three-dimensional array with the shape `(n, Nj, Ni)`,
+    /// e.g. to put all vertical levels of a parameter together.
+    ///
+    /// All the selected submessages must be on the same grid; otherwise
+    /// [`GribError::GridMismatchError`] is returned.
+    /// [`GribError::InvalidArgument`] is returned if no submessages are
+    /// selected.
+    pub fn stack_by<F>(&self, mut select: F) -> Result<FieldStack, GribError>
+    where
+        F: FnMut(&SubMessage<'_>) -> bool,
+    {
+        let selected = self
+            .submessages()
+            .enumerate()
+            .filter(|(_, submessage)| select(submessage))
+            .collect::<Vec<_>>();
+        let (_, first) = selected
+            .first()
+            .ok_or_else(|| GribError::InvalidArgument("no submessages are selected".to_owned()))?;
+        if !selected.iter().all(|(_, s)| s.is_same_grid_as(first)) {
+            return Err(GribError::GridMismatchError);
+        }
+        let grid = first.grid()?;
+        let layers = selected
+            .iter()
+            .map(|(i, _)| self.get_values(*i))
+            .collect::<Result<Vec<_>, _>>()?;
+        FieldStack::from_layers(grid, &layers)
+    }
+
+    /// Returns valid times of submessages for which `select` returns `true`,
+    /// sorted and without duplicates, e.g. as frames of an animation of a
+    /// parameter at a level.
+    ///
+    /// Submessages whose valid times are not available are skipped.
+    pub fn time_steps<F>(&self, mut select: F) -> Vec<DateTime<Utc>>
+    where
+        F: FnMut(&SubMessage<'_>) -> bool,
+    {
+        let mut times = self
+            .submessages()
+            .filter(|submessage| select(submessage))
+            .filter_map(|submessage| submessage.valid_time())
+            .collect::<Vec<_>>();
+        times.sort();
+        times.dedup();
+        times
+    }
+
+    /// Iterates over submessages of the individual ensemble forecast with the
+    /// perturbation number `number`, along with their indices.
+    pub fn filter_by_ensemble(&self, number: u8) -> impl Iterator<Item = (usize, SubMessage<'_>)> {
+        self.submessages()
+            .enumerate()
+            .filter(move |(_, submessage)| submessage.ensemble_number() == Some(number))
+    }
+
+    /// Iterates over submessages whose forecast valid time (see
+    /// [`SubMessage::valid_time`]) differs from `valid_time` by at most
+    /// `tolerance_secs` seconds, along with their indices.
+    pub fn filter_by_valid_time(
+        &self,
+        valid_time: DateTime<Utc>,
+        tolerance_secs: u64,
+    ) -> impl Iterator<Item = (usize, SubMessage<'_>)> {
+        self.submessages()
+            .enumerate()
+            .filter(move |(_, submessage)| {
+                submessage.valid_time().is_some_and(|time| {
+                    (time - valid_time).num_seconds().unsigned_abs() <= tolerance_secs
+                })
+            })
+    }
+
+    /// Returns a summary of each submessage, which is roughly equivalent to
+    /// the output of `wgrib2 -s`.
+    pub fn inventory(&self) -> Vec<InventoryEntry> {
+        self.submessages
+            .iter()
+            .zip(self.submessages())
+            .map(|(index, submessage)| submessage.inventory_entry(index))
+            .collect()
+    }
+
     pub fn list_templates(&self) -> Vec<TemplateInfo> {
         get_templates(&self.sections)
     }
 }
 
+impl<R> IntoIterator for Grib2<R> {
+    type Item = OwnedSubMessage;
+    type IntoIter = OwnedSubmessageIterator;
+
+    /// Consumes the reader and iterates over submessages which own their
+    /// section data and can therefore be moved to other threads.
+    fn into_iter(self) -> Self::IntoIter {
+        OwnedSubmessageIterator {
+            indices: self.submessages.into_iter(),
+            sections: self.sections,
+        }
+    }
+}
+
 fn get_templates(sects: &[SectionInfo]) -> Vec<TemplateInfo> {
     let uniq: HashSet<_> = sects.iter().filter_map(|s| s.get_tmpl_code()).collect();
     let mut vec: Vec<_> = uniq.into_iter().collect();
@@ -251,7 +1136,7 @@ pub struct SubMessage<'a>(
 );
 
 impl<'a> SubMessage<'a> {
-    pub fn indicator(&self) -> &Indicator {
+    pub fn indicator(&self) -> &'a Indicator {
         // panics should not happen if data is correct
         match self.0.body.body.as_ref().unwrap() {
             SectionBody::Section0(data) => data,
@@ -259,7 +1144,23 @@ impl<'a> SubMessage<'a> {
         }
     }
 
-    pub fn prod_def(&self) -> &ProdDefinition {
+    pub fn identification(&self) -> &'a Identification {
+        // panics should not happen if data is correct
+        match self.1.body.body.as_ref().unwrap() {
+            SectionBody::Section1(data) => data,
+            _ => panic!("something unexpected happened"),
+        }
+    }
+
+    pub fn grid_def(&self) -> &'a GridDefinition {
+        // panics should not happen if data is correct
+        match self.3.body.body.as_ref().unwrap() {
+            SectionBody::Section3(data) => data,
+            _ => panic!("something unexpected happened"),
+        }
+    }
+
+    pub fn prod_def(&self) -> &'a ProdDefinition {
         // panics should not happen if data is correct
         match self.4.body.body.as_ref().unwrap() {
             SectionBody::Section4(data) => data,
@@ -267,6 +1168,153 @@ impl<'a> SubMessage<'a> {
         }
     }
 
+    pub fn repr_def(&self) -> &'a ReprDefinition {
+        // panics should not happen if data is correct
+        match self.5.body.body.as_ref().unwrap() {
+            SectionBody::Section5(data) => data,
+            _ => panic!("something unexpected happened"),
+        }
+    }
+
+    /// Returns the geometry of the grid defined in Section 3.
+    pub fn grid(&self) -> Result<Grid, GribError> {
+        Grid::from_grid_def(self.grid_def())
+    }
+
+    /// Returns the perturbation number of the individual ensemble forecast.
+    pub fn ensemble_number(&self) -> Option<u8> {
+        self.prod_def()
+            .ensemble()
+            .map(|ens| ens.perturbation_number)
+    }
+
+    /// Returns the number of forecasts in the ensemble.
+    pub fn ensemble_size(&self) -> Option<u8> {
+        self.prod_def()
+            .ensemble()
+            .map(|ens| ens.number_of_forecasts)
+    }
+
+    /// Returns the percentile value of a percentile forecast (Templates 4.6
+    /// and 4.10).
+    pub fn percentile(&self) -> Option<u8> {
+        self.prod_def().percentile()
+    }
+
+    /// Checks if the grid of this submessage is identical to the one of
+    /// `other`, which is required in operations combining values of
+    /// submessages.
+    pub fn is_same_grid_as(&self, other: &SubMessage) -> bool {
+        is_same_grid(self.grid_def(), other.grid_def())
+    }
+
+    /// Returns the forecast valid time, which is the reference time plus the
+    /// forecast time. For statistically processed products, this is the start
+    /// of the time range.
+    pub fn valid_time(&self) -> Option<DateTime<Utc>> {
+        let ref_time = self.identification().ref_time()?;
+        self.prod_def().forecast_time()?.add_to(ref_time)
+    }
+
+    /// Returns `true` if data is compressed with a general-purpose codec such
+    /// as JPEG 2000. See [`representation_is_compressed`].
+    pub fn is_compressed(&self) -> bool {
+        representation_is_compressed(self.repr_def().repr_tmpl_num())
+    }
+
+    /// Returns the type and identifiers of the generating process, or `None`
+    /// if the product definition template does not have them.
+    pub fn generating_process(&self) -> Option<GeneratingProcess> {
+        self.prod_def().generating_process_info()
+    }
+
+    /// Returns the parameter, or `None` if the product definition template
+    /// does not have one.
+    pub fn parameter(&self) -> Option<ParameterInfo> {
+        let prod_def = self.prod_def();
+        Some(ParameterInfo {
+            discipline: self.indicator().discipline,
+            category: prod_def.parameter_category()?,
+            number: prod_def.parameter_number()?,
+        })
+    }
+
+    /// Returns a summary of the contents of this submessage, which can be
+    /// used to find identical fields across files regardless of where
+    /// sections are located.
+    pub fn content_key(&self) -> ContentKey {
+        fn hash_of<'b>(iter: impl Iterator<Item = &'b u8>) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            iter.for_each(|b| b.hash(&mut hasher));
+            hasher.finish()
+        }
+
+        ContentKey {
+            discipline: self.indicator().discipline,
+            grid_tmpl_num: self.grid_def().grid_tmpl_num(),
+            grid_hash: hash_of(self.grid_def().iter()),
+            prod_tmpl_num: self.prod_def().prod_tmpl_num(),
+            prod_hash: hash_of(self.prod_def().iter()),
+            repr_tmpl_num: self.repr_def().repr_tmpl_num(),
+        }
+    }
+
+    /// Returns byte ranges of Sections 1 to 7 of this submessage in the
+    /// source, keyed by section number. Section 2 is included only if
+    /// present. Sections 1 and 2 may be shared with other submessages.
+    pub fn section_offsets(&self) -> HashMap<u8, Range<u64>> {
+        [
+            Some(&self.1),
+            self.2.as_ref(),
+            Some(&self.3),
+            Some(&self.4),
+            Some(&self.5),
+            Some(&self.6),
+            Some(&self.7),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|sect| {
+            let start = sect.body.offset as u64;
+            (sect.body.num, start..(start + sect.body.size as u64))
+        })
+        .collect()
+    }
+
+    fn inventory_entry(&self, index: &Grib2SubmessageIndex) -> InventoryEntry {
+        let prod_def = self.prod_def();
+        let parameter = self
+            .parameter()
+            .map(|parameter| parameter.describe())
+            .unwrap_or_default();
+        let level = prod_def
+            .fixed_surfaces()
+            .map(|(first, _)| {
+                let (stype, _, _) = first.describe();
+                if first.value_is_nan() {
+                    stype
+                } else {
+                    format!("{} {}", stype, first.value())
+                }
+            })
+            .unwrap_or_default();
+        let forecast_time = prod_def
+            .forecast_time()
+            .map(|ft| ft.to_string())
+            .unwrap_or_default();
+
+        InventoryEntry {
+            message: index.message,
+            submessage: index.submessage,
+            offset: self.0.body.offset,
+            parameter,
+            level,
+            ref_time: self.identification().ref_time(),
+            forecast_time,
+            grid_tmpl_num: self.grid_def().grid_tmpl_num(),
+        }
+    }
+
     pub fn describe(&self) -> String {
         let category = self.prod_def().parameter_category();
         let forecast_time = self
@@ -336,41 +1384,324 @@ Data Representation:                    {}
     }
 }
 
-pub struct SubMessageSection<'a> {
-    pub index: usize,
-    pub body: &'a SectionInfo,
+/// Content-based summary of a submessage returned by
+/// [`SubMessage::content_key`].
+///
+/// Hashes are computed from the bodies of the Grid Definition Section and
+/// the Product Definition Section. They are only comparable within the same
+/// build of the program, and should not be persisted.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ContentKey {
+    pub discipline: u8,
+    pub grid_tmpl_num: u16,
+    pub grid_hash: u64,
+    pub prod_tmpl_num: u16,
+    pub prod_hash: u64,
+    pub repr_tmpl_num: u16,
 }
 
-impl<'a> SubMessageSection<'a> {
-    pub fn new(index: usize, body: &'a SectionInfo) -> Self {
-        Self { index, body }
-    }
-
-    pub fn template_code(&self) -> Option<TemplateInfo> {
-        self.body.get_tmpl_code()
-    }
+/// Iterator over [`OwnedSubMessage`]s created by consuming [`Grib2`].
+pub struct OwnedSubmessageIterator {
+    indices: std::vec::IntoIter<Grib2SubmessageIndex>,
+    sections: Box<[SectionInfo]>,
+}
 
-    pub fn describe(&self) -> Option<String> {
-        self.template_code().and_then(|code| code.describe())
+impl OwnedSubmessageIterator {
+    fn new_submessage_section(&self, index: usize) -> Option<OwnedSubMessageSection> {
+        Some(OwnedSubMessageSection {
+            index,
+            body: self.sections.get(index)?.clone(),
+        })
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+impl Iterator for OwnedSubmessageIterator {
+    type Item = OwnedSubMessage;
 
-    use std::fs::File;
-    use std::io::BufReader;
+    fn next(&mut self) -> Option<OwnedSubMessage> {
+        let submessage_index = self.indices.next()?;
 
-    macro_rules! sect_placeholder {
-        ($num:expr) => {{
-            SectionInfo {
-                num: $num,
-                offset: 0,
-                size: 0,
-                body: None,
-            }
-        }};
+        Some(OwnedSubMessage(
+            self.new_submessage_section(0)?,
+            self.new_submessage_section(1)?,
+            submessage_index
+                .sections
+                .2
+                .and_then(|i| self.new_submessage_section(i)),
+            self.new_submessage_section(submessage_index.sections.3)?,
+            self.new_submessage_section(submessage_index.sections.4)?,
+            self.new_submessage_section(submessage_index.sections.5)?,
+            self.new_submessage_section(submessage_index.sections.6)?,
+            self.new_submessage_section(submessage_index.sections.7)?,
+            self.new_submessage_section(self.sections.len() - 1)?,
+        ))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.indices.size_hint()
+    }
+}
+
+/// Iterator over [`OwnedSubMessage`]s and parse errors created by
+/// [`iter_submessages`].
+pub struct FallibleSubmessageIterator<R: Grib2Read> {
+    parser: Grib2SubmessageIndexStream<Grib2SectionStream<R>>,
+    pending: Vec<Grib2SubmessageIndex>,
+    ready: std::vec::IntoIter<OwnedSubMessage>,
+    // Number of sections of already completed messages, which have been
+    // removed from the cache of the parser
+    num_released_sections: usize,
+}
+
+impl<R: Grib2Read> Iterator for FallibleSubmessageIterator<R> {
+    type Item = Result<OwnedSubMessage, ParseError>;
+
+    fn next(&mut self) -> Option<Result<OwnedSubMessage, ParseError>> {
+        loop {
+            if let Some(submessage) = self.ready.next() {
+                return Some(Ok(submessage));
+            }
+
+            let submessage_index = match self.parser.next()? {
+                Ok(index) => index,
+                Err(e) => {
+                    self.pending.clear();
+                    return Some(Err(e));
+                }
+            };
+            // Section 8 is dummy (0) except in the last submessage of a message
+            let sect8 = submessage_index.sections.8;
+            self.pending.push(submessage_index);
+            if sect8 == 0 {
+                continue;
+            }
+
+            // Sections are not referred to across messages, so the cache is
+            // released here to keep memory usage independent of the file size.
+            let sections = self.parser.take_cached_sections();
+            let base = self.num_released_sections;
+            self.num_released_sections += sections.len();
+            let new_submessage_section = |index: usize| OwnedSubMessageSection {
+                index,
+                body: sections[index - base].clone(),
+            };
+            self.ready = self
+                .pending
+                .drain(..)
+                .map(|index| {
+                    OwnedSubMessage(
+                        new_submessage_section(index.sections.0),
+                        new_submessage_section(index.sections.1),
+                        index.sections.2.map(new_submessage_section),
+                        new_submessage_section(index.sections.3),
+                        new_submessage_section(index.sections.4),
+                        new_submessage_section(index.sections.5),
+                        new_submessage_section(index.sections.6),
+                        new_submessage_section(index.sections.7),
+                        new_submessage_section(sect8),
+                    )
+                })
+                .collect::<Vec<_>>()
+                .into_iter();
+        }
+    }
+}
+
+/// A submessage holding copies of its section data instead of references to
+/// [`Grib2`].
+///
+/// Section data do not include payloads of Section 7, so values are decoded
+/// with a reader of the same source supplied by the caller.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedSubMessage(
+    pub OwnedSubMessageSection,
+    pub OwnedSubMessageSection,
+    pub Option<OwnedSubMessageSection>,
+    pub OwnedSubMessageSection,
+    pub OwnedSubMessageSection,
+    pub OwnedSubMessageSection,
+    pub OwnedSubMessageSection,
+    pub OwnedSubMessageSection,
+    pub OwnedSubMessageSection,
+);
+
+impl OwnedSubMessage {
+    /// Borrows this submessage as a [`SubMessage`].
+    pub fn as_submessage(&self) -> SubMessage<'_> {
+        SubMessage(
+            self.0.as_section(),
+            self.1.as_section(),
+            self.2.as_ref().map(|s| s.as_section()),
+            self.3.as_section(),
+            self.4.as_section(),
+            self.5.as_section(),
+            self.6.as_section(),
+            self.7.as_section(),
+            self.8.as_section(),
+        )
+    }
+
+    pub fn indicator(&self) -> &Indicator {
+        self.as_submessage().indicator()
+    }
+
+    pub fn identification(&self) -> &Identification {
+        self.as_submessage().identification()
+    }
+
+    pub fn grid_def(&self) -> &GridDefinition {
+        self.as_submessage().grid_def()
+    }
+
+    pub fn prod_def(&self) -> &ProdDefinition {
+        self.as_submessage().prod_def()
+    }
+
+    pub fn repr_def(&self) -> &ReprDefinition {
+        self.as_submessage().repr_def()
+    }
+
+    /// See [`SubMessage::grid`].
+    pub fn grid(&self) -> Result<Grid, GribError> {
+        self.as_submessage().grid()
+    }
+
+    /// See [`SubMessage::ensemble_number`].
+    pub fn ensemble_number(&self) -> Option<u8> {
+        self.as_submessage().ensemble_number()
+    }
+
+    /// See [`SubMessage::ensemble_size`].
+    pub fn ensemble_size(&self) -> Option<u8> {
+        self.as_submessage().ensemble_size()
+    }
+
+    /// See [`SubMessage::percentile`].
+    pub fn percentile(&self) -> Option<u8> {
+        self.as_submessage().percentile()
+    }
+
+    /// See [`SubMessage::valid_time`].
+    pub fn valid_time(&self) -> Option<DateTime<Utc>> {
+        self.as_submessage().valid_time()
+    }
+
+    /// See [`SubMessage::is_compressed`].
+    pub fn is_compressed(&self) -> bool {
+        self.as_submessage().is_compressed()
+    }
+
+    /// See [`SubMessage::generating_process`].
+    pub fn generating_process(&self) -> Option<GeneratingProcess> {
+        self.as_submessage().generating_process()
+    }
+
+    /// See [`SubMessage::content_key`].
+    pub fn content_key(&self) -> ContentKey {
+        self.as_submessage().content_key()
+    }
+
+    /// See [`SubMessage::parameter`].
+    pub fn parameter(&self) -> Option<ParameterInfo> {
+        self.as_submessage().parameter()
+    }
+
+    /// See [`SubMessage::section_offsets`].
+    pub fn section_offsets(&self) -> HashMap<u8, Range<u64>> {
+        self.as_submessage().section_offsets()
+    }
+
+    pub fn describe(&self) -> String {
+        self.as_submessage().describe()
+    }
+
+    /// Decodes grid values with `reader`, which must read the same source as
+    /// the one this submessage was read from.
+    pub fn decode<R2: Read + Seek>(&self, reader: &mut R2) -> Result<Box<[f32]>, GribError> {
+        self.decode_with_options(reader, &DecodeOptions::default())
+    }
+
+    /// Decodes grid values like [`OwnedSubMessage::decode`] with options.
+    pub fn decode_with_options<R2: Read + Seek>(
+        &self,
+        reader: &mut R2,
+        opts: &DecodeOptions,
+    ) -> Result<Box<[f32]>, GribError> {
+        let reader = RefCell::new(SeekableGrib2Reader::new(reader));
+        decoders::decode_on_grid(
+            &self.5.body,
+            &self.6.body,
+            &self.7.body,
+            reader.borrow_mut(),
+            opts,
+            self.grid_def().num_points() as usize,
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedSubMessageSection {
+    pub index: usize,
+    pub body: SectionInfo,
+}
+
+impl OwnedSubMessageSection {
+    fn as_section(&self) -> SubMessageSection<'_> {
+        SubMessageSection::new(self.index, &self.body)
+    }
+}
+
+pub struct SubMessageSection<'a> {
+    pub index: usize,
+    pub body: &'a SectionInfo,
+}
+
+impl<'a> SubMessageSection<'a> {
+    pub fn new(index: usize, body: &'a SectionInfo) -> Self {
+        Self { index, body }
+    }
+
+    pub fn template_code(&self) -> Option<TemplateInfo> {
+        self.body.get_tmpl_code()
+    }
+
+    pub fn describe(&self) -> Option<String> {
+        self.template_code().and_then(|code| code.describe())
+    }
+}
+
+fn is_same_grid(a: &GridDefinition, b: &GridDefinition) -> bool {
+    if a.grid_tmpl_num() != b.grid_tmpl_num() {
+        return false;
+    }
+    match (Grid::from_grid_def(a), Grid::from_grid_def(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        // compares raw bytes for templates not supported by `Grid`
+        _ => {
+            a.iter().as_slice()[START_OF_GRID_TEMPLATE..]
+                == b.iter().as_slice()[START_OF_GRID_TEMPLATE..]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{assert_fields_close, bytes_from_xz_file};
+
+    use std::fs::File;
+    use std::io::BufReader;
+
+    macro_rules! sect_placeholder {
+        ($num:expr) => {{
+            SectionInfo {
+                num: $num,
+                offset: 0,
+                size: 0,
+                body: None,
+                checksum: None,
+            }
+        }};
     }
 
     #[test]
@@ -397,6 +1728,194 @@ mod tests {
         assert!(result.is_ok())
     }
 
+    #[test]
+    fn validate_template_pairings() {
+        let buf = std::fs::read(
+            "testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+        )
+        .unwrap();
+        let offset_of = |num| {
+            let grib2 = from_slice(&buf).unwrap();
+            let sect = grib2.sections().iter().find(|s| s.num == num).unwrap();
+            sect.offset
+        };
+        // the template number is at octets 13-14 of Section 3, 8-9 of Section
+        // 4 and 10-11 of Section 5
+        let patched = |num, pos, tmpl_num: u16| {
+            let mut buf = buf.clone();
+            let start = offset_of(num) + pos;
+            buf[start..start + 2].copy_from_slice(&tmpl_num.to_be_bytes());
+            let grib2 = from_slice(&buf).unwrap();
+            let sub = grib2.iter().next().unwrap();
+            validate_submessage(&sub)
+        };
+
+        let grib2 = from_slice(&buf).unwrap();
+        let sub = grib2.iter().next().unwrap();
+        assert_eq!(validate_submessage(&sub), Ok(()));
+        assert_eq!(
+            patched(5, 9, 50),
+            Err(ParseError::IncompatibleTemplates(
+                TemplateInfo(5, 50),
+                TemplateInfo(3, 101)
+            ))
+        );
+        assert_eq!(
+            patched(3, 12, 50),
+            Err(ParseError::IncompatibleTemplates(
+                TemplateInfo(3, 50),
+                TemplateInfo(5, 0)
+            ))
+        );
+        assert_eq!(
+            patched(4, 7, 1100),
+            Err(ParseError::IncompatibleTemplates(
+                TemplateInfo(4, 1100),
+                TemplateInfo(3, 101)
+            ))
+        );
+    }
+
+    #[test]
+    fn skip_prepended_header() {
+        let grib = std::fs::read(
+            "testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+        )
+        .unwrap();
+        let mut buf = b"HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\n\r\n".to_vec();
+        buf.extend_from_slice(&grib);
+
+        let grib2 = skip_to_grib(buf.as_slice()).unwrap();
+        let expected = from_slice(&grib).unwrap();
+        assert_eq!(grib2.sections(), expected.sections());
+        assert_eq!(grib2.get_values(0), expected.get_values(0));
+    }
+
+    #[test]
+    fn skip_to_grib_within_limit_only() {
+        let grib = std::fs::read(
+            "testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+        )
+        .unwrap();
+        let mut buf = vec![0xef, 0xbb, 0xbf]; // BOM
+        buf.resize(SKIP_TO_GRIB_SCAN_LIMIT, b' ');
+        buf.extend_from_slice(&grib);
+        assert!(skip_to_grib(buf.as_slice()).is_ok());
+
+        buf.insert(0, b' ');
+        assert_eq!(
+            skip_to_grib(buf.as_slice()).err(),
+            Some(GribError::ParseError(ParseError::GribMagicNotFound))
+        );
+    }
+
+    #[test]
+    fn hexdump_of_sect1() {
+        let mut f = File::open(
+            "testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+        )
+        .unwrap();
+        let grib2 = from_reader(BufReader::new(f.try_clone().unwrap())).unwrap();
+        let dump = grib2.sections()[1].hexdump(&mut f).unwrap();
+        let lines = dump.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2 + 15);
+        assert_eq!(
+            lines[0],
+            "00000010  00 00 00 15 01 00 4e 00 ff 13 01 01 07 e5 0b 14  |......N.........|"
+        );
+        assert_eq!(
+            lines[1],
+            "00000020  12 00 00 00 01                                   |.....|"
+        );
+        assert_eq!(lines[2], "octets 1-4: section_length = 21");
+        assert_eq!(lines[4], "octets 6-7: centre_id = 78");
+        assert_eq!(lines[6], "octet 10: master_table_version = 19");
+        assert_eq!(lines[9], "octets 13-14: year = 2021");
+        assert_eq!(lines[16], "octet 21: data_type = 1");
+
+        // no annotations for the other sections
+        let dump = grib2.sections()[8].hexdump(&mut f).unwrap();
+        assert_eq!(
+            dump,
+            "000000bd  37 37 37 37                                      |7777|\n"
+        );
+    }
+
+    #[test]
+    fn decode_owned_submessages_with_reader_in_another_thread() {
+        let grib2 = grib2_from_xz_file(
+            "testdata/Z__C_RJTD_20170221120000_MSG_GPV_Gll0p5deg_Pys_B20170221120000_F2017022115-2017022212_grib2.bin.xz",
+        );
+        let expected_description = grib2.iter().nth(1).unwrap().describe();
+        let expected_parameter = grib2.iter().nth(1).unwrap().parameter();
+        let expected = grib2.get_values(1).unwrap();
+
+        let (mut reader, submessages) = grib2.into_submessages_owned();
+        assert_eq!(submessages.len(), 16);
+        assert_eq!(submessages[1].parameter(), expected_parameter);
+
+        let handle = {
+            let submessages = submessages.clone();
+            std::thread::spawn(move || submessages[1].decode(&mut reader))
+        };
+        assert_eq!(handle.join().unwrap().unwrap(), expected);
+        assert_eq!(submessages[1].describe(), expected_description);
+    }
+
+    #[test]
+    fn discipline_from_sect0() {
+        let f = File::open(
+            "testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+        )
+        .unwrap();
+        let f = BufReader::new(f);
+        let grib2 = from_reader(f).unwrap();
+        assert_eq!(grib2.discipline(), Ok(0));
+        assert_eq!(grib2.discipline_enum(), Ok(Discipline::Meteorological));
+    }
+
+    #[test]
+    fn inventory_entries() {
+        let f =
+            File::open("testdata/CMC_glb_TMP_ISBL_1_latlon.24x.24_2021051800_P000.grib2").unwrap();
+        let f = BufReader::new(f);
+        let grib2 = from_reader(f).unwrap();
+        let inventory = grib2.inventory();
+
+        assert_eq!(inventory.len(), 1);
+        let first = &inventory[0];
+        assert_eq!((first.message, first.submessage, first.offset), (0, 0, 0));
+        assert_eq!(first.parameter, "Temperature");
+        assert_eq!(first.level, "Isobaric surface 100");
+        assert_eq!(
+            first.ref_time.map(|t| t.to_string()),
+            Some("2021-05-18 00:00:00 UTC".to_owned())
+        );
+        assert_eq!(first.forecast_time, "0 [h]");
+        assert_eq!(first.grid_tmpl_num, 0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn inventory_entries_as_json_lines() {
+        let f =
+            File::open("testdata/CMC_glb_TMP_ISBL_1_latlon.24x.24_2021051800_P000.grib2").unwrap();
+        let f = BufReader::new(f);
+        let grib2 = from_reader(f).unwrap();
+        let lines = grib2
+            .inventory()
+            .iter()
+            .map(|entry| serde_json::to_string(entry).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            lines,
+            vec![
+                r#"{"message":0,"submessage":0,"offset":0,"parameter":"Temperature","level":"Isobaric surface 100","ref_time":"2021-05-18T00:00:00Z","forecast_time":"0 [h]","grid_tmpl_num":0}"#
+            ]
+        );
+    }
+
     #[test]
     fn get_tmpl_code_normal() {
         let sect = SectionInfo {
@@ -409,6 +1928,7 @@ mod tests {
                 )
                 .unwrap(),
             )),
+            checksum: None,
         };
 
         assert_eq!(sect.get_tmpl_code(), Some(TemplateInfo(5, 200)));
@@ -426,6 +1946,7 @@ mod tests {
                 body: Some(SectionBody::Section3(
                     GridDefinition::from_payload(vec![0; 9].into_boxed_slice()).unwrap(),
                 )),
+                checksum: None,
             },
             SectionInfo {
                 num: 4,
@@ -434,6 +1955,7 @@ mod tests {
                 body: Some(SectionBody::Section4(
                     ProdDefinition::from_payload(vec![0; 4].into_boxed_slice()).unwrap(),
                 )),
+                checksum: None,
             },
             SectionInfo {
                 num: 5,
@@ -442,6 +1964,7 @@ mod tests {
                 body: Some(SectionBody::Section5(
                     ReprDefinition::from_payload(vec![0; 6].into_boxed_slice()).unwrap(),
                 )),
+                checksum: None,
             },
             sect_placeholder!(6),
             sect_placeholder!(7),
@@ -455,6 +1978,7 @@ mod tests {
                     )
                     .unwrap(),
                 )),
+                checksum: None,
             },
             SectionInfo {
                 num: 4,
@@ -463,6 +1987,7 @@ mod tests {
                 body: Some(SectionBody::Section4(
                     ProdDefinition::from_payload(vec![0; 4].into_boxed_slice()).unwrap(),
                 )),
+                checksum: None,
             },
             SectionInfo {
                 num: 5,
@@ -471,6 +1996,7 @@ mod tests {
                 body: Some(SectionBody::Section5(
                     ReprDefinition::from_payload(vec![0; 6].into_boxed_slice()).unwrap(),
                 )),
+                checksum: None,
             },
             sect_placeholder!(6),
             sect_placeholder!(7),
@@ -488,4 +2014,1337 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn same_grid() {
+        let f =
+            File::open("testdata/CMC_glb_TMP_ISBL_1_latlon.24x.24_2021051800_P000.grib2").unwrap();
+        let f = BufReader::new(f);
+        let cmc = from_reader(f).unwrap();
+        let cmc = cmc.iter().next().unwrap();
+        let f = File::open(
+            "testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+        )
+        .unwrap();
+        let f = BufReader::new(f);
+        let icon = from_reader(f).unwrap();
+        let icon = icon.iter().next().unwrap();
+
+        assert!(cmc.is_same_grid_as(&cmc));
+        assert!(icon.is_same_grid_as(&icon));
+        assert!(!cmc.is_same_grid_as(&icon));
+
+        // Di = Dj = 0.48 degrees
+        let mut payload = cmc.grid_def().iter().as_slice().to_vec();
+        payload[START_OF_GRID_TEMPLATE + 49..START_OF_GRID_TEMPLATE + 53]
+            .copy_from_slice(&480000_u32.to_be_bytes());
+        payload[START_OF_GRID_TEMPLATE + 53..START_OF_GRID_TEMPLATE + 57]
+            .copy_from_slice(&480000_u32.to_be_bytes());
+        let coarse = GridDefinition::from_payload(payload.into_boxed_slice()).unwrap();
+        assert!(is_same_grid(cmc.grid_def(), cmc.grid_def()));
+        assert!(!is_same_grid(cmc.grid_def(), &coarse));
+    }
+
+    fn grib2_from_xz_file(path: &str) -> Grib2<SeekableGrib2Reader<Cursor<Vec<u8>>>> {
+        from_reader(Cursor::new(bytes_from_xz_file(path))).unwrap()
+    }
+
+    #[test]
+    fn decode_reading_sect7_in_chunks() {
+        let grib2 = grib2_from_xz_file(
+            "testdata/Z__C_RJTD_20170221120000_MSG_GPV_Gll0p5deg_Pys_B20170221120000_F2017022115-2017022212_grib2.bin.xz",
+        );
+        let opts = DecodeOptions {
+            sect7_chunk_size: Some(3),
+            ..Default::default()
+        };
+        for i in 0..grib2.submessages().count() {
+            let expected = grib2.get_values(i).unwrap();
+            let actual = grib2.get_values_with_options(i, &opts).unwrap();
+            assert_fields_close(&actual, &expected, 0.);
+
+            let mut out = vec![0.; expected.len()];
+            grib2.decode_into_with_options(i, &mut out, &opts).unwrap();
+            assert_fields_close(&out, &expected, 0.);
+        }
+    }
+
+    #[test]
+    fn owned_submessages_decoded_with_fresh_reader() {
+        let buf = bytes_from_xz_file(
+            "testdata/Z__C_RJTD_20170221120000_MSG_GPV_Gll0p5deg_Pys_B20170221120000_F2017022115-2017022212_grib2.bin.xz",
+        );
+        let grib2 = from_slice(&buf).unwrap();
+        let expected = (0..grib2.submessages().count())
+            .map(|i| grib2.get_values(i).unwrap())
+            .collect::<Vec<_>>();
+        let valid_times = grib2.iter().map(|s| s.valid_time()).collect::<Vec<_>>();
+
+        let owned = from_slice(&buf).unwrap().into_iter().collect::<Vec<_>>();
+        assert_eq!(owned.len(), 16);
+        assert_eq!(
+            owned.iter().map(|s| s.valid_time()).collect::<Vec<_>>(),
+            valid_times
+        );
+
+        let actual = std::thread::spawn(move || {
+            let mut reader = Cursor::new(buf);
+            owned
+                .iter()
+                .map(|submessage| submessage.decode(&mut reader).unwrap())
+                .collect::<Vec<_>>()
+        })
+        .join()
+        .unwrap();
+        for (actual, expected) in actual.iter().zip(expected.iter()) {
+            assert_fields_close(actual, expected, 0.);
+        }
+    }
+
+    #[test]
+    fn decode_all_with_index_of_failed_submessage() {
+        let mut buf = bytes_from_xz_file(
+            "testdata/Z__C_RJTD_20170221120000_MSG_GPV_Gll0p5deg_Pys_B20170221120000_F2017022115-2017022212_grib2.bin.xz",
+        );
+        let grib2 = from_slice(&buf).unwrap();
+        let expected = (0..grib2.submessages().count())
+            .map(|i| grib2.get_values(i).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(grib2.decode_all().unwrap(), expected);
+
+        // makes the bit-map indicator of the submessage 2 unsupported
+        let sect6_offset = grib2.submessages().nth(2).unwrap().section_offsets()[&6].start;
+        buf[sect6_offset as usize + 5] = 254;
+        let grib2 = from_slice(&buf).unwrap();
+        assert_eq!(
+            grib2.decode_all(),
+            Err(GribError::DecodeError(
+                decoders::DecodeError::SubmessageDecodeError {
+                    submessage_index: 2,
+                    cause: Box::new(decoders::DecodeError::BitMapIndicatorUnsupported),
+                }
+            ))
+        );
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn decode_all_in_parallel() {
+        let buf = bytes_from_xz_file(
+            "testdata/Z__C_RJTD_20190605000000_MEPS_GPV_Rjp_L-pall_FH00-15_grib2.bin.0-20.xz",
+        );
+        let mut f = tempfile::NamedTempFile::new().unwrap();
+        f.write_all(&buf).unwrap();
+
+        let grib2 = from_slice(&buf).unwrap();
+        let expected = (0..grib2.submessages().count())
+            .map(|i| grib2.get_values(i).unwrap())
+            .collect::<Vec<_>>();
+        let actual = decode_all_rayon(f.path()).unwrap();
+        assert_eq!(actual.len(), expected.len());
+        for (actual, expected) in actual.iter().zip(expected.iter()) {
+            assert_fields_close(actual, expected, 0.);
+        }
+    }
+
+    #[cfg(feature = "jpeg2000")]
+    #[test]
+    fn message_at_offset_in_multi_message_data() {
+        let mut buf = std::fs::read(
+            "testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+        )
+        .unwrap();
+        let offset = buf.len();
+        let second =
+            std::fs::read("testdata/CMC_glb_TMP_ISBL_1_latlon.24x.24_2021051800_P000.grib2")
+                .unwrap();
+        buf.extend_from_slice(&second);
+        buf.extend_from_slice(&[0; 16]);
+
+        let grib2 = message_at_offset(Cursor::new(&buf), offset as u64).unwrap();
+        assert_eq!(grib2.submessages().count(), 1);
+        assert_eq!(grib2.sections()[0].offset, offset);
+        assert_eq!(grib2.sections().last().unwrap().offset, buf.len() - 16 - 4);
+        let submessage = grib2.iter().next().unwrap();
+        assert_eq!(submessage.identification().centre_id(), 54);
+        assert_eq!(submessage.grid_def().num_points(), 1500 * 751);
+
+        let expected = from_slice(&second).unwrap().get_values(0).unwrap();
+        assert_eq!(grib2.get_values(0).unwrap(), expected);
+
+        assert_eq!(
+            message_at_offset(Cursor::new(&buf), 1).err(),
+            Some(GribError::ParseError(ParseError::NotGRIB {
+                found: buf[1..5].try_into().unwrap(),
+            }))
+        );
+    }
+
+    #[test]
+    fn decode_submessage_with_unsupported_repr_template() {
+        let mut buf = bytes_from_xz_file(
+            "testdata/Z__C_RJTD_20170221120000_MSG_GPV_Gll0p5deg_Pys_B20170221120000_F2017022115-2017022212_grib2.bin.xz",
+        );
+        let grib2 = from_slice(&buf).unwrap();
+
+        // makes the submessage 1 use Template 5.4 (IEEE floating point)
+        let sect5_offset = grib2.submessages().nth(1).unwrap().section_offsets()[&5].start;
+        buf[sect5_offset as usize + 9..][..2].copy_from_slice(&[0, 4]);
+        let grib2 = from_slice(&buf).unwrap();
+        assert!(grib2.get_values(0).is_ok());
+        assert_eq!(
+            grib2.get_values(1),
+            Err(GribError::Unsupported(
+                "Data Representation Template 5.4".to_owned()
+            ))
+        );
+    }
+
+    #[cfg(not(feature = "jpeg2000"))]
+    #[test]
+    fn jpeg2000_unsupported_without_feature() {
+        let f =
+            File::open("testdata/CMC_glb_TMP_ISBL_1_latlon.24x.24_2021051800_P000.grib2").unwrap();
+        let grib2 = from_reader(BufReader::new(f)).unwrap();
+        assert_eq!(
+            grib2.get_values(0),
+            Err(GribError::Unsupported(
+                "Data Representation Template 5.40".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn generating_processes() {
+        let f =
+            File::open("testdata/CMC_glb_TMP_ISBL_1_latlon.24x.24_2021051800_P000.grib2").unwrap();
+        let grib2 = from_reader(BufReader::new(f)).unwrap();
+        let process = grib2.iter().next().unwrap().generating_process().unwrap();
+        assert_eq!(
+            process,
+            GeneratingProcess {
+                process_type: 2,
+                background_process_id: 47,
+                process_id: 47,
+            }
+        );
+        assert_eq!(process.describe_type(), "Forecast");
+        assert_eq!(
+            grib2
+                .iter()
+                .next()
+                .unwrap()
+                .prod_def()
+                .generating_process_name(),
+            Some("Forecast")
+        );
+
+        let grib2 = grib2_from_xz_file(
+            "testdata/Z__C_RJTD_20190605000000_MEPS_GPV_Rjp_L-pall_FH00-15_grib2.bin.0-20.xz",
+        );
+        let process = grib2.iter().next().unwrap().generating_process().unwrap();
+        assert_eq!(process.process_type, 4);
+        assert_eq!(process.describe_type(), "Ensemble forecast");
+    }
+
+    #[test]
+    fn read_from_stream_without_seek() {
+        use xz2::bufread::XzDecoder;
+
+        let path =
+            "testdata/Z__C_RJTD_20160822020000_NOWC_GPV_Ggis10km_Pphw10_FH0000-0100_grib2.bin.xz";
+        let f = File::open(path).unwrap();
+        let grib2 = from_stream(XzDecoder::new(BufReader::new(f))).unwrap();
+        let expected = grib2_from_xz_file(path);
+        assert_eq!(grib2.submessages().count(), expected.submessages().count());
+        assert_fields_close(
+            &grib2.get_values(0).unwrap(),
+            &expected.get_values(0).unwrap(),
+            0.,
+        );
+    }
+
+    #[test]
+    fn read_from_stream_with_padding() {
+        let content = std::fs::read(
+            "testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+        )
+        .unwrap();
+        let expected = from_slice(&content).unwrap().get_values(0).unwrap();
+
+        let mut buf = content.clone();
+        buf.extend_from_slice(b"\n\0\0  \r\n");
+        let grib2 = from_stream(&buf[..]).unwrap();
+        assert_eq!(grib2.iter().count(), 1);
+        assert_eq!(grib2.get_values(0).unwrap(), expected);
+
+        // a second message after padding is read, and anything else after
+        // it is ignored
+        buf.extend_from_slice(&content);
+        buf.extend_from_slice(b"trailing garbage");
+        let grib2 = from_stream(&buf[..]).unwrap();
+        assert_eq!(grib2.sections().iter().filter(|s| s.num == 0).count(), 2);
+        assert_eq!(grib2.get_values(0).unwrap(), expected);
+
+        // offsets of sections are those in the stream
+        let sect0_offsets = grib2
+            .sections()
+            .iter()
+            .filter(|s| s.num == 0)
+            .map(|s| s.offset)
+            .collect::<Vec<_>>();
+        assert_eq!(sect0_offsets, [0, content.len() + 7]);
+        let sect7_offset =
+            |sections: &[SectionInfo]| sections.iter().rfind(|s| s.num == 7).unwrap().offset;
+        assert_eq!(
+            sect7_offset(grib2.sections()),
+            content.len() + 7 + sect7_offset(from_slice(&content).unwrap().sections())
+        );
+
+        // padding before the first message
+        let buf = [&b"\n\n"[..], &content].concat();
+        let grib2 = from_stream(&buf[..]).unwrap();
+        assert_eq!(grib2.sections()[0].offset, 2);
+        assert_eq!(grib2.get_values(0).unwrap(), expected);
+
+        assert!(from_stream(&b"not GRIB data"[..]).is_err());
+        assert!(from_stream(&content[..content.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn content_keys() {
+        let grib2 = grib2_from_xz_file(
+            "testdata/Z__C_RJTD_20190605000000_MEPS_GPV_Rjp_L-pall_FH00-15_grib2.bin.0-20.xz",
+        );
+        let keys = grib2.iter().map(|s| s.content_key()).collect::<Vec<_>>();
+        assert_eq!(keys[0].grid_hash, keys[1].grid_hash);
+        assert_eq!(keys[0].grid_tmpl_num, keys[1].grid_tmpl_num);
+        assert_ne!(keys[0].prod_hash, keys[1].prod_hash);
+        assert_ne!(keys[0], keys[1]);
+
+        // the same content in another copy of the data gives the same key
+        let copy = grib2_from_xz_file(
+            "testdata/Z__C_RJTD_20190605000000_MEPS_GPV_Rjp_L-pall_FH00-15_grib2.bin.0-20.xz",
+        );
+        assert_eq!(copy.iter().next().unwrap().content_key(), keys[0]);
+        let unique = keys.iter().collect::<HashSet<_>>();
+        assert_eq!(unique.len(), keys.len());
+    }
+
+    #[test]
+    fn filter_by_valid_time() {
+        use chrono::TimeZone;
+
+        let grib2 = grib2_from_xz_file(
+            "testdata/Z__C_RJTD_20170221120000_MSG_GPV_Gll0p5deg_Pys_B20170221120000_F2017022115-2017022212_grib2.bin.xz",
+        );
+        let valid_time = Utc.with_ymd_and_hms(2017, 2, 21, 18, 0, 0).unwrap();
+        let indices = grib2
+            .filter_by_valid_time(valid_time, 0)
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        assert_eq!(indices, vec![2, 3]);
+
+        let valid_time = Utc.with_ymd_and_hms(2017, 2, 21, 18, 1, 0).unwrap();
+        assert_eq!(grib2.filter_by_valid_time(valid_time, 59).count(), 0);
+        let indices = grib2
+            .filter_by_valid_time(valid_time, 60)
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        assert_eq!(indices, vec![2, 3]);
+    }
+
+    #[test]
+    fn get_values_with_fill_value() {
+        let grib2 = grib2_from_xz_file(
+            "testdata/Z__C_RJTD_20160822020000_NOWC_GPV_Ggis10km_Pphw10_FH0000-0100_grib2.bin.xz",
+        );
+        let default = grib2.get_values(0).unwrap();
+        let opts = DecodeOptions {
+            fill_value: -999.0,
+            ..Default::default()
+        };
+        let filled = grib2.get_values_with_options(0, &opts).unwrap();
+
+        assert_eq!(default.len(), filled.len());
+        let num_missing = default.iter().filter(|v| v.is_nan()).count();
+        assert!(num_missing > 0);
+        assert_eq!(filled.iter().filter(|v| **v == -999.0).count(), num_missing);
+        assert!(default
+            .iter()
+            .zip(filled.iter())
+            .all(|(d, f)| if d.is_nan() { *f == -999.0 } else { d == f }));
+    }
+
+    #[test]
+    fn decode_to_writer() {
+        let f = File::open(
+            "testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+        )
+        .unwrap();
+        let grib2 = from_reader(BufReader::new(f)).unwrap();
+        let num_points = grib2.iter().next().unwrap().repr_def().num_points() as usize;
+
+        for format in [OutputFormat::F32Le, OutputFormat::F32Be] {
+            let mut out = Vec::new();
+            grib2.decode_to_writer(0, &mut out, format).unwrap();
+            assert_eq!(out.len(), num_points * std::mem::size_of::<f32>());
+        }
+
+        let mut out = Vec::new();
+        grib2
+            .decode_to_writer(0, &mut out, OutputFormat::Text)
+            .unwrap();
+        assert_eq!(out.len(), num_points * "0\n".len());
+    }
+
+    #[test]
+    fn decode_to_writer_matches_decoded_values() {
+        for (path, count) in [
+            ("testdata/Z__C_RJTD_20170221120000_MSG_GPV_Gll0p5deg_Pys_B20170221120000_F2017022115-2017022212_grib2.bin.xz", 16),
+            ("testdata/Z__C_RJTD_20190605000000_MEPS_GPV_Rjp_L-pall_FH00-15_grib2.bin.0-20.xz", 1),
+        ] {
+            let grib2 = grib2_from_xz_file(path);
+            for i in 0..count {
+                let mut out = Vec::new();
+                grib2
+                    .decode_to_writer(i, &mut out, OutputFormat::F32Be)
+                    .unwrap();
+                let expected = grib2
+                    .get_values(i)
+                    .unwrap()
+                    .iter()
+                    .flat_map(|value| value.to_be_bytes())
+                    .collect::<Vec<_>>();
+                assert_eq!(out, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn get_raw_integers() {
+        use crate::utils::{read_as, GribInt};
+        use std::convert::TryInto;
+
+        let grib2 = grib2_from_xz_file(
+            "testdata/Z__C_RJTD_20170221120000_MSG_GPV_Gll0p5deg_Pys_B20170221120000_F2017022115-2017022212_grib2.bin.xz",
+        );
+        let raw = grib2.get_raw_integers(0).unwrap();
+        let values = grib2.get_values(0).unwrap();
+        assert_eq!(raw.len(), values.len());
+
+        let submessage = grib2.iter().next().unwrap();
+        let payload = submessage.repr_def().iter().copied().collect::<Vec<_>>();
+        let ref_val = read_as!(f32, payload, 6);
+        let exp = read_as!(u16, payload, 10).as_grib_int();
+        let dig = read_as!(u16, payload, 12).as_grib_int();
+        let unpacked = raw
+            .iter()
+            .map(|v| (ref_val + *v as f32 * 2_f32.powi(exp.into())) * 10_f32.powi(-dig as i32))
+            .collect::<Vec<_>>();
+        assert_fields_close(&unpacked, &values, 1e-6);
+
+        let f = File::open(
+            "testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+        )
+        .unwrap();
+        let grib2 = from_reader(BufReader::new(f)).unwrap();
+        assert_eq!(
+            grib2.get_raw_integers(0).unwrap(),
+            vec![0; 2949120].into_boxed_slice()
+        );
+    }
+
+    #[test]
+    fn parameter_of_oceanographic_products() {
+        let mut buf = std::fs::read(
+            "testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+        )
+        .unwrap();
+        // discipline 10 (oceanographic products) with parameter 0.3
+        // (significant height of combined wind waves and swell)
+        buf[6] = 10;
+        buf[108] = 0;
+        buf[109] = 3;
+        let grib2 = from_slice(&buf).unwrap();
+        let submessage = grib2.iter().next().unwrap();
+        let parameter = submessage.parameter().unwrap();
+        assert_eq!(
+            parameter,
+            ParameterInfo {
+                discipline: 10,
+                category: 0,
+                number: 3
+            }
+        );
+        assert_eq!(
+            parameter.describe(),
+            "Significant height of combined wind waves and swell"
+        );
+
+        for (category, number, expected) in [
+            (0, 11, "Primary wave mean period"),
+            (1, 1, "Current speed"),
+            (2, 0, "Ice cover"),
+            (3, 0, "Water temperature"),
+            (4, 3, "Salinity"),
+        ] {
+            let parameter = ParameterInfo {
+                discipline: 10,
+                category,
+                number,
+            };
+            assert_eq!(parameter.describe(), expected);
+        }
+    }
+
+    #[test]
+    fn compressed_submessages() {
+        let f =
+            File::open("testdata/CMC_glb_TMP_ISBL_1_latlon.24x.24_2021051800_P000.grib2").unwrap();
+        let grib2 = from_reader(BufReader::new(f)).unwrap();
+        assert!(grib2.iter().next().unwrap().is_compressed());
+
+        let f = File::open(
+            "testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+        )
+        .unwrap();
+        let grib2 = from_reader(BufReader::new(f)).unwrap();
+        assert!(!grib2.iter().next().unwrap().is_compressed());
+    }
+
+    #[test]
+    fn jpeg2000_packing_params() {
+        let f =
+            File::open("testdata/CMC_glb_TMP_ISBL_1_latlon.24x.24_2021051800_P000.grib2").unwrap();
+        let grib2 = from_reader(BufReader::new(f)).unwrap();
+        let submessage = grib2.iter().next().unwrap();
+        let repr_def = submessage.repr_def();
+        assert_eq!(repr_def.simple_packing(), None);
+        let params = repr_def.jpeg2000_packing().unwrap();
+        assert_eq!(
+            params,
+            Jpeg2000PackingParams {
+                simple: SimplePackingParams {
+                    ref_val: f32::from_be_bytes([0x45, 0x0e, 0xcc, 0x05]),
+                    binary_scale: -2,
+                    decimal_scale: 1,
+                    nbit: 12,
+                    value_type: 0,
+                },
+                compression_type: 0,
+                target_compression_ratio: 255,
+            }
+        );
+        assert!(params.is_lossless());
+    }
+
+    #[cfg(feature = "jpeg2000")]
+    #[test]
+    fn decode_field() {
+        use chrono::TimeZone;
+
+        let f =
+            File::open("testdata/CMC_glb_TMP_ISBL_1_latlon.24x.24_2021051800_P000.grib2").unwrap();
+        let grib2 = from_reader(BufReader::new(f)).unwrap();
+        let field = grib2.decode_field(0, &DecodeOptions::default()).unwrap();
+
+        assert_eq!(field.data, grib2.get_values(0).unwrap());
+        assert_eq!(field.data.len(), field.grid.latlons().len());
+        assert_eq!(
+            field.ref_time,
+            Some(Utc.with_ymd_and_hms(2021, 5, 18, 0, 0, 0).unwrap())
+        );
+        assert_eq!(field.valid_time, field.ref_time);
+        let parameter = field.parameter.unwrap();
+        assert_eq!(parameter.describe(), "Temperature");
+        assert!(field.fill_value.is_nan());
+    }
+
+    #[cfg(feature = "jpeg2000")]
+    #[test]
+    fn weighted_mean() {
+        let f =
+            File::open("testdata/CMC_glb_TMP_ISBL_1_latlon.24x.24_2021051800_P000.grib2").unwrap();
+        let grib2 = from_reader(BufReader::new(f)).unwrap();
+        let values = grib2.get_values(0).unwrap();
+        let naive_mean = values.iter().map(|v| f64::from(*v)).sum::<f64>() / values.len() as f64;
+        let weighted_mean = grib2.weighted_mean(0).unwrap();
+        assert!((f64::from(weighted_mean) - naive_mean).abs() > 0.1);
+
+        let (min, max) = values.iter().fold((f32::MAX, f32::MIN), |(min, max), v| {
+            (min.min(*v), max.max(*v))
+        });
+        assert!((min..=max).contains(&weighted_mean));
+    }
+
+    #[cfg(feature = "jpeg2000")]
+    #[test]
+    fn decode_with_coordinates() {
+        let f =
+            File::open("testdata/CMC_glb_TMP_ISBL_1_latlon.24x.24_2021051800_P000.grib2").unwrap();
+        let grib2 = from_reader(BufReader::new(f)).unwrap();
+        let values = grib2.decode_with_coordinates(0).unwrap();
+        assert_eq!(values.len(), 1500 * 751);
+        let (lat_min, lat_max, lon_min, lon_max, value_min, value_max) = values.iter().fold(
+            (f64::MAX, f64::MIN, f64::MAX, f64::MIN, f32::MAX, f32::MIN),
+            |(lat_min, lat_max, lon_min, lon_max, value_min, value_max), (lat, lon, value)| {
+                (
+                    lat_min.min(*lat),
+                    lat_max.max(*lat),
+                    lon_min.min(*lon),
+                    lon_max.max(*lon),
+                    value_min.min(*value),
+                    value_max.max(*value),
+                )
+            },
+        );
+        assert_eq!((lat_min, lat_max), (-90., 90.));
+        assert_eq!(lon_min, 0.);
+        assert!((lon_max - 359.76).abs() < 1e-4);
+        // temperature in K at 1 hPa
+        assert!(value_min > 200. && value_max < 300.);
+
+        let grib2 = grib2_from_xz_file(
+            "testdata/Z__C_RJTD_20160822020000_NOWC_GPV_Ggis10km_Pphw10_FH0000-0100_grib2.bin.xz",
+        );
+        let num_missing = grib2
+            .get_values(0)
+            .unwrap()
+            .iter()
+            .filter(|v| v.is_nan())
+            .count();
+        let values = grib2.decode_with_coordinates(0).unwrap();
+        let num_points = grib2.iter().next().unwrap().grid().unwrap().num_points();
+        assert_eq!(values.len(), num_points - num_missing);
+        assert!(values.iter().all(|(_, _, value)| !value.is_nan()));
+    }
+
+    #[test]
+    fn headers() {
+        let grib2 = grib2_from_xz_file(
+            "testdata/Z__C_RJTD_20170221120000_MSG_GPV_Gll0p5deg_Pys_B20170221120000_F2017022115-2017022212_grib2.bin.xz",
+        );
+        let headers = grib2.headers().collect::<Vec<_>>();
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers[0].edition, 2);
+        assert_eq!(headers[0].discipline, 0);
+        assert_eq!(headers[0].identification.centre_id(), 34);
+
+        let buf = std::fs::read(
+            "testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+        )
+        .unwrap();
+        let grib2 = from_reader(Cursor::new(buf.repeat(3))).unwrap();
+        let centres = grib2
+            .headers()
+            .map(|header| header.identification.centre_id())
+            .collect::<Vec<_>>();
+        assert_eq!(centres, vec![78, 78, 78]);
+    }
+
+    #[test]
+    fn time_steps_of_fixture() {
+        use chrono::TimeZone;
+
+        let grib2 = grib2_from_xz_file(
+            "testdata/Z__C_RJTD_20170221120000_MSG_GPV_Gll0p5deg_Pys_B20170221120000_F2017022115-2017022212_grib2.bin.xz",
+        );
+        let times = grib2.time_steps(|_| true);
+        assert!(times.len() > 1);
+        assert!(times.windows(2).all(|w| w[0] < w[1]));
+        assert!(grib2
+            .submessages()
+            .all(|s| times.contains(&s.valid_time().unwrap())));
+        assert_eq!(
+            times.first(),
+            Some(&Utc.with_ymd_and_hms(2017, 2, 21, 15, 0, 0).unwrap())
+        );
+
+        let first = grib2.submessages().next().unwrap().parameter();
+        let param_times = grib2.time_steps(|s| s.parameter() == first);
+        assert!(!param_times.is_empty());
+        assert!(param_times.iter().all(|t| times.contains(t)));
+        assert!(grib2.time_steps(|_| false).is_empty());
+    }
+
+    #[test]
+    fn stacking_fields_on_same_grid() {
+        let grib2 = grib2_from_xz_file(
+            "testdata/Z__C_RJTD_20170221120000_MSG_GPV_Gll0p5deg_Pys_B20170221120000_F2017022115-2017022212_grib2.bin.xz",
+        );
+
+        let stack = grib2.stack_by(|_| true).unwrap();
+        let (ni, nj) = match &stack.grid {
+            Grid::LatLon(tmpl) => (tmpl.ni as usize, tmpl.nj as usize),
+            _ => panic!("unexpected grid"),
+        };
+        assert_eq!(stack.shape, (16, nj, ni));
+        assert_eq!(stack.values.len(), 16 * nj * ni);
+        assert_eq!(stack.layer(15), Some(&*grib2.get_values(15).unwrap()));
+        assert_eq!(stack.layer(16), None);
+
+        assert_eq!(
+            grib2.stack_by(|_| false),
+            Err(GribError::InvalidArgument(
+                "no submessages are selected".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn corner_values_of_fixture() {
+        let grib2 = grib2_from_xz_file(
+            "testdata/Z__C_RJTD_20170221120000_MSG_GPV_Gll0p5deg_Pys_B20170221120000_F2017022115-2017022212_grib2.bin.xz",
+        );
+        let tmpl = match grib2.iter().next().unwrap().grid().unwrap() {
+            Grid::LatLon(tmpl) => tmpl,
+            _ => panic!("unexpected grid"),
+        };
+        assert_eq!(tmpl.scanning_mode, 0);
+        let (ni, nj) = (tmpl.ni as usize, tmpl.nj as usize);
+        let values = grib2.get_values(0).unwrap();
+        let corners = grib2.corner_values(0).unwrap();
+
+        let deg = |v: i32| f64::from((f64::from(v) * 1e-6) as f32);
+        let (lat1, lon1) = (deg(tmpl.first_point_lat), deg(tmpl.first_point_lon));
+        let (lat2, lon2) = (deg(tmpl.last_point_lat), deg(tmpl.last_point_lon));
+        assert_eq!(corners.first, (lat1, lon1, values[0]));
+        assert_eq!(corners.first_row_last, (lat1, lon2, values[ni - 1]));
+        assert_eq!(corners.last_row_first, (lat2, lon1, values[(nj - 1) * ni]));
+        assert_eq!(corners.last, (lat2, lon2, values[ni * nj - 1]));
+    }
+
+    #[test]
+    fn corner_values_of_empty_grid() {
+        let mut buf = bytes_from_xz_file(
+            "testdata/Z__C_RJTD_20170221120000_MSG_GPV_Gll0p5deg_Pys_B20170221120000_F2017022115-2017022212_grib2.bin.xz",
+        );
+        let grib2 = from_slice(&buf).unwrap();
+
+        // sets Ni of the grid to 0
+        let sect3_offset = grib2.submessages().next().unwrap().section_offsets()[&3].start;
+        buf[sect3_offset as usize + 30..][..4].copy_from_slice(&[0; 4]);
+        let grib2 = from_slice(&buf).unwrap();
+        assert_eq!(
+            grib2.corner_values(0),
+            Err(GribError::GridError(GridError::EmptyGrid))
+        );
+    }
+
+    #[test]
+    fn complex_packing_parameters_of_fixture() {
+        let buf = bytes_from_xz_file(
+            "testdata/Z__C_RJTD_20190605000000_MEPS_GPV_Rjp_L-pall_FH00-15_grib2.bin.0-20.xz",
+        );
+        let grib2 = from_slice(&buf).unwrap();
+        for submessage in grib2.iter() {
+            let repr_def = submessage.repr_def();
+            assert_eq!(repr_def.repr_tmpl_num(), 3);
+            let params = repr_def.complex_packing().unwrap();
+
+            // octets 32-35 and 38-41 of Section 5
+            let sect5 = &buf[submessage.section_offsets()[&5].start as usize..];
+            assert_eq!(params.num_groups.to_be_bytes(), sect5[31..35]);
+            assert_eq!(params.group_len_ref.to_be_bytes(), sect5[37..41]);
+            assert!(params.num_groups > 0);
+            assert!(params.group_len_last <= repr_def.num_points());
+            assert!(params.spatial_differencing.is_some());
+        }
+    }
+
+    #[test]
+    fn ensemble_members() {
+        let grib2 = grib2_from_xz_file(
+            "testdata/Z__C_RJTD_20190605000000_MEPS_GPV_Rjp_L-pall_FH00-15_grib2.bin.0-20.xz",
+        );
+
+        let submessage = grib2.iter().next().unwrap();
+        assert_eq!(
+            submessage.prod_def().ensemble(),
+            Some(EnsembleForecast {
+                ensemble_type: 0,
+                perturbation_number: 0,
+                number_of_forecasts: 21,
+            })
+        );
+        assert_eq!(submessage.ensemble_number(), Some(0));
+        assert_eq!(submessage.ensemble_size(), Some(21));
+
+        let indices = grib2
+            .filter_by_ensemble(0)
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        assert_eq!(indices, (0..20).collect::<Vec<_>>());
+        assert_eq!(grib2.filter_by_ensemble(1).count(), 0);
+
+        let ensemble = crate::field::ForecastEnsemble::from_submessages(&grib2, &[0]).unwrap();
+        assert_eq!(ensemble.members.len(), 1);
+        assert_eq!(ensemble.mean(), grib2.get_values(0).unwrap());
+        assert!(ensemble.std_dev().iter().all(|v| *v == 0. || v.is_nan()));
+        assert_eq!(
+            crate::field::ForecastEnsemble::from_submessages(&grib2, &[]),
+            Err(GribError::InvalidArgument(
+                "no members are specified".to_owned()
+            ))
+        );
+        assert_eq!(
+            crate::field::ForecastEnsemble::from_submessages(&grib2, &[0, 20]),
+            Err(GribError::InvalidArgument(
+                "submessage index 20 is out of range".to_owned()
+            ))
+        );
+
+        let f =
+            File::open("testdata/CMC_glb_TMP_ISBL_1_latlon.24x.24_2021051800_P000.grib2").unwrap();
+        let grib2 = from_reader(BufReader::new(f)).unwrap();
+        let submessage = grib2.iter().next().unwrap();
+        assert_eq!(submessage.ensemble_number(), None);
+        assert_eq!(submessage.ensemble_size(), None);
+    }
+
+    #[test]
+    fn decode_into_reused_buffer() {
+        let grib2 = grib2_from_xz_file(
+            "testdata/Z__C_RJTD_20170221120000_MSG_GPV_Gll0p5deg_Pys_B20170221120000_F2017022115-2017022212_grib2.bin.xz",
+        );
+        let num_points = grib2.iter().next().unwrap().grid().unwrap().num_points();
+        let mut buf = vec![0.; num_points];
+        for i in 0..grib2.iter().count() {
+            grib2.decode_into(i, &mut buf).unwrap();
+            assert_eq!(&buf[..], &grib2.get_values(i).unwrap()[..]);
+        }
+
+        let mut buf = vec![0.; num_points + 1];
+        assert_eq!(
+            grib2.decode_into(0, &mut buf),
+            Err(GribError::DecodeError(
+                decoders::DecodeError::OutputLengthMismatch {
+                    expected: num_points,
+                    actual: num_points + 1,
+                }
+            ))
+        );
+
+        // templates without a direct implementation are copied into the buffer
+        let grib2 = grib2_from_xz_file(
+            "testdata/Z__C_RJTD_20190605000000_MEPS_GPV_Rjp_L-pall_FH00-15_grib2.bin.0-20.xz",
+        );
+        let expected = grib2.get_values(0).unwrap();
+        let mut buf = vec![0.; expected.len()];
+        grib2.decode_into(0, &mut buf).unwrap();
+        assert_fields_close(&buf, &expected, 0.);
+    }
+
+    #[cfg(feature = "jpeg2000")]
+    #[test]
+    fn decode_into_with_jpeg2000() {
+        let f =
+            File::open("testdata/CMC_glb_TMP_ISBL_1_latlon.24x.24_2021051800_P000.grib2").unwrap();
+        let grib2 = from_reader(BufReader::new(f)).unwrap();
+        let mut buf = vec![0.; 1500 * 751];
+        grib2.decode_into(0, &mut buf).unwrap();
+        assert_eq!(&buf[..], &grib2.get_values(0).unwrap()[..]);
+    }
+
+    #[test]
+    fn section_offsets() {
+        let buf = bytes_from_xz_file(
+            "testdata/Z__C_RJTD_20170221120000_MSG_GPV_Gll0p5deg_Pys_B20170221120000_F2017022115-2017022212_grib2.bin.xz",
+        );
+        let grib2 = from_slice(&buf).unwrap();
+        for submessage in grib2.iter() {
+            let offsets = submessage.section_offsets();
+            let mut nums = offsets.keys().copied().collect::<Vec<_>>();
+            nums.sort_unstable();
+            assert_eq!(nums, vec![1, 3, 4, 5, 6, 7]);
+
+            let sections = [
+                &submessage.1,
+                &submessage.3,
+                &submessage.4,
+                &submessage.5,
+                &submessage.6,
+                &submessage.7,
+            ];
+            for sect in sections {
+                let range = &offsets[&sect.body.num];
+                let bytes = &buf[range.start as usize..range.end as usize];
+                let mut reader = SeekableGrib2Reader::new(Cursor::new(bytes));
+                let header = reader.read_sect_header().unwrap().unwrap();
+                assert_eq!(header, (sect.body.size, sect.body.num));
+                let body = reader.read_sect_payload(&header).unwrap();
+                assert_eq!(Some(body), sect.body.body);
+            }
+        }
+
+        let owned = grib2.into_iter().next().unwrap();
+        assert_eq!(
+            owned.section_offsets(),
+            owned.as_submessage().section_offsets()
+        );
+    }
+
+    #[test]
+    fn validate_grids_while_reading() {
+        let mut buf = bytes_from_xz_file(
+            "testdata/Z__C_RJTD_20170221120000_MSG_GPV_Gll0p5deg_Pys_B20170221120000_F2017022115-2017022212_grib2.bin.xz",
+        );
+        let opts = ReadOptions {
+            validate_grids: true,
+            ..Default::default()
+        };
+        assert!(from_reader_with_options(Cursor::new(&buf), &opts).is_ok());
+
+        // moves the last grid point (Lo2) away by more than half an increment
+        let grib2 = from_slice(&buf).unwrap();
+        let sect3_offset = grib2.submessages().next().unwrap().section_offsets()[&3].start;
+        let lo2_offset = sect3_offset as usize + 59;
+        let lo2 = u32::from_be_bytes(buf[lo2_offset..][..4].try_into().unwrap());
+        buf[lo2_offset..][..4].copy_from_slice(&(lo2 + 1_000_000).to_be_bytes());
+
+        assert!(from_slice(&buf).is_ok());
+        assert_eq!(
+            from_reader_with_options(Cursor::new(&buf), &opts).err(),
+            Some(GribError::GridError(GridError::InconsistentLongitudes(
+                i64::from(lo2),
+                i64::from(lo2 + 1_000_000)
+            )))
+        );
+    }
+
+    #[test]
+    fn section_checksums() {
+        let buf = bytes_from_xz_file(
+            "testdata/Z__C_RJTD_20170221120000_MSG_GPV_Gll0p5deg_Pys_B20170221120000_F2017022115-2017022212_grib2.bin.xz",
+        );
+        let grib2 = from_slice(&buf).unwrap();
+        assert!(grib2.sections().iter().all(|s| s.checksum().is_none()));
+
+        let opts = ReadOptions {
+            compute_checksums: true,
+            ..Default::default()
+        };
+        let grib2 = from_reader_with_options(Cursor::new(&buf), &opts).unwrap();
+        assert!(grib2.sections().iter().all(|s| s.checksum().is_some()));
+
+        let sect7s = grib2
+            .iter()
+            .map(|submessage| submessage.7.body)
+            .collect::<Vec<_>>();
+        let raw = |sect: &SectionInfo| &buf[sect.offset..(sect.offset + sect.size)];
+        for a in sect7s.iter() {
+            for b in sect7s.iter() {
+                assert_eq!(a.checksum() == b.checksum(), raw(a) == raw(b));
+            }
+        }
+        // values are decoded as usual after reading Section 7 payloads
+        assert_eq!(
+            grib2.get_values(0).unwrap(),
+            from_slice(&buf).unwrap().get_values(0).unwrap()
+        );
+
+        let single = std::fs::read(
+            "testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+        )
+        .unwrap();
+        let grib2 = from_reader_with_options(Cursor::new(single.repeat(2)), &opts).unwrap();
+        let sect7s = grib2
+            .sections()
+            .iter()
+            .filter(|s| s.num == 7)
+            .collect::<Vec<_>>();
+        assert_eq!(sect7s.len(), 2);
+        assert_eq!(sect7s[0].checksum(), sect7s[1].checksum());
+        assert_ne!(sect7s[0].offset, sect7s[1].offset);
+    }
+
+    #[test]
+    fn access_to_raw_sections() {
+        let grib2 = grib2_from_xz_file(
+            "testdata/Z__C_RJTD_20170221120000_MSG_GPV_Gll0p5deg_Pys_B20170221120000_F2017022115-2017022212_grib2.bin.xz",
+        );
+        let sections = grib2.sections();
+        assert_eq!(sections.iter().filter(|s| s.num == 7).count(), 16);
+        assert_eq!(grib2.section_by_index(0).map(|s| s.num), Some(0));
+        assert_eq!(
+            grib2.section_by_index(sections.len() - 1).map(|s| s.num),
+            Some(8)
+        );
+        assert_eq!(grib2.section_by_index(sections.len()), None);
+    }
+
+    #[test]
+    fn read_length_prefixed_frames() {
+        let content = std::fs::read(
+            "testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+        )
+        .unwrap();
+        let mut framed = (content.len() as u32).to_be_bytes().to_vec();
+        framed.extend_from_slice(&content);
+
+        let grib2 = from_framed_reader(&framed[..], FrameFormat::LengthPrefixU32Be).unwrap();
+        assert_eq!(grib2.iter().count(), 1);
+        assert_eq!(
+            grib2.get_values(0).unwrap(),
+            from_slice(&content).unwrap().get_values(0).unwrap()
+        );
+
+        assert!(from_slice(&framed).is_err());
+        assert_eq!(
+            from_framed_reader(&framed[..framed.len() - 1], FrameFormat::LengthPrefixU32Be).err(),
+            Some(GribError::ParseError(ParseError::UnexpectedEndOfData(
+                content.len() - 1
+            )))
+        );
+    }
+
+    #[test]
+    fn parse_message_ignoring_trailing_octets() {
+        let mut buf = std::fs::read(
+            "testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+        )
+        .unwrap();
+        buf.extend_from_slice(b"trailing garbage");
+        assert!(from_slice(&buf).is_err());
+
+        let grib2 = parse_message(&buf).unwrap();
+        assert_eq!(grib2.iter().count(), 1);
+        assert_eq!(grib2.get_values(0).unwrap().len(), 2949120);
+    }
+
+    #[test]
+    fn decode_values_with_bitmap_onto_whole_grid() {
+        let content = std::fs::read(
+            "testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+        )
+        .unwrap();
+        let grib2 = from_slice(&content).unwrap();
+        let original = grib2.get_values(0).unwrap();
+        let offsets = grib2.iter().next().unwrap().section_offsets();
+        let section = |num: u8| {
+            let range = &offsets[&num];
+            content[range.start as usize..range.end as usize].to_vec()
+        };
+
+        // Only the first 1000 values of Section 7 are used, at every other
+        // grid point from the beginning.
+        let num_values = 1000_usize;
+        let mut sect5 = section(5);
+        sect5[5..9].copy_from_slice(&(num_values as u32).to_be_bytes());
+        let num_grid_points = original.len();
+        let mut bitmap = vec![0_u8; num_grid_points.div_ceil(8)];
+        for index in (0..num_values * 2).step_by(2) {
+            bitmap[index / 8] |= 0b1000_0000 >> (index % 8);
+        }
+        let mut sect6 = ((6 + bitmap.len()) as u32).to_be_bytes().to_vec();
+        sect6.extend_from_slice(&[6, 0]);
+        sect6.extend_from_slice(&bitmap);
+
+        let mut buf = content[..16].to_vec();
+        for num in 1..=4 {
+            if offsets.contains_key(&num) {
+                buf.extend_from_slice(&section(num));
+            }
+        }
+        buf.extend_from_slice(&sect5);
+        buf.extend_from_slice(&sect6);
+        buf.extend_from_slice(&section(7));
+        buf.extend_from_slice(b"7777");
+        let len = buf.len() as u64;
+        buf[8..16].copy_from_slice(&len.to_be_bytes());
+
+        let grib2 = from_slice(&buf).unwrap();
+        let submessage = grib2.iter().next().unwrap();
+        assert!((submessage.repr_def().num_points() as usize) < num_grid_points);
+
+        let values = grib2.get_values(0).unwrap();
+        assert_eq!(values.len(), num_grid_points);
+        for (index, value) in values.iter().enumerate() {
+            if index < num_values * 2 && index % 2 == 0 {
+                assert_eq!(*value, original[index / 2]);
+            } else {
+                assert!(value.is_nan());
+            }
+        }
+
+        let mut out = vec![0.; num_grid_points];
+        grib2.decode_into(0, &mut out).unwrap();
+        assert!(out
+            .iter()
+            .zip(values.iter())
+            .all(|(a, b)| a == b || (a.is_nan() && b.is_nan())));
+        assert_eq!(
+            grib2.decode_into(0, &mut out[..num_values]),
+            Err(GribError::DecodeError(
+                decoders::DecodeError::OutputLengthMismatch {
+                    expected: num_grid_points,
+                    actual: num_values,
+                }
+            ))
+        );
+
+        let mut written = Vec::new();
+        grib2
+            .decode_to_writer(0, &mut written, OutputFormat::F32Le)
+            .unwrap();
+        let expected = values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<_>>();
+        assert_eq!(written, expected);
+    }
+
+    // Builds a message of spherical harmonic coefficients of triangular
+    // truncation T2 with Sections 0, 1, 2 and 4 taken from a fixture.
+    fn spectral_message(num_values: u32, sect5_template: &[u8], sect7_data: &[u8]) -> Vec<u8> {
+        let content = std::fs::read(
+            "testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+        )
+        .unwrap();
+        let grib2 = from_slice(&content).unwrap();
+        let offsets = grib2.iter().next().unwrap().section_offsets();
+        let section = |num: u8| {
+            let range = &offsets[&num];
+            content[range.start as usize..range.end as usize].to_vec()
+        };
+        let with_header = |num: u8, body: &[u8]| {
+            let mut sect = ((5 + body.len()) as u32).to_be_bytes().to_vec();
+            sect.push(num);
+            sect.extend_from_slice(body);
+            sect
+        };
+
+        let mut sect3 = vec![0];
+        sect3.extend_from_slice(&num_values.to_be_bytes());
+        sect3.extend_from_slice(&[0, 0, 0, 50]);
+        for _ in 0..3 {
+            sect3.extend_from_slice(&2_u32.to_be_bytes());
+        }
+        sect3.extend_from_slice(&[1, 1]);
+        let mut sect5 = num_values.to_be_bytes().to_vec();
+        sect5.extend_from_slice(sect5_template);
+
+        let mut buf = content[..16].to_vec();
+        buf.extend_from_slice(&section(1));
+        buf.extend_from_slice(&section(2));
+        buf.extend_from_slice(&with_header(3, &sect3));
+        buf.extend_from_slice(&section(4));
+        buf.extend_from_slice(&with_header(5, &sect5));
+        buf.extend_from_slice(&with_header(6, &[255]));
+        buf.extend_from_slice(&with_header(7, sect7_data));
+        buf.extend_from_slice(b"7777");
+        let len = buf.len() as u64;
+        buf[8..16].copy_from_slice(&len.to_be_bytes());
+        buf
+    }
+
+    #[test]
+    fn decode_spectral_coefficients_with_simple_packing() {
+        // template number, R, E, D, bits per value and the real part of (0,0)
+        let mut sect5 = vec![0, 50];
+        sect5.extend_from_slice(&0_f32.to_be_bytes());
+        sect5.extend_from_slice(&[0, 0, 0, 0, 8]);
+        sect5.extend_from_slice(&100.5_f32.to_be_bytes());
+        let sect7 = (1..=11).collect::<Vec<u8>>();
+
+        let buf = spectral_message(12, &sect5, &sect7);
+        let grib2 = from_slice(&buf).unwrap();
+        let mut expected = vec![100.5];
+        expected.extend((1..=11_u8).map(f32::from));
+        assert_eq!(grib2.spectral_coefficients(0).unwrap().into_vec(), expected);
+
+        // T2 has 6 complex coefficients, i.e. 12 values
+        let buf = spectral_message(10, &sect5, &sect7[..9]);
+        let grib2 = from_slice(&buf).unwrap();
+        assert_eq!(
+            grib2.spectral_coefficients(0),
+            Err(GribError::DecodeError(
+                decoders::DecodeError::SpectralDecodeError(
+                    decoders::SpectralDecodeError::TruncationMismatch {
+                        expected: 12,
+                        actual: 10,
+                    }
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn decode_spectral_coefficients_with_complex_packing() {
+        // template number, R, E, D, bits per value, P = 0.5, Js, Ks, Ms = 1,
+        // Ts and precision
+        let mut sect5 = vec![0, 51];
+        sect5.extend_from_slice(&0_f32.to_be_bytes());
+        sect5.extend_from_slice(&[0, 0, 0, 0, 8]);
+        sect5.extend_from_slice(&500000_u32.to_be_bytes());
+        sect5.extend_from_slice(&[0, 1, 0, 1, 0, 1]);
+        sect5.extend_from_slice(&6_u32.to_be_bytes());
+        sect5.push(1);
+
+        // unpacked (0,0), (0,1) and (1,1) followed by packed (0,2), (1,2)
+        // and (2,2)
+        let mut sect7 = Vec::new();
+        for value in [10., 0., 20., 1., 30., 2.] {
+            sect7.extend_from_slice(&f32::to_be_bytes(value));
+        }
+        sect7.extend_from_slice(&[2, 4, 6, 8, 10, 12]);
+
+        let buf = spectral_message(12, &sect5, &sect7);
+        let grib2 = from_slice(&buf).unwrap();
+        let actual = grib2.spectral_coefficients(0).unwrap();
+        let factor = 1. / 6_f32.sqrt();
+        let expected = [
+            10.,
+            0.,
+            20.,
+            1.,
+            2. * factor,
+            4. * factor,
+            30.,
+            2.,
+            6. * factor,
+            8. * factor,
+            10. * factor,
+            12. * factor,
+        ];
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected.iter()) {
+            assert!((a - e).abs() < 1e-6, "{} != {}", a, e);
+        }
+
+        sect5[24] = 5;
+        let buf = spectral_message(12, &sect5, &sect7);
+        let grib2 = from_slice(&buf).unwrap();
+        assert_eq!(
+            grib2.spectral_coefficients(0),
+            Err(GribError::DecodeError(
+                decoders::DecodeError::SpectralDecodeError(
+                    decoders::SpectralDecodeError::UnpackedSubsetMismatch {
+                        expected: 6,
+                        actual: 5,
+                    }
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn reject_byte_swapped_message_size() {
+        let mut buf = std::fs::read(
+            "testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+        )
+        .unwrap();
+        buf[8..16].reverse();
+        assert_eq!(
+            from_slice(&buf).err(),
+            Some(GribError::ParseError(ParseError::ImplausibleMessageSize {
+                declared: 193_u64.swap_bytes(),
+            }))
+        );
+
+        buf[8..16].reverse();
+        let opts = ReadOptions {
+            max_message_size: 192,
+            ..Default::default()
+        };
+        assert_eq!(
+            from_reader_with_options(Cursor::new(&buf), &opts).err(),
+            Some(GribError::ParseError(ParseError::ImplausibleMessageSize {
+                declared: 193,
+            }))
+        );
+        let opts = ReadOptions {
+            max_message_size: 193,
+            ..Default::default()
+        };
+        assert!(from_reader_with_options(Cursor::new(&buf), &opts).is_ok());
+    }
+
+    #[test]
+    fn parse_message_with_broken_sizes() {
+        // section size smaller than the header size
+        let mut buf = b"GRIB\x00\x01\x00\x02".to_vec();
+        buf.extend_from_slice(&41_u64.to_be_bytes());
+        buf.extend_from_slice(&[0; 24]);
+        buf.push(26);
+        assert!(parse_message(&buf).is_err());
+
+        // total length smaller than the size of Section 0
+        let mut buf = b"GRIB\x00\x01\x00\x02".to_vec();
+        buf.extend_from_slice(&8_u64.to_be_bytes());
+        buf.extend_from_slice(b"7777");
+        assert!(parse_message(&buf).is_err());
+
+        // section exceeding the end of the message
+        let mut buf = b"GRIB\x00\x01\x00\x02".to_vec();
+        buf.extend_from_slice(&25_u64.to_be_bytes());
+        buf.extend_from_slice(&[0, 0, 0, 21, 1]);
+        buf.extend_from_slice(&[0; 16]);
+        buf.extend_from_slice(b"7777");
+        assert!(parse_message(&buf).is_err());
+    }
+
+    #[test]
+    fn iter_submessages_up_to_corrupt_message() {
+        let msg = bytes_from_xz_file(
+            "testdata/Z__C_RJTD_20170221120000_MSG_GPV_Gll0p5deg_Pys_B20170221120000_F2017022115-2017022212_grib2.bin.xz",
+        );
+        let icon = std::fs::read(
+            "testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+        )
+        .unwrap();
+        let mut corrupt = icon.clone();
+        let len = corrupt.len();
+        corrupt[len - 4..].copy_from_slice(b"8888");
+        let buf = [msg.as_slice(), &icon, &corrupt, &icon].concat();
+
+        let results = iter_submessages(Cursor::new(buf.as_slice())).collect::<Vec<_>>();
+        assert_eq!(results.len(), 18);
+        assert!(results[..17].iter().all(Result::is_ok));
+        assert!(results[17].is_err());
+
+        let expected = from_slice(&msg).unwrap();
+        let submessages = results
+            .into_iter()
+            .filter_map(Result::ok)
+            .collect::<Vec<_>>();
+        assert_eq!(submessages[3].0.body, expected.sections()[0]);
+        let mut reader = Cursor::new(buf.as_slice());
+        assert_eq!(
+            submessages[3].decode(&mut reader).unwrap(),
+            expected.get_values(3).unwrap()
+        );
+        assert_eq!(submessages[16].identification().centre_id(), 78);
+    }
+
+    #[test]
+    fn iter_submessages_releasing_sections_of_completed_messages() {
+        let msg = bytes_from_xz_file(
+            "testdata/Z__C_RJTD_20170221120000_MSG_GPV_Gll0p5deg_Pys_B20170221120000_F2017022115-2017022212_grib2.bin.xz",
+        );
+        let icon = std::fs::read(
+            "testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+        )
+        .unwrap();
+        let buf = [msg.as_slice(), &icon, &icon].concat();
+        let sect_stream =
+            Grib2SectionStream::new(SeekableGrib2Reader::new(Cursor::new(buf.as_slice())));
+        let mut parser = Grib2SubmessageIndexStream::new(sect_stream).with_cacher();
+        let expected_indices = parser.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+        let expected_sections = parser.into_cached_sections();
+
+        let mut iter = iter_submessages(Cursor::new(buf.as_slice()));
+        let mut count = 0;
+        while let Some(result) = iter.next() {
+            let submessage = result.unwrap();
+            assert!(iter.parser.take_cached_sections().is_empty());
+            let expected = &expected_indices[count].sections;
+            assert_eq!(submessage.0.index, expected.0);
+            assert_eq!(submessage.7.index, expected.7);
+            assert_eq!(submessage.7.body, expected_sections[expected.7]);
+            count += 1;
+        }
+        assert_eq!(count, 18);
+    }
 }
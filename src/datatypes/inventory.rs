@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// A summary of a submessage, which is roughly equivalent to a line of the
+/// output of `wgrib2 -s`.
+///
+/// With the `serde` feature enabled, each entry can be serialized, e.g. as one
+/// JSON object per line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct InventoryEntry {
+    /// Index of the message in the data
+    pub message: usize,
+    /// Index of the submessage in the message
+    pub submessage: usize,
+    /// Offset in octets of the message (Section 0) containing the submessage
+    pub offset: usize,
+    /// Textual representation of the parameter (see Code Table 4.2)
+    pub parameter: String,
+    /// Textual representation of the first fixed surface
+    pub level: String,
+    /// Reference time of data, or `None` if it is missing
+    pub ref_time: Option<DateTime<Utc>>,
+    /// Textual representation of the forecast time
+    pub forecast_time: String,
+    /// Grid Definition Template Number
+    pub grid_tmpl_num: u16,
+}
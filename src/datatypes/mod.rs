@@ -1,3 +1,5 @@
+mod inventory;
+pub use inventory::*;
 mod product_attributes;
 pub use product_attributes::*;
 mod sections;
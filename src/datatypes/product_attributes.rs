@@ -1,3 +1,4 @@
+use chrono::{DateTime, Duration, Months, Utc};
 use std::convert::TryFrom;
 use std::fmt::{self, Display, Formatter};
 
@@ -20,6 +21,62 @@ impl ForecastTime {
         Self { unit, value }
     }
 
+    /// Returns the time obtained by adding the forecast time to `time`, or
+    /// `None` if the unit is missing or unknown.
+    pub fn add_to(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let value = self.value;
+        let unit = match &self.unit {
+            Name(unit) => unit,
+            Num(_) => return None,
+        };
+        let seconds =
+            |factor: i64| time.checked_add_signed(Duration::seconds(i64::from(value) * factor));
+        let months = |factor: u32| time.checked_add_months(Months::new(value.checked_mul(factor)?));
+        match unit {
+            Table4_4::Minute => seconds(60),
+            Table4_4::Hour => seconds(3600),
+            Table4_4::Day => seconds(86400),
+            Table4_4::Month => months(1),
+            Table4_4::Year => months(12),
+            Table4_4::Decade => months(120),
+            Table4_4::Normal => months(360),
+            Table4_4::Century => months(1200),
+            Table4_4::ThreeHours => seconds(10800),
+            Table4_4::SixHours => seconds(21600),
+            Table4_4::TwelveHours => seconds(43200),
+            Table4_4::Second => seconds(1),
+            Table4_4::Missing => None,
+        }
+    }
+
+    /// Returns the forecast time converted into hours, or `None` if the unit
+    /// is missing or unknown.
+    ///
+    /// Since months and years vary in length, 1 month is approximated as
+    /// 730.5 hours and 1 year as 8766 hours (365.25 days).
+    pub fn in_hours(&self) -> Option<f64> {
+        let unit = match &self.unit {
+            Name(unit) => unit,
+            Num(_) => return None,
+        };
+        let hours_per_unit = match unit {
+            Table4_4::Minute => 1. / 60.,
+            Table4_4::Hour => 1.,
+            Table4_4::Day => 24.,
+            Table4_4::Month => 730.5,
+            Table4_4::Year => 8766.,
+            Table4_4::Decade => 87660.,
+            Table4_4::Normal => 262980.,
+            Table4_4::Century => 876600.,
+            Table4_4::ThreeHours => 3.,
+            Table4_4::SixHours => 6.,
+            Table4_4::TwelveHours => 12.,
+            Table4_4::Second => 1. / 3600.,
+            Table4_4::Missing => return None,
+        };
+        Some(f64::from(self.value) * hours_per_unit)
+    }
+
     pub fn describe(&self) -> (String, String) {
         let unit = match &self.unit {
             Name(unit) => format!("{:#?}", unit),
@@ -49,6 +106,41 @@ impl Display for ForecastTime {
     }
 }
 
+/// Attributes of an individual ensemble forecast, found in Product Definition
+/// Templates 4.1 and 4.11.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EnsembleForecast {
+    /// Type of ensemble forecast (see Code Table 4.6)
+    pub ensemble_type: u8,
+    /// Perturbation number
+    pub perturbation_number: u8,
+    /// Number of forecasts in ensemble
+    pub number_of_forecasts: u8,
+}
+
+/// Identification of the process which generated data, found in most
+/// Product Definition Templates.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GeneratingProcess {
+    /// Type of generating process (see Code Table 4.3)
+    pub process_type: u8,
+    /// Background generating process identifier (defined by originating
+    /// centre)
+    pub background_process_id: u8,
+    /// Analysis or forecast generating process identifier, i.e. the model
+    /// (defined by originating centre)
+    pub process_id: u8,
+}
+
+impl GeneratingProcess {
+    /// Returns a textual representation of the type of generating process.
+    pub fn describe_type(&self) -> String {
+        CodeTable4_3
+            .lookup(usize::from(self.process_type))
+            .to_string()
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct FixedSurface {
     /// Use [CodeTable4_5] to get textual representation.
@@ -75,6 +167,12 @@ impl FixedSurface {
         }
     }
 
+    /// Checks if the type of surface is missing (255), in which case the
+    /// scale factor and the scaled value are meaningless.
+    pub fn is_missing(&self) -> bool {
+        self.surface_type == 255
+    }
+
     /// Checks if the scale factor should be treated as missing.
     pub fn scale_factor_is_nan(&self) -> bool {
         // Handle as NaN if all bits are 1. Note that this is i8::MIN + 1 and not
@@ -89,6 +187,37 @@ impl FixedSurface {
         self.scaled_value == i32::MIN + 1
     }
 
+    /// Returns a human-readable representation of the level such as "500
+    /// hPa", "2 m above ground" and "surface".
+    pub fn display_level(&self) -> String {
+        let value = self.value();
+        match self.surface_type {
+            1 => return "surface".to_owned(),
+            8 => return "top of atmosphere".to_owned(),
+            101 => return "mean sea level".to_owned(),
+            _ => {}
+        }
+        let name = || {
+            lookup_fixed_surface(self.surface_type)
+                .map(str::to_owned)
+                .unwrap_or_else(|| format!("surface type {}", self.surface_type))
+        };
+        if value.is_nan() {
+            return name();
+        }
+        match self.surface_type {
+            100 => format!("{} hPa", value / 100.),
+            102 => format!("{} m above mean sea level", value),
+            103 => format!("{} m above ground", value),
+            104 => format!("{} sigma level", value),
+            105 => format!("hybrid level {}", value),
+            106 => format!("{} m below land surface", value),
+            107 => format!("{} K isentropic level", value),
+            108 => format!("{} hPa above ground", value / 100.),
+            _ => format!("{} {}", name(), value),
+        }
+    }
+
     pub fn describe(&self) -> (String, String, String) {
         let stype = CodeTable4_5
             .lookup(usize::from(self.surface_type))
@@ -106,3 +235,122 @@ impl FixedSurface {
         (stype, scale_factor, scaled_value)
     }
 }
+
+/// Vertical level of a product, which is either a single surface or a layer
+/// between two surfaces.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Level {
+    /// A single surface, where the second fixed surface is missing
+    Surface(FixedSurface),
+    /// A layer between the first and second fixed surfaces
+    Layer(FixedSurface, FixedSurface),
+}
+
+impl Level {
+    /// Creates a level from the first and second fixed surfaces, ignoring the
+    /// second one if its type is missing.
+    pub fn from_surfaces(first: FixedSurface, second: FixedSurface) -> Self {
+        if second.is_missing() {
+            Self::Surface(first)
+        } else {
+            Self::Layer(first, second)
+        }
+    }
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Surface(surface) => write!(f, "{}", surface.display_level()),
+            Self::Layer(first, second) => {
+                write!(f, "{} - {}", first.display_level(), second.display_level())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::TimeZone;
+
+    #[test]
+    fn forecast_time_added_to_time() {
+        let time = Utc.with_ymd_and_hms(2021, 1, 31, 0, 0, 0).unwrap();
+        assert_eq!(
+            ForecastTime::from_numbers(0, 90).add_to(time),
+            Some(Utc.with_ymd_and_hms(2021, 1, 31, 1, 30, 0).unwrap())
+        );
+        assert_eq!(
+            ForecastTime::from_numbers(11, 2).add_to(time),
+            Some(Utc.with_ymd_and_hms(2021, 1, 31, 12, 0, 0).unwrap())
+        );
+        assert_eq!(
+            ForecastTime::from_numbers(3, 1).add_to(time),
+            Some(Utc.with_ymd_and_hms(2021, 2, 28, 0, 0, 0).unwrap())
+        );
+        assert_eq!(ForecastTime::from_numbers(255, 1).add_to(time), None);
+        assert_eq!(ForecastTime::from_numbers(254, 1).add_to(time), None);
+    }
+
+    #[test]
+    fn forecast_time_in_hours() {
+        assert_eq!(ForecastTime::from_numbers(0, 90).in_hours(), Some(1.5));
+        assert_eq!(ForecastTime::from_numbers(1, 6).in_hours(), Some(6.));
+        assert_eq!(ForecastTime::from_numbers(2, 3).in_hours(), Some(72.));
+        assert_eq!(ForecastTime::from_numbers(3, 2).in_hours(), Some(1461.));
+        assert_eq!(ForecastTime::from_numbers(11, 2).in_hours(), Some(12.));
+        assert_eq!(ForecastTime::from_numbers(255, 1).in_hours(), None);
+        assert_eq!(ForecastTime::from_numbers(254, 1).in_hours(), None);
+    }
+
+    #[test]
+    fn fixed_surface_display_level() {
+        assert_eq!(FixedSurface::new(100, 0, 50000).display_level(), "500 hPa");
+        assert_eq!(FixedSurface::new(100, -2, 925).display_level(), "925 hPa");
+        assert_eq!(FixedSurface::new(100, 0, 97500).display_level(), "975 hPa");
+        assert_eq!(
+            FixedSurface::new(103, 0, 2).display_level(),
+            "2 m above ground"
+        );
+        assert_eq!(
+            FixedSurface::new(103, 1, 105).display_level(),
+            "10.5 m above ground"
+        );
+        assert_eq!(FixedSurface::new(1, 0, 0).display_level(), "surface");
+        assert_eq!(
+            FixedSurface::new(1, i8::MIN + 1, i32::MIN + 1).display_level(),
+            "surface"
+        );
+        assert_eq!(
+            FixedSurface::new(100, i8::MIN + 1, i32::MIN + 1).display_level(),
+            "Isobaric surface"
+        );
+        assert_eq!(
+            FixedSurface::new(160, 0, 10).display_level(),
+            "Depth below sea level 10"
+        );
+        assert_eq!(
+            FixedSurface::new(250, 0, 10).display_level(),
+            "surface type 250 10"
+        );
+    }
+
+    #[test]
+    fn level_from_surfaces() {
+        let level = Level::from_surfaces(
+            FixedSurface::new(101, 0, 0),
+            FixedSurface::new(255, i8::MIN + 1, i32::MIN + 1),
+        );
+        assert_eq!(level, Level::Surface(FixedSurface::new(101, 0, 0)));
+        assert_eq!(level.to_string(), "mean sea level");
+
+        let level = Level::from_surfaces(
+            FixedSurface::new(100, 0, 50000),
+            FixedSurface::new(100, 0, 85000),
+        );
+        assert!(matches!(level, Level::Layer(_, _)));
+        assert_eq!(level.to_string(), "500 hPa - 850 hPa");
+    }
+}
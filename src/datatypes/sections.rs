@@ -2,7 +2,9 @@ use chrono::{offset::TimeZone, DateTime, Utc};
 use std::convert::TryInto;
 use std::slice::Iter;
 
-use crate::codetables::SUPPORTED_PROD_DEF_TEMPLATE_NUMBERS;
+use crate::codetables::{
+    lookup_data_type, lookup_generating_process, SUPPORTED_PROD_DEF_TEMPLATE_NUMBERS,
+};
 use crate::datatypes::*;
 use crate::error::*;
 use crate::utils::{read_as, GribInt};
@@ -30,7 +32,7 @@ impl Identification {
         }
     }
 
-    pub fn iter(&self) -> Iter<u8> {
+    pub fn iter(&self) -> Iter<'_, u8> {
         self.payload.iter()
     }
 
@@ -68,20 +70,29 @@ impl Identification {
         self.payload[6]
     }
 
-    /// Reference time of data
+    /// Reference time of data, or `None` if it is missing or invalid
     #[inline]
-    pub fn ref_time(&self) -> DateTime<Utc> {
+    pub fn ref_time(&self) -> Option<DateTime<Utc>> {
+        if self.ref_time_is_missing() {
+            return None;
+        }
         let payload = &self.payload;
-        Utc.ymd(
+        Utc.with_ymd_and_hms(
             read_as!(u16, payload, 7).into(),
             self.payload[9].into(),
             self.payload[10].into(),
-        )
-        .and_hms(
             self.payload[11].into(),
             self.payload[12].into(),
             self.payload[13].into(),
         )
+        .single()
+    }
+
+    /// Checks if the reference time is marked as missing, where all octets
+    /// are set to 1 or 0.
+    pub fn ref_time_is_missing(&self) -> bool {
+        let octets = &self.payload[7..14];
+        octets.iter().all(|b| *b == 0xff) || octets.iter().all(|b| *b == 0)
     }
 
     /// Production status of processed data in this GRIB message
@@ -91,11 +102,42 @@ impl Identification {
         self.payload[14]
     }
 
+    /// Checks if the production status is operational products (code 0 in
+    /// Code Table 1.3).
+    pub fn is_operational(&self) -> bool {
+        self.prod_status() == 0
+    }
+
+    /// Checks if the production status is operational test products (code 1
+    /// in Code Table 1.3).
+    pub fn is_test(&self) -> bool {
+        self.prod_status() == 1
+    }
+
+    /// Checks if the production status is research products (code 2 in Code
+    /// Table 1.3).
+    pub fn is_research(&self) -> bool {
+        self.prod_status() == 2
+    }
+
     /// Type of processed data in this GRIB message (see Code Table 1.4)
     #[inline]
     pub fn data_type(&self) -> u8 {
         self.payload[15]
     }
+
+    /// Returns the name of the type of processed data such as "Forecast
+    /// products", or `None` if the code is not assigned.
+    pub fn data_type_name(&self) -> Option<&'static str> {
+        lookup_data_type(self.data_type())
+    }
+
+    /// Octets following the standard fields (octet 22 and later), which
+    /// are reserved for use by originating centres
+    #[inline]
+    pub fn extra(&self) -> &[u8] {
+        &self.payload[16..]
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -108,7 +150,7 @@ impl LocalUse {
         Self { payload: slice }
     }
 
-    pub fn iter(&self) -> Iter<u8> {
+    pub fn iter(&self) -> Iter<'_, u8> {
         self.payload.iter()
     }
 }
@@ -128,21 +170,58 @@ impl GridDefinition {
         }
     }
 
-    pub fn iter(&self) -> Iter<u8> {
+    pub fn iter(&self) -> Iter<'_, u8> {
         self.payload.iter()
     }
 
+    /// Source of grid definition (see Code Table 3.0)
+    pub fn source(&self) -> u8 {
+        self.payload[0]
+    }
+
     /// Number of data points
     pub fn num_points(&self) -> u32 {
         let payload = &self.payload;
         read_as!(u32, payload, 1)
     }
 
+    /// Number of octets for optional list of numbers defining number of points
+    pub fn num_octets_for_optional_list(&self) -> u8 {
+        self.payload[5]
+    }
+
+    /// Interpretation of list of numbers defining number of points (see Code
+    /// Table 3.11)
+    pub fn optional_list_interpretation(&self) -> u8 {
+        self.payload[6]
+    }
+
+    /// Checks if a list of numbers defining number of points, which is used
+    /// for quasi-regular grids, is appended to the Grid Definition Template.
+    pub fn has_optional_point_list(&self) -> bool {
+        self.num_octets_for_optional_list() != 0
+            && !matches!(self.optional_list_interpretation(), 0 | 255)
+    }
+
     /// Grid Definition Template Number
     pub fn grid_tmpl_num(&self) -> u16 {
         let payload = &self.payload;
         read_as!(u16, payload, 7)
     }
+
+    /// Checks if the grid is a predetermined one defined by the originating
+    /// centre (source 1 in Code Table 3.0), in which case the Grid
+    /// Definition Template is not supplied.
+    pub fn is_predetermined_grid(&self) -> bool {
+        self.source() == 1
+    }
+
+    /// Number of the predetermined grid, which is stored in place of the
+    /// Grid Definition Template Number, or `None` if the grid is not a
+    /// predetermined one.
+    pub fn predetermined_grid_number(&self) -> Option<u16> {
+        self.is_predetermined_grid().then(|| self.grid_tmpl_num())
+    }
 }
 
 const START_OF_PROD_TEMPLATE: usize = 4;
@@ -162,7 +241,7 @@ impl ProdDefinition {
         }
     }
 
-    pub fn iter(&self) -> Iter<u8> {
+    pub fn iter(&self) -> Iter<'_, u8> {
         self.payload.iter()
     }
 
@@ -209,8 +288,37 @@ impl ProdDefinition {
     /// Use [CodeTable4_3](crate::codetables::CodeTable4_3) to get textual
     /// representation of the returned numerical value.
     pub fn generating_process(&self) -> Option<u8> {
+        let index = self.generating_process_index()?;
+        self.payload.get(START_OF_PROD_TEMPLATE + index).copied()
+    }
+
+    /// Returns the name of the type of generating process in Code Table 4.3,
+    /// or `None` if the template has no type of generating process or the
+    /// code is not assigned.
+    pub fn generating_process_name(&self) -> Option<&'static str> {
+        self.generating_process()
+            .and_then(lookup_generating_process)
+    }
+
+    /// Returns the type of generating process along with the background and
+    /// analysis or forecast generating process identifiers, the latter of
+    /// which identifies the model in the originating centre.
+    pub fn generating_process_info(&self) -> Option<GeneratingProcess> {
+        let start = START_OF_PROD_TEMPLATE + self.generating_process_index()?;
+        let octets = self.payload.get(start..start + 3)?;
+        Some(GeneratingProcess {
+            process_type: octets[0],
+            background_process_id: octets[1],
+            process_id: octets[2],
+        })
+    }
+
+    // Returns the position of the type of generating process in the template,
+    // which is followed by the background and analysis or forecast generating
+    // process identifiers in all templates.
+    fn generating_process_index(&self) -> Option<usize> {
         if self.template_supported() {
-            let index = match self.prod_tmpl_num() {
+            match self.prod_tmpl_num() {
                 0..=39 => Some(2),
                 40..=43 => Some(4),
                 44..=46 => Some(15),
@@ -235,13 +343,68 @@ impl ProdDefinition {
                 254 => Some(2),
                 1000..=1101 => Some(2),
                 _ => None,
-            }?;
-            self.payload.get(START_OF_PROD_TEMPLATE + index).copied()
+            }
         } else {
             None
         }
     }
 
+    /// Returns attributes of the individual ensemble forecast for Templates
+    /// 4.1 and 4.11.
+    pub fn ensemble(&self) -> Option<EnsembleForecast> {
+        match self.prod_tmpl_num() {
+            1 | 11 => {
+                let start = START_OF_PROD_TEMPLATE + 25;
+                let octets = self.payload.get(start..start + 3)?;
+                Some(EnsembleForecast {
+                    ensemble_type: octets[0],
+                    perturbation_number: octets[1],
+                    number_of_forecasts: octets[2],
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the percentile value (from 0 to 100) for Templates 4.6 and
+    /// 4.10.
+    pub fn percentile(&self) -> Option<u8> {
+        match self.prod_tmpl_num() {
+            6 | 10 => self.payload.get(START_OF_PROD_TEMPLATE + 25).copied(),
+            _ => None,
+        }
+    }
+
+    /// Returns the end of the overall time interval for Templates 4.8 to
+    /// 4.12, which hold statistically processed values.
+    pub fn end_of_overall_time_interval(&self) -> Option<DateTime<Utc>> {
+        let index = match self.prod_tmpl_num() {
+            8 => 25,
+            9 => 38,
+            10 => 26,
+            11 => 28,
+            12 => 27,
+            _ => return None,
+        };
+        let start = START_OF_PROD_TEMPLATE + index;
+        let octets = self.payload.get(start..start + 7)?;
+        Utc.with_ymd_and_hms(
+            read_as!(u16, octets, 0).into(),
+            octets[2].into(),
+            octets[3].into(),
+            octets[4].into(),
+            octets[5].into(),
+            octets[6].into(),
+        )
+        .single()
+    }
+
+    /// Returns the forecast time converted into hours. See
+    /// [`ForecastTime::in_hours`] for details.
+    pub fn forecast_time_in_hours(&self) -> Option<f64> {
+        self.forecast_time().and_then(|ft| ft.in_hours())
+    }
+
     /// Returns the unit and value of the forecast time wrapped by `Option`.
     /// Use [CodeTable4_4](crate::codetables::CodeTable4_4) to get textual
     /// representation of the unit.
@@ -276,13 +439,27 @@ impl ProdDefinition {
             let unit = self.payload.get(unit_index).copied();
             let start = unit_index + 1;
             let end = unit_index + 5;
-            let time = u32::from_be_bytes(self.payload[start..end].try_into().unwrap());
+            let time = u32::from_be_bytes(self.payload.get(start..end)?.try_into().unwrap());
             unit.map(|v| ForecastTime::from_numbers(v, time))
         } else {
             None
         }
     }
 
+    /// Returns a human-readable representation of the first fixed surface.
+    /// See [`FixedSurface::display_level`] for details.
+    pub fn display_level(&self) -> Option<String> {
+        self.fixed_surfaces()
+            .map(|(first, _)| first.display_level())
+    }
+
+    /// Returns the vertical level built from the fixed surfaces, which is a
+    /// single surface if the type of the second fixed surface is missing.
+    pub fn level(&self) -> Option<Level> {
+        self.fixed_surfaces()
+            .map(|(first, second)| Level::from_surfaces(first, second))
+    }
+
     /// Returns a tuple of two [FixedSurface], wrapped by `Option`.
     pub fn fixed_surfaces(&self) -> Option<(FixedSurface, FixedSurface)> {
         if self.template_supported() {
@@ -327,7 +504,7 @@ impl ProdDefinition {
         let start = index + 2;
         let end = index + 6;
         let scaled_value =
-            u32::from_be_bytes(self.payload[start..end].try_into().unwrap()).as_grib_int();
+            u32::from_be_bytes(self.payload.get(start..end)?.try_into().unwrap()).as_grib_int();
         surface_type
             .zip(scale_factor)
             .map(|(stype, factor)| FixedSurface::new(stype, factor, scaled_value))
@@ -349,7 +526,7 @@ impl ReprDefinition {
         }
     }
 
-    pub fn iter(&self) -> Iter<u8> {
+    pub fn iter(&self) -> Iter<'_, u8> {
         self.payload.iter()
     }
 
@@ -366,18 +543,419 @@ impl ReprDefinition {
         let payload = &self.payload;
         read_as!(u16, payload, 4)
     }
+
+    /// Parameters of simple packing, or `None` if the template is not
+    /// Template 5.0
+    pub fn simple_packing(&self) -> Option<SimplePackingParams> {
+        match self.repr_tmpl_num() {
+            0 => SimplePackingParams::from_payload(&self.payload),
+            _ => None,
+        }
+    }
+
+    /// Parameters of JPEG 2000 code stream format, or `None` if the template
+    /// is not Template 5.40
+    pub fn jpeg2000_packing(&self) -> Option<Jpeg2000PackingParams> {
+        match self.repr_tmpl_num() {
+            40 => Jpeg2000PackingParams::from_payload(&self.payload),
+            _ => None,
+        }
+    }
+
+    /// Parameters of complex packing, or `None` if the template is not
+    /// Template 5.2 or 5.3
+    pub fn complex_packing(&self) -> Option<ComplexPackingParams> {
+        match self.repr_tmpl_num() {
+            2 | 3 => ComplexPackingParams::from_payload(&self.payload),
+            _ => None,
+        }
+    }
+
+    /// Missing value management used in complex packing (see Code Table 5.5),
+    /// or `None` if the template is not Template 5.2 or 5.3
+    pub fn missing_value_management(&self) -> Option<u8> {
+        match self.repr_tmpl_num() {
+            2 | 3 => self.payload.get(17).copied(),
+            _ => None,
+        }
+    }
+
+    /// Primary and secondary missing value substitutes used in complex
+    /// packing, or `None` if the template is not Template 5.2 or 5.3
+    ///
+    /// The substitutes are interpreted according to the type of original
+    /// field values (see Code Table 5.1).
+    pub fn missing_value_substitutes(&self) -> Option<(f32, f32)> {
+        self.missing_value_management()?;
+        let payload = self.payload.get(..26)?;
+        let substitutes = match payload[15] {
+            0 => (read_as!(f32, payload, 18), read_as!(f32, payload, 22)),
+            1 => (
+                read_as!(u32, payload, 18).as_grib_int() as f32,
+                read_as!(u32, payload, 22).as_grib_int() as f32,
+            ),
+            _ => return None,
+        };
+        Some(substitutes)
+    }
+}
+
+/// Returns `true` if the Data Representation Template specified by `num`
+/// compresses data with a general-purpose codec, i.e. JPEG 2000, PNG or CCSDS.
+///
+/// Templates packing values as they are, such as simple packing and IEEE
+/// floating point data, are not regarded as compressed.
+pub fn representation_is_compressed(num: u16) -> bool {
+    // 40000 and 40010 are the numbers used for JPEG 2000 and PNG before the
+    // assignment of 40 and 41.
+    matches!(num, 40 | 41 | 42 | 40000 | 40010)
+}
+
+/// Information of a message read from Section 0 and Section 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageHeader<'a> {
+    /// GRIB edition number
+    pub edition: u8,
+    /// Discipline - GRIB Master Table Number (see Code Table 0.0)
+    pub discipline: u8,
+    /// Identification Section
+    pub identification: &'a Identification,
+}
+
+/// Parameters of simple packing (octets 12-21 of Section 5), which are
+/// shared by Templates 5.0, 5.2, 5.3, 5.40 and 5.41
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimplePackingParams {
+    /// Reference value (R)
+    pub ref_val: f32,
+    /// Binary scale factor (E)
+    pub binary_scale: i16,
+    /// Decimal scale factor (D)
+    pub decimal_scale: i16,
+    /// Number of bits used for each packed value
+    pub nbit: u8,
+    /// Type of original field values (see Code Table 5.1)
+    pub value_type: u8,
+}
+
+impl SimplePackingParams {
+    const START: usize = 6;
+    const SIZE: usize = 10;
+
+    /// Reads parameters from the payload of Section 5, or returns `None` if
+    /// the payload is too short.
+    pub(crate) fn from_payload(payload: &[u8]) -> Option<Self> {
+        let buf = payload.get(Self::START..Self::START + Self::SIZE)?;
+        Some(Self {
+            ref_val: read_as!(f32, buf, 0),
+            binary_scale: read_as!(u16, buf, 4).as_grib_int(),
+            decimal_scale: read_as!(u16, buf, 6).as_grib_int(),
+            nbit: buf[8],
+            value_type: buf[9],
+        })
+    }
+
+    /// Returns the octets 12-21 of Section 5 representing the parameters.
+    pub fn to_bytes(&self) -> [u8; 10] {
+        fn to_grib_u16(value: i16) -> u16 {
+            if value < 0 {
+                0x8000 | value.unsigned_abs()
+            } else {
+                value as u16
+            }
+        }
+
+        let mut buf = [0; Self::SIZE];
+        buf[0..4].copy_from_slice(&self.ref_val.to_be_bytes());
+        buf[4..6].copy_from_slice(&to_grib_u16(self.binary_scale).to_be_bytes());
+        buf[6..8].copy_from_slice(&to_grib_u16(self.decimal_scale).to_be_bytes());
+        buf[8] = self.nbit;
+        buf[9] = self.value_type;
+        buf
+    }
+}
+
+/// Parameters of JPEG 2000 code stream format (Template 5.40)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Jpeg2000PackingParams {
+    /// Parameters shared with simple packing
+    pub simple: SimplePackingParams,
+    /// Type of compression (see Code Table 5.40)
+    pub compression_type: u8,
+    /// Target compression ratio M:1 (with respect to the bit-depth specified
+    /// in `simple.nbit`), which is 255 (missing) for lossless compression
+    pub target_compression_ratio: u8,
+}
+
+impl Jpeg2000PackingParams {
+    /// Reads parameters from the payload of Section 5, or returns `None` if
+    /// the payload is too short.
+    pub(crate) fn from_payload(payload: &[u8]) -> Option<Self> {
+        let simple = SimplePackingParams::from_payload(payload)?;
+        let buf = payload.get(16..18)?;
+        Some(Self {
+            simple,
+            compression_type: buf[0],
+            target_compression_ratio: buf[1],
+        })
+    }
+
+    /// Returns `true` if the compression is lossless.
+    pub fn is_lossless(&self) -> bool {
+        self.compression_type == 0
+    }
+}
+
+/// Parameters of complex packing (Templates 5.2 and 5.3)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComplexPackingParams {
+    /// Parameters shared with simple packing
+    pub simple: SimplePackingParams,
+    /// Group splitting method used (see Code Table 5.4)
+    pub group_splitting_method: u8,
+    /// Missing value management used (see Code Table 5.5)
+    pub missing_value_management: u8,
+    /// Primary missing value substitute as raw octets, which are interpreted
+    /// according to `simple.value_type`. See also
+    /// [`ReprDefinition::missing_value_substitutes`].
+    pub primary_missing_value: u32,
+    /// Secondary missing value substitute as raw octets
+    pub secondary_missing_value: u32,
+    /// NG - number of groups of data values
+    pub num_groups: u32,
+    /// Reference for group widths
+    pub group_width_ref: u8,
+    /// Number of bits used for the group widths
+    pub group_width_nbit: u8,
+    /// Reference for group lengths
+    pub group_len_ref: u32,
+    /// Length increment for the group lengths
+    pub group_len_inc: u8,
+    /// True length of last group
+    pub group_len_last: u32,
+    /// Number of bits used for the scaled group lengths
+    pub group_len_nbit: u8,
+    /// Parameters of spatial differencing, which are present only in
+    /// Template 5.3
+    pub spatial_differencing: Option<SpatialDifferencingParams>,
+}
+
+impl ComplexPackingParams {
+    /// Reads parameters from the payload of Section 5, or returns `None` if
+    /// the payload is too short.
+    pub(crate) fn from_payload(payload: &[u8]) -> Option<Self> {
+        let simple = SimplePackingParams::from_payload(payload)?;
+        let buf = payload.get(..42)?;
+        let spatial_differencing = match read_as!(u16, buf, 4) {
+            3 => {
+                let buf = payload.get(42..44)?;
+                Some(SpatialDifferencingParams {
+                    order: buf[0],
+                    num_extra_octets: buf[1],
+                })
+            }
+            _ => None,
+        };
+        Some(Self {
+            simple,
+            group_splitting_method: buf[16],
+            missing_value_management: buf[17],
+            primary_missing_value: read_as!(u32, buf, 18),
+            secondary_missing_value: read_as!(u32, buf, 22),
+            num_groups: read_as!(u32, buf, 26),
+            group_width_ref: buf[30],
+            group_width_nbit: buf[31],
+            group_len_ref: read_as!(u32, buf, 32),
+            group_len_inc: buf[36],
+            group_len_last: read_as!(u32, buf, 37),
+            group_len_nbit: buf[41],
+            spatial_differencing,
+        })
+    }
+}
+
+/// Parameters of spatial differencing in complex packing (Template 5.3)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SpatialDifferencingParams {
+    /// Order of spatial differencing (see Code Table 5.6)
+    pub order: u8,
+    /// Number of octets required in the data section to specify extra
+    /// descriptors needed for spatial differencing
+    pub num_extra_octets: u8,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct BitMap {
     /// Bit-map indicator
     pub bitmap_indicator: u8,
+    /// Bit-map following the indicator, which is present only if the
+    /// indicator is 0 (a bit-map applies to this product and is specified in
+    /// this section)
+    pub bitmap: Option<Vec<u8>>,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn identification_with_extra_octets() {
+        let payload = vec![
+            0x00, 0x22, 0x00, 0x00, 0x02, 0x01, 0x01, 0x07, 0xe1, 0x02, 0x15, 0x0c, 0x00, 0x00,
+            0x00, 0x01, 0xde, 0xad, 0xbe, 0xef,
+        ];
+        let data = Identification::from_payload(payload.into_boxed_slice()).unwrap();
+        assert_eq!(data.centre_id(), 34);
+        assert_eq!(data.data_type(), 1);
+        assert_eq!(
+            data.ref_time(),
+            Some(Utc.with_ymd_and_hms(2017, 2, 21, 12, 0, 0).unwrap())
+        );
+        assert_eq!(data.extra(), &[0xde, 0xad, 0xbe, 0xef]);
+        assert!(data.is_operational());
+        assert!(!data.is_test());
+        assert!(!data.is_research());
+        assert_eq!(data.data_type_name(), Some("Forecast products"));
+
+        let payload = vec![
+            0x00, 0x22, 0x00, 0x00, 0x02, 0x01, 0x01, 0x07, 0xe1, 0x02, 0x15, 0x0c, 0x00, 0x00,
+            0x00, 0x01,
+        ];
+        let data = Identification::from_payload(payload.into_boxed_slice()).unwrap();
+        assert!(data.extra().is_empty());
+    }
+
+    #[test]
+    fn identification_status_and_type() {
+        let mut payload = vec![
+            0x00, 0x22, 0x00, 0x00, 0x02, 0x01, 0x01, 0x07, 0xe1, 0x02, 0x15, 0x0c, 0x00, 0x00,
+            0x02, 0xff,
+        ];
+        let data = Identification::from_payload(payload.clone().into_boxed_slice()).unwrap();
+        assert!(!data.is_operational());
+        assert!(data.is_research());
+        assert_eq!(data.data_type_name(), None);
+
+        payload[14] = 0x01;
+        payload[15] = 0x00;
+        let data = Identification::from_payload(payload.into_boxed_slice()).unwrap();
+        assert!(data.is_test());
+        assert_eq!(data.data_type_name(), Some("Analysis products"));
+    }
+
+    #[test]
+    fn identification_with_missing_ref_time() {
+        let payload = vec![
+            0x00, 0x22, 0x00, 0x00, 0x02, 0x01, 0x01, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+            0x00, 0x01,
+        ];
+        let data = Identification::from_payload(payload.into_boxed_slice()).unwrap();
+        assert!(data.ref_time_is_missing());
+        assert_eq!(data.ref_time(), None);
+
+        let payload = vec![
+            0x00, 0x22, 0x00, 0x00, 0x02, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x01,
+        ];
+        let data = Identification::from_payload(payload.into_boxed_slice()).unwrap();
+        assert!(data.ref_time_is_missing());
+        assert_eq!(data.ref_time(), None);
+
+        // invalid but not marked as missing
+        let payload = vec![
+            0x00, 0x22, 0x00, 0x00, 0x02, 0x01, 0x01, 0x07, 0xe1, 0x02, 0x1e, 0x00, 0x00, 0x00,
+            0x00, 0x01,
+        ];
+        let data = Identification::from_payload(payload.into_boxed_slice()).unwrap();
+        assert!(!data.ref_time_is_missing());
+        assert_eq!(data.ref_time(), None);
+    }
+
+    #[test]
+    fn repr_definition_missing_value_management() {
+        let mut payload = vec![0; 43];
+        payload[3] = 0x10; // number of data points
+        payload[5] = 3; // template number
+        payload[17] = 1; // missing value management
+        payload[18..22].copy_from_slice(&9999_f32.to_be_bytes());
+        payload[22..26].copy_from_slice(&(-999_f32).to_be_bytes());
+        let data = ReprDefinition::from_payload(payload.clone().into_boxed_slice()).unwrap();
+        assert_eq!(data.missing_value_management(), Some(1));
+        assert_eq!(data.missing_value_substitutes(), Some((9999.0, -999.0)));
+
+        payload[15] = 1; // original field values are integers
+        payload[18..22].copy_from_slice(&[0x80, 0x00, 0x03, 0xe7]);
+        let data = ReprDefinition::from_payload(payload.clone().into_boxed_slice()).unwrap();
+        assert_eq!(data.missing_value_substitutes().unwrap().0, -999.0);
+
+        payload[5] = 0;
+        let data = ReprDefinition::from_payload(payload.into_boxed_slice()).unwrap();
+        assert_eq!(data.missing_value_management(), None);
+        assert_eq!(data.missing_value_substitutes(), None);
+    }
+
+    #[test]
+    fn simple_packing_params_roundtrip() {
+        let payload = vec![
+            0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x35, 0x3e, 0x6b, 0xf6, 0x80, 0x1a, 0x00, 0x02,
+            0x10, 0x00,
+        ];
+        let data = ReprDefinition::from_payload(payload.clone().into_boxed_slice()).unwrap();
+        let params = data.simple_packing().unwrap();
+        assert_eq!(
+            params,
+            SimplePackingParams {
+                ref_val: f32::from_be_bytes([0x35, 0x3e, 0x6b, 0xf6]),
+                binary_scale: -26,
+                decimal_scale: 2,
+                nbit: 16,
+                value_type: 0,
+            }
+        );
+        assert_eq!(&params.to_bytes()[..], &payload[6..]);
+
+        let mut payload = payload;
+        payload[5] = 3;
+        let data = ReprDefinition::from_payload(payload.into_boxed_slice()).unwrap();
+        assert_eq!(data.simple_packing(), None);
+    }
+
+    #[test]
+    fn compressed_representations() {
+        assert!(!representation_is_compressed(0));
+        assert!(!representation_is_compressed(4));
+        assert!(representation_is_compressed(40));
+        assert!(representation_is_compressed(41));
+        assert!(representation_is_compressed(42));
+    }
+
+    #[test]
+    fn grid_definition_header() {
+        let data =
+            GridDefinition::from_payload(vec![0, 0, 0, 0x10, 0x00, 0, 0, 0, 0].into_boxed_slice())
+                .unwrap();
+        assert_eq!(data.source(), 0);
+        assert_eq!(data.num_points(), 4096);
+        assert_eq!(data.num_octets_for_optional_list(), 0);
+        assert_eq!(data.optional_list_interpretation(), 0);
+        assert!(!data.has_optional_point_list());
+
+        let data =
+            GridDefinition::from_payload(vec![0, 0, 0, 0x10, 0x00, 2, 1, 0, 0].into_boxed_slice())
+                .unwrap();
+        assert_eq!(data.num_octets_for_optional_list(), 2);
+        assert_eq!(data.optional_list_interpretation(), 1);
+        assert!(data.has_optional_point_list());
+        assert!(!data.is_predetermined_grid());
+        assert_eq!(data.predetermined_grid_number(), None);
+
+        let data =
+            GridDefinition::from_payload(vec![1, 0, 0, 0x10, 0x00, 0, 0, 0, 7].into_boxed_slice())
+                .unwrap();
+        assert!(data.is_predetermined_grid());
+        assert_eq!(data.predetermined_grid_number(), Some(7));
+    }
+
     #[test]
     fn prod_definition_parameters() {
         let data = ProdDefinition::from_payload(
@@ -402,5 +980,125 @@ mod tests {
                 FixedSurface::new(255, -127, -2147483647)
             ))
         );
+        assert_eq!(
+            data.level(),
+            Some(Level::Surface(FixedSurface::new(1, -127, -2147483647)))
+        );
+    }
+
+    #[test]
+    fn prod_definition_percentile() {
+        let mut payload = vec![
+            0, 0, 0, 10, 0, 0, 2, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 255, 255, 255,
+            255, 255, 255, 90,
+        ];
+        payload.extend_from_slice(&[0x07, 0xe5, 5, 19, 0, 0, 0]); // 2021-05-19T00:00:00Z
+        payload.extend_from_slice(&[1, 0, 0, 0, 0, 1, 2, 1, 0, 0, 0, 24, 255, 0, 0, 0, 0]);
+        let data = ProdDefinition::from_payload(payload.clone().into_boxed_slice()).unwrap();
+
+        assert_eq!(data.percentile(), Some(90));
+        assert_eq!(
+            data.end_of_overall_time_interval(),
+            Some(Utc.with_ymd_and_hms(2021, 5, 19, 0, 0, 0).unwrap())
+        );
+        assert_eq!(data.parameter_category(), Some(0));
+        assert_eq!(data.forecast_time(), Some(ForecastTime::from_numbers(1, 0)));
+
+        // Template 4.8 has the same layout without the percentile value
+        payload[3] = 8;
+        payload.remove(29);
+        let data = ProdDefinition::from_payload(payload.into_boxed_slice()).unwrap();
+        assert_eq!(data.percentile(), None);
+        assert_eq!(
+            data.end_of_overall_time_interval(),
+            Some(Utc.with_ymd_and_hms(2021, 5, 19, 0, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn prod_definition_level_of_layer() {
+        let data = ProdDefinition::from_payload(
+            vec![
+                0, 0, 0, 0, 2, 0, 3, 5, 255, 0, 0, 0, 0, 0, 0, 0, 0, 106, 0, 0, 0, 0, 0, 106, 1, 0,
+                0, 0, 1,
+            ]
+            .into_boxed_slice(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            data.level(),
+            Some(Level::Layer(
+                FixedSurface::new(106, 0, 0),
+                FixedSurface::new(106, 1, 1)
+            ))
+        );
+        assert_eq!(
+            data.level().unwrap().to_string(),
+            "0 m below land surface - 0.1 m below land surface"
+        );
+    }
+
+    #[test]
+    fn complex_packing_parameters() {
+        let payload = vec![
+            0x00, 0x01, 0x00, 0x00, // number of data points
+            0x00, 0x02, // template number
+            0x42, 0x48, 0x00, 0x00, // reference value
+            0x80, 0x01, // binary scale factor
+            0x00, 0x02, // decimal scale factor
+            0x0c, // number of bits for group references
+            0x00, // type of original field values
+            0x01, // group splitting method
+            0x01, // missing value management
+            0x7f, 0xc0, 0x00, 0x00, // primary missing value substitute
+            0x00, 0x00, 0x00, 0x00, // secondary missing value substitute
+            0x00, 0x00, 0x01, 0x2c, // number of groups
+            0x00, // reference for group widths
+            0x04, // number of bits for group widths
+            0x00, 0x00, 0x00, 0x01, // reference for group lengths
+            0x01, // length increment for group lengths
+            0x00, 0x00, 0x00, 0x0a, // true length of last group
+            0x08, // number of bits for scaled group lengths
+        ];
+        let data = ReprDefinition::from_payload(payload.into_boxed_slice()).unwrap();
+        assert_eq!(
+            data.complex_packing(),
+            Some(ComplexPackingParams {
+                simple: SimplePackingParams {
+                    ref_val: 50.,
+                    binary_scale: -1,
+                    decimal_scale: 2,
+                    nbit: 12,
+                    value_type: 0,
+                },
+                group_splitting_method: 1,
+                missing_value_management: 1,
+                primary_missing_value: 0x7fc00000,
+                secondary_missing_value: 0,
+                num_groups: 300,
+                group_width_ref: 0,
+                group_width_nbit: 4,
+                group_len_ref: 1,
+                group_len_inc: 1,
+                group_len_last: 10,
+                group_len_nbit: 8,
+                spatial_differencing: None,
+            })
+        );
+        assert_eq!(data.simple_packing(), None);
+
+        let mut payload = data.iter().copied().collect::<Vec<_>>();
+        payload[5] = 3;
+        payload.extend_from_slice(&[0x02, 0x02]);
+        let data = ReprDefinition::from_payload(payload.into_boxed_slice()).unwrap();
+        let params = data.complex_packing().unwrap();
+        assert_eq!(
+            params.spatial_differencing,
+            Some(SpatialDifferencingParams {
+                order: 2,
+                num_extra_octets: 2,
+            })
+        );
     }
 }
@@ -1,21 +1,57 @@
 use std::cell::RefMut;
 
 use crate::context::{SectionBody, SectionInfo};
+use crate::datatypes::{BitMap, SimplePackingParams};
 use crate::decoders::complex::*;
+#[cfg(feature = "jpeg2000")]
 use crate::decoders::jpeg2000::*;
 use crate::decoders::run_length::*;
 use crate::decoders::simple::*;
+pub use crate::decoders::spectral::{decode_spectral, SpectralDecodeError};
 use crate::error::*;
 use crate::reader::Grib2Read;
+use crate::utils::NBitwiseIterator;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum DecodeError {
+    #[deprecated(
+        since = "0.4.4",
+        note = "This error is no more used; `GribError::Unsupported` is returned instead"
+    )]
     TemplateNumberUnsupported,
     BitMapIndicatorUnsupported,
     SimplePackingDecodeError(SimplePackingDecodeError),
     ComplexPackingDecodeError(ComplexPackingDecodeError),
+    #[cfg(feature = "jpeg2000")]
     Jpeg2000CodeStreamDecodeError(Jpeg2000CodeStreamDecodeError),
     RunLengthEncodingDecodeError(RunLengthEncodingDecodeError),
+    SpectralDecodeError(SpectralDecodeError),
+    /// The length of the output buffer differs from the number of data
+    /// points.
+    OutputLengthMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    /// The bit-map is shorter than the grid, or the number of its set bits
+    /// differs from the number of decoded values.
+    BitMapLengthMismatch,
+    /// Decoding of the submessage at `submessage_index` failed while
+    /// decoding many submessages, e.g. in
+    /// [`Grib2::decode_all`](crate::context::Grib2::decode_all).
+    SubmessageDecodeError {
+        submessage_index: usize,
+        cause: Box<DecodeError>,
+    },
+}
+
+impl DecodeError {
+    /// Wraps the error with the index of the submessage where it occurred.
+    pub fn with_submessage_index(self, idx: usize) -> DecodeError {
+        Self::SubmessageDecodeError {
+            submessage_index: idx,
+            cause: Box::new(self),
+        }
+    }
 }
 
 impl From<SimplePackingDecodeError> for DecodeError {
@@ -30,23 +66,73 @@ impl From<ComplexPackingDecodeError> for DecodeError {
     }
 }
 
+#[cfg(feature = "jpeg2000")]
 impl From<Jpeg2000CodeStreamDecodeError> for DecodeError {
     fn from(e: Jpeg2000CodeStreamDecodeError) -> Self {
         Self::Jpeg2000CodeStreamDecodeError(e)
     }
 }
 
+impl From<SpectralDecodeError> for DecodeError {
+    fn from(e: SpectralDecodeError) -> Self {
+        Self::SpectralDecodeError(e)
+    }
+}
+
 impl From<RunLengthEncodingDecodeError> for DecodeError {
     fn from(e: RunLengthEncodingDecodeError) -> Self {
         Self::RunLengthEncodingDecodeError(e)
     }
 }
 
+/// Options controlling how grid values are decoded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecodeOptions {
+    /// Value stored at grid points without valid data. Defaults to
+    /// [`f32::NAN`]; tools that cannot handle NaN may set a sentinel such as
+    /// `9999.0` instead.
+    pub fill_value: f32,
+    /// Size in octets of blocks in which packed data in Section 7 are read,
+    /// or `None` to read the whole section at once, which is the default.
+    ///
+    /// Reading in blocks keeps memory usage low for huge fields, but is
+    /// currently supported only for simple packing (Template 5.0); the other
+    /// templates ignore this option.
+    pub sect7_chunk_size: Option<usize>,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self {
+            fill_value: f32::NAN,
+            sect7_chunk_size: None,
+        }
+    }
+}
+
+/// Formats in which decoded values are written out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutputFormat {
+    /// 32-bit little-endian floating-point numbers
+    F32Le,
+    /// 32-bit big-endian floating-point numbers
+    F32Be,
+    /// One value per line in text
+    Text,
+}
+
+/// Decodes values packed in Section 7.
+///
+/// When a bit-map is present, only values at grid points where the bit-map
+/// is set are returned, so the result is shorter than the grid.
+/// [`Grib2::get_values`](crate::context::Grib2::get_values) places them on
+/// the whole grid.
 pub fn dispatch<R: Grib2Read>(
     sect5: &SectionInfo,
     sect6: &SectionInfo,
     sect7: &SectionInfo,
     reader: RefMut<R>,
+    opts: &DecodeOptions,
 ) -> Result<Box<[f32]>, GribError> {
     let sect5_body = match &sect5.body {
         Some(SectionBody::Section5(body)) => body,
@@ -54,24 +140,402 @@ pub fn dispatch<R: Grib2Read>(
     };
 
     let decoded = match sect5_body.repr_tmpl_num() {
-        0 => SimplePackingDecoder::decode(sect5, sect6, sect7, reader)?,
-        3 => ComplexPackingDecoder::decode(sect5, sect6, sect7, reader)?,
-        40 => Jpeg2000CodeStreamDecoder::decode(sect5, sect6, sect7, reader)?,
-        200 => RunLengthEncodingDecoder::decode(sect5, sect6, sect7, reader)?,
-        _ => {
-            return Err(GribError::DecodeError(
-                DecodeError::TemplateNumberUnsupported,
-            ))
+        0 => SimplePackingDecoder::decode(sect5, sect6, sect7, reader, opts)?,
+        3 => ComplexPackingDecoder::decode(sect5, sect6, sect7, reader, opts)?,
+        #[cfg(feature = "jpeg2000")]
+        40 => Jpeg2000CodeStreamDecoder::decode(sect5, sect6, sect7, reader, opts)?,
+        200 => RunLengthEncodingDecoder::decode(sect5, sect6, sect7, reader, opts)?,
+        n => {
+            return Err(GribError::Unsupported(format!(
+                "Data Representation Template 5.{}",
+                n
+            )))
         }
     };
     Ok(decoded)
 }
 
+/// Returns the packed integers of Section 7 before the reference value and
+/// the scale factors are applied, which are useful for lossless workflows.
+///
+/// Only simple packing (Template 5.0) and JPEG 2000 code stream format
+/// (Template 5.40) are supported.
+pub fn decode_raw_integers<R: Grib2Read>(
+    sect5: &SectionInfo,
+    sect7: &SectionInfo,
+    mut reader: RefMut<R>,
+) -> Result<Box<[u32]>, GribError> {
+    let sect5_body = match &sect5.body {
+        Some(SectionBody::Section5(body)) => body,
+        _ => return Err(GribError::InternalDataError),
+    };
+    let num_points = sect5_body.num_points() as usize;
+
+    let sect5_data = reader.read_sect_payload_as_slice(sect5)?;
+    let sect7_data = reader.read_sect_payload_as_slice(sect7)?;
+    let decoded = match sect5_body.repr_tmpl_num() {
+        0 => {
+            let SimplePackingParams { nbit, .. } = SimplePackingParams::from_payload(&sect5_data)
+                .ok_or(GribError::InternalDataError)?;
+            check_nbit(nbit)?;
+            if nbit == 0 {
+                vec![0; num_points]
+            } else {
+                NBitwiseIterator::new(&sect7_data, usize::from(nbit))
+                    .take(num_points)
+                    .collect::<Vec<_>>()
+            }
+        }
+        #[cfg(feature = "jpeg2000")]
+        40 => {
+            let SimplePackingParams { nbit, .. } = SimplePackingParams::from_payload(&sect5_data)
+                .ok_or(GribError::InternalDataError)?;
+            if nbit == 0 {
+                return Ok(vec![0; num_points].into_boxed_slice());
+            }
+            let stream = Stream::from_bytes(&sect7_data).map_err(|e| {
+                GribError::DecodeError(DecodeError::Jpeg2000CodeStreamDecodeError(e))
+            })?;
+            decode_jp2(stream)
+                .map_err(|e| GribError::DecodeError(DecodeError::Jpeg2000CodeStreamDecodeError(e)))?
+                .map(|v| v as u32)
+                .collect::<Vec<_>>()
+        }
+        n => {
+            return Err(GribError::Unsupported(format!(
+                "raw integers for Data Representation Template 5.{}",
+                n
+            )))
+        }
+    };
+
+    if decoded.len() != num_points {
+        return Err(GribError::DecodeError(
+            DecodeError::SimplePackingDecodeError(SimplePackingDecodeError::LengthMismatch),
+        ));
+    }
+    Ok(decoded.into_boxed_slice())
+}
+
+/// Decodes grid values like [`dispatch`] into `out`, which can be reused
+/// across fields to avoid allocating a buffer for each field.
+///
+/// The length of `out` must be equal to the number of data points. Simple
+/// packing and JPEG 2000 code stream format are decoded directly into `out`,
+/// while other templates are decoded into a temporary buffer and copied.
+pub fn dispatch_into<R: Grib2Read>(
+    sect5: &SectionInfo,
+    sect6: &SectionInfo,
+    sect7: &SectionInfo,
+    reader: RefMut<R>,
+    opts: &DecodeOptions,
+    out: &mut [f32],
+) -> Result<(), GribError> {
+    let sect5_body = match &sect5.body {
+        Some(SectionBody::Section5(body)) => body,
+        _ => return Err(GribError::InternalDataError),
+    };
+
+    match sect5_body.repr_tmpl_num() {
+        0 => SimplePackingDecoder::decode_into(sect5, sect6, sect7, reader, opts, out),
+        #[cfg(feature = "jpeg2000")]
+        40 => Jpeg2000CodeStreamDecoder::decode_into(sect5, sect6, sect7, reader, opts, out),
+        _ => {
+            let decoded = dispatch(sect5, sect6, sect7, reader, opts)?;
+            check_output_len(out, decoded.len())?;
+            out.copy_from_slice(&decoded);
+            Ok(())
+        }
+    }
+}
+
+/// Decodes values like [`dispatch`] and, if a bit-map is present, places them
+/// at the grid points where the bit-map is set, filling the other points of
+/// the grid of `num_grid_points` points with the fill value.
+pub(crate) fn decode_on_grid<R: Grib2Read>(
+    sect5: &SectionInfo,
+    sect6: &SectionInfo,
+    sect7: &SectionInfo,
+    reader: RefMut<R>,
+    opts: &DecodeOptions,
+    num_grid_points: usize,
+) -> Result<Box<[f32]>, GribError> {
+    let values = dispatch(sect5, sect6, sect7, reader, opts)?;
+    match bitmap_of(sect6)? {
+        None => Ok(values),
+        Some(bitmap) => {
+            let mut out = vec![0.; num_grid_points];
+            expand_with_bitmap(&values, bitmap, opts.fill_value, &mut out)?;
+            Ok(out.into_boxed_slice())
+        }
+    }
+}
+
+/// Decodes values like [`decode_on_grid`] into `out`, whose length must be
+/// equal to the number of grid points.
+pub(crate) fn decode_on_grid_into<R: Grib2Read>(
+    sect5: &SectionInfo,
+    sect6: &SectionInfo,
+    sect7: &SectionInfo,
+    reader: RefMut<R>,
+    opts: &DecodeOptions,
+    out: &mut [f32],
+) -> Result<(), GribError> {
+    match bitmap_of(sect6)? {
+        None => dispatch_into(sect5, sect6, sect7, reader, opts, out),
+        Some(bitmap) => {
+            let values = dispatch(sect5, sect6, sect7, reader, opts)?;
+            expand_with_bitmap(&values, bitmap, opts.fill_value, out)
+        }
+    }
+}
+
+/// Size in octets of blocks in which Section 7 is read by
+/// [`decode_on_grid_in_batches`] unless specified in [`DecodeOptions`].
+const DEFAULT_SECT7_CHUNK_SIZE: usize = 65536;
+
+/// Decodes values like [`decode_on_grid`] and passes them to `sink` in
+/// batches of at most `batch_size` values.
+///
+/// Simple packing (Template 5.0) is decoded while reading Section 7 in
+/// blocks, so that the whole field is never held in memory. The other
+/// templates are decoded as a whole and then passed in batches.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn decode_on_grid_in_batches<R: Grib2Read>(
+    sect5: &SectionInfo,
+    sect6: &SectionInfo,
+    sect7: &SectionInfo,
+    reader: RefMut<R>,
+    opts: &DecodeOptions,
+    num_grid_points: usize,
+    batch_size: usize,
+    sink: &mut dyn FnMut(&[f32]) -> Result<(), GribError>,
+) -> Result<(), GribError> {
+    let sect5_body = match &sect5.body {
+        Some(SectionBody::Section5(body)) => body,
+        _ => return Err(GribError::InternalDataError),
+    };
+    if sect5_body.repr_tmpl_num() != 0 {
+        let values = decode_on_grid(sect5, sect6, sect7, reader, opts, num_grid_points)?;
+        return values.chunks(batch_size.max(1)).try_for_each(sink);
+    }
+
+    let bitmap = bitmap_of(sect6)?;
+    let num_points = sect5_body.num_points() as usize;
+    let chunk_size = opts.sect7_chunk_size.unwrap_or(DEFAULT_SECT7_CHUNK_SIZE);
+    SimplePackingDecoder::decode_with(sect5, sect7, reader, chunk_size, |values| {
+        let mut batch = Vec::with_capacity(batch_size.clamp(1, num_grid_points.max(1)));
+        let mut push = |value: f32| {
+            batch.push(value);
+            if batch.len() >= batch_size {
+                sink(&batch)?;
+                batch.clear();
+            }
+            Ok::<_, GribError>(())
+        };
+        match bitmap {
+            None => {
+                let mut count = 0;
+                for value in values {
+                    push(value)?;
+                    count += 1;
+                }
+                if count != num_points {
+                    return Err(GribError::DecodeError(
+                        DecodeError::SimplePackingDecodeError(
+                            SimplePackingDecodeError::LengthMismatch,
+                        ),
+                    ));
+                }
+            }
+            Some(bitmap) => {
+                let mismatch = || GribError::DecodeError(DecodeError::BitMapLengthMismatch);
+                if bitmap.len() * 8 < num_grid_points {
+                    return Err(mismatch());
+                }
+                for index in 0..num_grid_points {
+                    let is_set = bitmap[index / 8] & (0b1000_0000 >> (index % 8)) != 0;
+                    push(if is_set {
+                        values.next().ok_or_else(mismatch)?
+                    } else {
+                        opts.fill_value
+                    })?;
+                }
+                if values.next().is_some() {
+                    return Err(mismatch());
+                }
+            }
+        }
+        if batch.is_empty() {
+            Ok(())
+        } else {
+            sink(&batch)
+        }
+    })
+}
+
+/// Returns the bit-map specified in Section 6, or `None` if no bit-map
+/// applies.
+fn bitmap_of(sect6: &SectionInfo) -> Result<Option<&[u8]>, GribError> {
+    let sect6_body = match &sect6.body {
+        Some(SectionBody::Section6(body)) => body,
+        _ => return Err(GribError::InternalDataError),
+    };
+    check_bitmap_indicator(sect6_body)?;
+    match (sect6_body.bitmap_indicator, &sect6_body.bitmap) {
+        (0, Some(bitmap)) => Ok(Some(bitmap)),
+        (0, None) => Err(GribError::InternalDataError),
+        _ => Ok(None),
+    }
+}
+
+/// Fails for bit-maps other than one specified in Section 6 (indicator 0)
+/// and no bit-map (indicator 255).
+pub(crate) fn check_bitmap_indicator(sect6_body: &BitMap) -> Result<(), GribError> {
+    match sect6_body.bitmap_indicator {
+        0 | 255 => Ok(()),
+        _ => Err(GribError::DecodeError(
+            DecodeError::BitMapIndicatorUnsupported,
+        )),
+    }
+}
+
+/// Writes `values` into `out` at positions where bits of `bitmap` are set,
+/// and `fill_value` at the other positions.
+pub(crate) fn expand_with_bitmap(
+    values: &[f32],
+    bitmap: &[u8],
+    fill_value: f32,
+    out: &mut [f32],
+) -> Result<(), GribError> {
+    let mismatch = GribError::DecodeError(DecodeError::BitMapLengthMismatch);
+    if bitmap.len() * 8 < out.len() {
+        return Err(mismatch);
+    }
+    let mut values = values.iter();
+    for (index, slot) in out.iter_mut().enumerate() {
+        let is_set = bitmap[index / 8] & (0b1000_0000 >> (index % 8)) != 0;
+        *slot = if is_set {
+            *values.next().ok_or_else(|| mismatch.clone())?
+        } else {
+            fill_value
+        };
+    }
+    if values.next().is_some() {
+        return Err(mismatch);
+    }
+    Ok(())
+}
+
+pub(crate) fn check_output_len(out: &[f32], num_points: usize) -> Result<(), GribError> {
+    if out.len() == num_points {
+        Ok(())
+    } else {
+        Err(GribError::DecodeError(DecodeError::OutputLengthMismatch {
+            expected: num_points,
+            actual: out.len(),
+        }))
+    }
+}
+
 pub(crate) trait Grib2DataDecode<R> {
     fn decode(
         sect5: &SectionInfo,
         sect6: &SectionInfo,
         sect7: &SectionInfo,
         reader: RefMut<R>,
+        opts: &DecodeOptions,
     ) -> Result<Box<[f32]>, GribError>;
+
+    /// Decodes values into `out`, whose length must be equal to the number
+    /// of data points.
+    fn decode_into(
+        sect5: &SectionInfo,
+        sect6: &SectionInfo,
+        sect7: &SectionInfo,
+        reader: RefMut<R>,
+        opts: &DecodeOptions,
+        out: &mut [f32],
+    ) -> Result<(), GribError> {
+        let decoded = Self::decode(sect5, sect6, sect7, reader, opts)?;
+        check_output_len(out, decoded.len())?;
+        out.copy_from_slice(&decoded);
+        Ok(())
+    }
+}
+
+/// Writes values from `iter` into `out` and fails if the number of values
+/// differs from the length of `out`.
+pub(crate) fn fill_from_iter<I: Iterator<Item = f32>>(
+    out: &mut [f32],
+    iter: I,
+) -> Result<(), GribError> {
+    let mut count = 0;
+    for value in iter {
+        if let Some(slot) = out.get_mut(count) {
+            *slot = value;
+        }
+        count += 1;
+    }
+    if count != out.len() {
+        return Err(GribError::DecodeError(
+            DecodeError::SimplePackingDecodeError(SimplePackingDecodeError::LengthMismatch),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) type TestReader =
+    std::cell::RefCell<crate::reader::SeekableGrib2Reader<std::io::Cursor<Vec<u8>>>>;
+
+/// Builds Sections 5 to 7 without a bit map from the given payloads, along
+/// with a reader over the encoded Sections 5 and 7, for decoder tests.
+#[cfg(test)]
+pub(crate) fn sections_for_test(
+    sect5_payload: Vec<u8>,
+    sect7_payload: &[u8],
+) -> (SectionInfo, SectionInfo, SectionInfo, TestReader) {
+    let mut buf = Vec::new();
+    let sect5_size = sect5_payload.len() + 5;
+    buf.extend_from_slice(&(sect5_size as u32).to_be_bytes());
+    buf.push(5);
+    buf.extend_from_slice(&sect5_payload);
+    let sect7_offset = buf.len();
+    let sect7_size = sect7_payload.len() + 5;
+    buf.extend_from_slice(&(sect7_size as u32).to_be_bytes());
+    buf.push(7);
+    buf.extend_from_slice(sect7_payload);
+
+    let sect5 = SectionInfo {
+        num: 5,
+        offset: 0,
+        size: sect5_size,
+        body: Some(SectionBody::Section5(
+            crate::datatypes::ReprDefinition::from_payload(sect5_payload.into_boxed_slice())
+                .unwrap(),
+        )),
+        checksum: None,
+    };
+    let sect6 = SectionInfo {
+        num: 6,
+        offset: 0,
+        size: 6,
+        body: Some(SectionBody::Section6(BitMap {
+            bitmap_indicator: 255,
+            bitmap: None,
+        })),
+        checksum: None,
+    };
+    let sect7 = SectionInfo {
+        num: 7,
+        offset: sect7_offset,
+        size: sect7_size,
+        body: Some(SectionBody::Section7),
+        checksum: None,
+    };
+    let reader = std::cell::RefCell::new(crate::reader::SeekableGrib2Reader::new(
+        std::io::Cursor::new(buf),
+    ));
+    (sect5, sect6, sect7, reader)
 }
@@ -4,6 +4,7 @@ use std::convert::TryInto;
 use std::iter;
 
 use crate::context::{SectionBody, SectionInfo};
+use crate::datatypes::{ComplexPackingParams, SimplePackingParams, SpatialDifferencingParams};
 use crate::decoders::common::*;
 use crate::decoders::simple::*;
 use crate::error::*;
@@ -24,32 +25,42 @@ impl<R: Grib2Read> Grib2DataDecode<R> for ComplexPackingDecoder {
         sect6: &SectionInfo,
         sect7: &SectionInfo,
         mut reader: RefMut<R>,
+        opts: &DecodeOptions,
     ) -> Result<Box<[f32]>, GribError> {
         let (sect5_body, sect6_body) = match (sect5.body.as_ref(), sect6.body.as_ref()) {
             (Some(SectionBody::Section5(b5)), Some(SectionBody::Section6(b6))) => (b5, b6),
             _ => return Err(GribError::InternalDataError),
         };
 
-        if sect6_body.bitmap_indicator != 255 {
-            return Err(GribError::DecodeError(
-                DecodeError::BitMapIndicatorUnsupported,
-            ));
-        }
+        check_bitmap_indicator(sect6_body)?;
 
         let sect5_data = reader.read_sect_payload_as_slice(sect5)?;
-        let ref_val = read_as!(f32, sect5_data, 6);
-        let exp = read_as!(u16, sect5_data, 10).as_grib_int();
-        let dig = read_as!(u16, sect5_data, 12).as_grib_int();
-        let nbit = read_as!(u8, sect5_data, 14);
-        let ngroup = read_as!(u32, sect5_data, 26);
-        let group_width_ref = read_as!(u8, sect5_data, 30);
-        let group_width_nbit = read_as!(u8, sect5_data, 31);
-        let group_len_ref = read_as!(u32, sect5_data, 32);
-        let group_len_inc = read_as!(u8, sect5_data, 36);
-        let group_len_last = read_as!(u32, sect5_data, 37);
-        let group_len_nbit = read_as!(u8, sect5_data, 41);
-        let spdiff_level = read_as!(u8, sect5_data, 42);
-        let spdiff_param_octet = read_as!(u8, sect5_data, 43);
+        let ComplexPackingParams {
+            simple:
+                SimplePackingParams {
+                    ref_val,
+                    binary_scale: exp,
+                    decimal_scale: dig,
+                    nbit,
+                    ..
+                },
+            missing_value_management,
+            num_groups: ngroup,
+            group_width_ref,
+            group_width_nbit,
+            group_len_ref,
+            group_len_inc,
+            group_len_last,
+            group_len_nbit,
+            spatial_differencing,
+            ..
+        } = ComplexPackingParams::from_payload(&sect5_data).ok_or(GribError::InternalDataError)?;
+        let SpatialDifferencingParams {
+            order: spdiff_level,
+            num_extra_octets: spdiff_param_octet,
+        } = spatial_differencing.ok_or(GribError::DecodeError(
+            DecodeError::ComplexPackingDecodeError(ComplexPackingDecodeError::NotSupported),
+        ))?;
 
         let sect7_data = reader.read_sect_payload_as_slice(sect7)?;
 
@@ -99,8 +110,16 @@ impl<R: Grib2Read> Grib2DataDecode<R> for ComplexPackingDecoder {
             group_lens_iter,
             z_min,
             &sect7_data[group_lens_end_octet..],
+            usize::from(nbit),
+            missing_value_management,
         );
 
+        if missing_value_management > 2 {
+            return Err(GribError::DecodeError(
+                DecodeError::ComplexPackingDecodeError(ComplexPackingDecodeError::NotSupported),
+            ));
+        }
+
         if spdiff_level != 2 {
             return Err(GribError::DecodeError(
                 DecodeError::ComplexPackingDecodeError(ComplexPackingDecodeError::NotSupported),
@@ -115,12 +134,21 @@ impl<R: Grib2Read> Grib2DataDecode<R> for ComplexPackingDecoder {
 
         let spdiff_packed_iter = unpacked_data.flatten();
         assert_eq!(
-            spdiff_packed_iter.clone().take(2).collect::<Vec<_>>(),
+            spdiff_packed_iter
+                .clone()
+                .flatten()
+                .take(2)
+                .collect::<Vec<_>>(),
             [i32::from(z1), i32::from(z2)]
         );
 
         let spdiff_unpacked = SpatialDiff2ndOrderDecodeIterator::new(spdiff_packed_iter);
-        let decoded = SimplePackingDecodeIterator::new(spdiff_unpacked, ref_val, exp, dig)
+        let (exp, dig) = (i32::from(exp), i32::from(dig));
+        let decoded = spdiff_unpacked
+            .map(|value| match value {
+                Some(value) => unpack_value(value as f32, ref_val, exp, dig),
+                None => opts.fill_value,
+            })
             .collect::<Vec<_>>();
         if decoded.len() != sect5_body.num_points() as usize {
             return Err(GribError::DecodeError(
@@ -140,6 +168,8 @@ struct ComplexPackingValueDecodeIterator<'a, I, J, K> {
     data: &'a [u8],
     pos: usize,
     start_offset_bits: usize,
+    ref_nbit: usize,
+    missing_value_management: u8,
 }
 
 impl<'a, I, J, K> ComplexPackingValueDecodeIterator<'a, I, J, K> {
@@ -149,6 +179,8 @@ impl<'a, I, J, K> ComplexPackingValueDecodeIterator<'a, I, J, K> {
         length_iter: K,
         z_min: i16,
         data: &'a [u8],
+        ref_nbit: usize,
+        missing_value_management: u8,
     ) -> Self {
         Self {
             ref_iter,
@@ -158,6 +190,23 @@ impl<'a, I, J, K> ComplexPackingValueDecodeIterator<'a, I, J, K> {
             data,
             pos: 0,
             start_offset_bits: 0,
+            ref_nbit,
+            missing_value_management,
+        }
+    }
+
+    // Returns whether `value` packed with `nbit` bits is the primary or
+    // secondary missing value, which are represented as all ones and all ones
+    // minus one respectively.
+    fn is_missing(&self, value: u32, nbit: usize) -> bool {
+        if nbit == 0 {
+            return false;
+        }
+        let all_ones = (u64::MAX >> (64 - nbit)) as u32;
+        match self.missing_value_management {
+            1 => value == all_ones,
+            2 => value == all_ones || value == all_ones - 1,
+            _ => false,
         }
     }
 }
@@ -169,9 +218,9 @@ where
     O: ToPrimitive,
     P: ToPrimitive,
 {
-    type Item = Vec<i32>;
+    type Item = Vec<Option<i32>>;
 
-    fn next(&mut self) -> Option<Vec<i32>> {
+    fn next(&mut self) -> Option<Vec<Option<i32>>> {
         match (
             self.ref_iter.next(),
             self.width_iter.next(),
@@ -179,19 +228,36 @@ where
         ) {
             (Some(_ref), Some(width), Some(length)) => {
                 let (_ref, width, length) = (
-                    _ref.to_i32().unwrap(),
+                    _ref.to_u32().unwrap(),
                     width.to_usize().unwrap(),
                     length.to_usize().unwrap(),
                 );
                 let bits = width * length;
                 let (pos_end, offset_bit) = (self.pos + bits / 8, bits % 8);
                 let offset_byte = if offset_bit > 0 { 1 } else { 0 };
-                let group_values =
+                let group_values = if width == 0 {
+                    // constant groups have no packed values, which may be
+                    // absent at the end of data
+                    let value = if self.is_missing(_ref, self.ref_nbit) {
+                        None
+                    } else {
+                        Some(_ref as i32 + self.z_min)
+                    };
+                    vec![value; length]
+                } else {
+                    let _ref = _ref as i32;
                     NBitwiseIterator::new(&self.data[self.pos..pos_end + offset_byte], width)
                         .with_offset(self.start_offset_bits)
                         .take(length)
-                        .map(|v| v.as_grib_int() + _ref + self.z_min)
-                        .collect::<Vec<i32>>();
+                        .map(|v| {
+                            if self.is_missing(v, width) {
+                                None
+                            } else {
+                                Some(v.as_grib_int() + _ref + self.z_min)
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                };
                 self.pos = pos_end;
                 self.start_offset_bits = offset_byte;
                 Some(group_values)
@@ -219,28 +285,146 @@ impl<I> SpatialDiff2ndOrderDecodeIterator<I> {
     }
 }
 
-impl<I: Iterator<Item = i32>> Iterator for SpatialDiff2ndOrderDecodeIterator<I> {
-    type Item = i32;
+// Missing values are passed through and skipped in the differencing.
+impl<I: Iterator<Item = Option<i32>>> Iterator for SpatialDiff2ndOrderDecodeIterator<I> {
+    type Item = Option<i32>;
 
-    fn next(&mut self) -> Option<i32> {
+    fn next(&mut self) -> Option<Option<i32>> {
+        let v = match self.iter.next()? {
+            Some(v) => v,
+            None => return Some(None),
+        };
         let count = self.count;
         self.count += 1;
-        match (count, self.iter.next()) {
-            (_, None) => None,
-            (0, Some(v)) => {
+        match count {
+            0 => {
                 self.prev2 = v;
-                Some(v)
+                Some(Some(v))
             }
-            (1, Some(v)) => {
+            1 => {
                 self.prev1 = v;
-                Some(v)
+                Some(Some(v))
             }
-            (_, Some(v)) => {
+            _ => {
                 let v = v + 2 * self.prev1 - self.prev2;
                 self.prev2 = self.prev1;
                 self.prev1 = v;
-                Some(v)
+                Some(Some(v))
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_group_values_with_primary_missing_values() {
+        let refs = vec![2u32, 15];
+        let widths = vec![2u32, 0];
+        let lengths = vec![3u32, 2];
+        let data = vec![0b0111_0000];
+        let actual = ComplexPackingValueDecodeIterator::new(
+            refs.into_iter(),
+            widths.into_iter(),
+            lengths.into_iter(),
+            0,
+            &data,
+            4,
+            1,
+        )
+        .collect::<Vec<_>>();
+        let expected = vec![vec![Some(3), None, Some(2)], vec![None, None]];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn decode_group_values_with_secondary_missing_values() {
+        let refs = vec![14u32];
+        let widths = vec![2u32];
+        let lengths = vec![3u32];
+        let data = vec![0b1011_0000];
+        let iter = |missing_value_management| {
+            ComplexPackingValueDecodeIterator::new(
+                refs.clone().into_iter(),
+                widths.clone().into_iter(),
+                lengths.clone().into_iter(),
+                0,
+                &data,
+                4,
+                missing_value_management,
+            )
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(iter(1), vec![vec![Some(16), None, Some(14)]]);
+        assert_eq!(iter(2), vec![vec![None, None, Some(14)]]);
+    }
+
+    #[test]
+    fn decode_field_with_missing_value_management() {
+        let sect5_payload = vec![
+            0x00, 0x00, 0x00, 0x06, // number of data points
+            0x00, 0x03, // template number
+            0x00, 0x00, 0x00, 0x00, // reference value
+            0x00, 0x00, // binary scale factor
+            0x00, 0x00, // decimal scale factor
+            0x04, // number of bits for group references
+            0x00, // type of original field values
+            0x01, // group splitting method
+            0x01, // missing value management
+            0x7f, 0xc0, 0x00, 0x00, // primary missing value substitute
+            0x7f, 0xc0, 0x00, 0x00, // secondary missing value substitute
+            0x00, 0x00, 0x00, 0x03, // number of groups
+            0x00, // reference for group widths
+            0x02, // number of bits for group widths
+            0x00, 0x00, 0x00, 0x01, // reference for group lengths
+            0x01, // length increment for group lengths
+            0x00, 0x00, 0x00, 0x01, // true length of last group
+            0x02, // number of bits for scaled group lengths
+            0x02, // order of spatial differencing
+            0x02, // number of octets for extra descriptors
+        ];
+        let sect7_payload = vec![
+            0x00,
+            0x01, // first value
+            0x00,
+            0x02, // second value
+            0x00,
+            0x00, // overall minimum of differences
+            0b0000_1111,
+            0b0001_0000, // group references: 0, 15 (missing), 1
+            0b1000_0000, // group widths: 2, 0, 0
+            0b1100_0000, // group lengths: 4, 1
+            0b0111_1000, // values of the first group: 1, 3 (missing), 2, 0
+        ];
+
+        let (sect5, sect6, sect7, reader) = sections_for_test(sect5_payload, &sect7_payload);
+        let decode = |opts| {
+            ComplexPackingDecoder::decode(&sect5, &sect6, &sect7, reader.borrow_mut(), &opts)
+                .unwrap()
+        };
+
+        let actual = decode(DecodeOptions::default());
+        assert_eq!(actual.len(), 6);
+        assert_eq!(actual[0], 1.);
+        assert!(actual[1].is_nan());
+        assert_eq!(actual[2..4], [2., 3.]);
+        assert!(actual[4].is_nan());
+        assert_eq!(actual[5], 5.);
+
+        let actual = decode(DecodeOptions {
+            fill_value: -1.,
+            ..Default::default()
+        });
+        assert_eq!(&actual[..], [1., -1., 2., 3., -1., 5.]);
+    }
+
+    #[test]
+    fn spatial_differencing_skips_missing_values() {
+        let input = vec![Some(1), None, Some(2), Some(0), None, Some(1)];
+        let actual = SpatialDiff2ndOrderDecodeIterator::new(input.into_iter()).collect::<Vec<_>>();
+        let expected = vec![Some(1), None, Some(2), Some(3), None, Some(5)];
+        assert_eq!(actual, expected);
+    }
+}
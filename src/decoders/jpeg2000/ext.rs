@@ -51,21 +51,21 @@ impl Stream {
                 return 0;
             }
 
-            let user_data = p_user_data as *mut SliceWithOffset;
+            let user_data = &mut *(p_user_data as *mut SliceWithOffset);
 
-            let len = (*user_data).buf.len();
+            let len = user_data.buf.len();
 
-            let offset = (*user_data).offset;
+            let offset = user_data.offset;
 
             let bytes_left = len - offset;
 
             let bytes_read = std::cmp::min(bytes_left, p_nb_bytes);
 
-            let slice = &(*user_data).buf[offset..offset + bytes_read];
+            let slice = &user_data.buf[offset..offset + bytes_read];
 
             std::ptr::copy_nonoverlapping(slice.as_ptr(), p_buffer as *mut u8, bytes_read);
 
-            (*user_data).offset += bytes_read;
+            user_data.offset += bytes_read;
 
             bytes_read
         }
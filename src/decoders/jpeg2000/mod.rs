@@ -1,15 +1,15 @@
 use openjpeg_sys as opj;
 use std::cell::RefMut;
-use std::convert::TryInto;
 
 use crate::context::{SectionBody, SectionInfo};
+use crate::datatypes::{Jpeg2000PackingParams, SimplePackingParams};
 use crate::decoders::common::*;
 use crate::decoders::simple::*;
 use crate::error::*;
 use crate::reader::Grib2Read;
-use crate::utils::{read_as, GribInt};
 
 mod ext;
+pub(crate) use ext::Stream;
 use ext::*;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -28,25 +28,46 @@ impl<R: Grib2Read> Grib2DataDecode<R> for Jpeg2000CodeStreamDecoder {
         sect5: &SectionInfo,
         sect6: &SectionInfo,
         sect7: &SectionInfo,
-        mut reader: RefMut<R>,
+        reader: RefMut<R>,
+        opts: &DecodeOptions,
     ) -> Result<Box<[f32]>, GribError> {
+        let num_points = match sect5.body.as_ref() {
+            Some(SectionBody::Section5(b5)) => b5.num_points() as usize,
+            _ => return Err(GribError::InternalDataError),
+        };
+        let mut decoded = vec![0.; num_points];
+        Self::decode_into(sect5, sect6, sect7, reader, opts, &mut decoded)?;
+        Ok(decoded.into_boxed_slice())
+    }
+
+    fn decode_into(
+        sect5: &SectionInfo,
+        sect6: &SectionInfo,
+        sect7: &SectionInfo,
+        mut reader: RefMut<R>,
+        _opts: &DecodeOptions,
+        out: &mut [f32],
+    ) -> Result<(), GribError> {
         let (sect5_body, sect6_body) = match (sect5.body.as_ref(), sect6.body.as_ref()) {
             (Some(SectionBody::Section5(b5)), Some(SectionBody::Section6(b6))) => (b5, b6),
             _ => return Err(GribError::InternalDataError),
         };
+        check_output_len(out, sect5_body.num_points() as usize)?;
 
-        if sect6_body.bitmap_indicator != 255 {
-            return Err(GribError::DecodeError(
-                DecodeError::BitMapIndicatorUnsupported,
-            ));
-        }
+        check_bitmap_indicator(sect6_body)?;
 
         let sect5_data = reader.read_sect_payload_as_slice(sect5)?;
-        let ref_val = read_as!(f32, sect5_data, 6);
-        let exp = read_as!(u16, sect5_data, 10).as_grib_int();
-        let dig = read_as!(u16, sect5_data, 12).as_grib_int();
-        //let nbit = read_as!(u8, sect5_data, 14);
-        let value_type = read_as!(u8, sect5_data, 15);
+        let Jpeg2000PackingParams {
+            simple:
+                SimplePackingParams {
+                    ref_val,
+                    binary_scale: exp,
+                    decimal_scale: dig,
+                    nbit,
+                    value_type,
+                },
+            ..
+        } = Jpeg2000PackingParams::from_payload(&sect5_data).ok_or(GribError::InternalDataError)?;
 
         if value_type != 0 {
             return Err(GribError::DecodeError(
@@ -56,24 +77,29 @@ impl<R: Grib2Read> Grib2DataDecode<R> for Jpeg2000CodeStreamDecoder {
             ));
         }
 
+        // As in simple packing, a field with no bits per value is a constant
+        // field of the reference value, for which Section 7 may be empty.
+        if nbit == 0 {
+            out.fill(ref_val);
+            return Ok(());
+        }
+
         let sect7_data = reader.read_sect_payload_as_slice(sect7)?;
 
         let stream = Stream::from_bytes(&sect7_data)
             .map_err(|e| GribError::DecodeError(DecodeError::Jpeg2000CodeStreamDecodeError(e)))?;
         let jp2_unpacked = decode_jp2(stream)
             .map_err(|e| GribError::DecodeError(DecodeError::Jpeg2000CodeStreamDecodeError(e)))?;
-        let decoded =
-            SimplePackingDecodeIterator::new(jp2_unpacked, ref_val, exp, dig).collect::<Vec<_>>();
-        if decoded.len() != sect5_body.num_points() as usize {
-            return Err(GribError::DecodeError(
-                DecodeError::SimplePackingDecodeError(SimplePackingDecodeError::LengthMismatch),
-            ));
-        }
-        Ok(decoded.into_boxed_slice())
+        fill_from_iter(
+            out,
+            SimplePackingDecodeIterator::new(jp2_unpacked, ref_val, exp, dig),
+        )
     }
 }
 
-fn decode_jp2(stream: Stream) -> Result<impl Iterator<Item = i32>, Jpeg2000CodeStreamDecodeError> {
+pub(crate) fn decode_jp2(
+    stream: Stream,
+) -> Result<impl Iterator<Item = i32>, Jpeg2000CodeStreamDecodeError> {
     let codec = Codec::j2k()?;
 
     let mut decode_params = unsafe { std::mem::zeroed::<opj::opj_dparameters>() };
@@ -112,3 +138,31 @@ fn decode_jp2(stream: Stream) -> Result<impl Iterator<Item = i32>, Jpeg2000CodeS
         Err(Jpeg2000CodeStreamDecodeError::NotSupported)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_constant_field_without_code_stream() {
+        let mut sect5_payload = 3_u32.to_be_bytes().to_vec();
+        sect5_payload.extend_from_slice(&[0x00, 0x28]); // template number
+        sect5_payload.extend_from_slice(&2.5_f32.to_be_bytes()); // reference value
+        sect5_payload.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]); // scale factors
+        sect5_payload.extend_from_slice(&[0, 0, 0, 255]); // nbit, value type, compression
+
+        let (sect5, sect6, sect7, reader) = sections_for_test(sect5_payload, &[]);
+        let actual = Jpeg2000CodeStreamDecoder::decode(
+            &sect5,
+            &sect6,
+            &sect7,
+            reader.borrow_mut(),
+            &DecodeOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(&actual[..], [2.5, 2.5, 2.5]);
+
+        let actual = decode_raw_integers(&sect5, &sect7, reader.borrow_mut()).unwrap();
+        assert_eq!(&actual[..], [0, 0, 0]);
+    }
+}
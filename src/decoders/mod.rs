@@ -1,6 +1,8 @@
 mod common;
 pub use common::*;
 mod complex;
+#[cfg(feature = "jpeg2000")]
 mod jpeg2000;
 mod run_length;
 mod simple;
+mod spectral;
@@ -23,17 +23,14 @@ impl<R: Grib2Read> Grib2DataDecode<R> for RunLengthEncodingDecoder {
         sect6: &SectionInfo,
         sect7: &SectionInfo,
         mut reader: RefMut<R>,
+        opts: &DecodeOptions,
     ) -> Result<Box<[f32]>, GribError> {
         let (sect5_body, sect6_body) = match (sect5.body.as_ref(), sect6.body.as_ref()) {
             (Some(SectionBody::Section5(b5)), Some(SectionBody::Section6(b6))) => (b5, b6),
             _ => return Err(GribError::InternalDataError),
         };
 
-        if sect6_body.bitmap_indicator != 255 {
-            return Err(GribError::DecodeError(
-                DecodeError::BitMapIndicatorUnsupported,
-            ));
-        }
+        check_bitmap_indicator(sect6_body)?;
 
         let sect5_data = reader.read_sect_payload_as_slice(sect5)?;
         let nbit = read_as!(u8, sect5_data, 6);
@@ -42,7 +39,7 @@ impl<R: Grib2Read> Grib2DataDecode<R> for RunLengthEncodingDecoder {
         let num_digits = read_as!(u8, sect5_data, 11);
 
         let mut level_map = Vec::with_capacity(max_level.into());
-        level_map.push(f32::NAN);
+        level_map.push(opts.fill_value);
         let mut pos = 12;
 
         for _ in 0..max_level {
@@ -106,7 +103,7 @@ fn rleunpack(
             exp = 1;
         } else {
             let prev = cached.ok_or(RunLengthEncodingDecodeError::InvalidFirstValue)?;
-            let length: usize = ((value as u16 - rlbase) as usize) * exp;
+            let length: usize = ((value - rlbase) as usize) * exp;
             out_buf.append(&mut vec![prev; length]);
             exp *= lngu;
         }
@@ -1,18 +1,35 @@
 use num::ToPrimitive;
 use std::cell::RefMut;
-use std::convert::TryInto;
+use std::io::{Read, SeekFrom};
 
 use crate::context::{SectionBody, SectionInfo};
+use crate::datatypes::SimplePackingParams;
 use crate::decoders::common::*;
 use crate::error::*;
-use crate::reader::Grib2Read;
-use crate::utils::{read_as, GribInt, NBitwiseIterator};
+use crate::reader::{Grib2Read, SECT_HEADER_SIZE};
+use crate::utils::{ChunkedNBitwiseIterator, NBitwiseIterator};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum SimplePackingDecodeError {
     NotSupported,
     OriginalFieldValueTypeNotSupported,
     LengthMismatch,
+    /// The number of bits per value exceeds 32, which packed values cannot
+    /// be read into.
+    BitsPerValueTooLarge {
+        nbit: u8,
+    },
+}
+
+pub(crate) fn check_nbit(nbit: u8) -> Result<(), GribError> {
+    if nbit > 32 {
+        return Err(GribError::DecodeError(
+            DecodeError::SimplePackingDecodeError(SimplePackingDecodeError::BitsPerValueTooLarge {
+                nbit,
+            }),
+        ));
+    }
+    Ok(())
 }
 
 pub(crate) struct SimplePackingDecoder {}
@@ -22,54 +39,153 @@ impl<R: Grib2Read> Grib2DataDecode<R> for SimplePackingDecoder {
         sect5: &SectionInfo,
         sect6: &SectionInfo,
         sect7: &SectionInfo,
-        mut reader: RefMut<R>,
+        reader: RefMut<R>,
+        opts: &DecodeOptions,
     ) -> Result<Box<[f32]>, GribError> {
+        let num_points = match sect5.body.as_ref() {
+            Some(SectionBody::Section5(b5)) => b5.num_points() as usize,
+            _ => return Err(GribError::InternalDataError),
+        };
+        let mut decoded = vec![0.; num_points];
+        Self::decode_into(sect5, sect6, sect7, reader, opts, &mut decoded)?;
+        Ok(decoded.into_boxed_slice())
+    }
+
+    fn decode_into(
+        sect5: &SectionInfo,
+        sect6: &SectionInfo,
+        sect7: &SectionInfo,
+        mut reader: RefMut<R>,
+        opts: &DecodeOptions,
+        out: &mut [f32],
+    ) -> Result<(), GribError> {
         let (sect5_body, sect6_body) = match (sect5.body.as_ref(), sect6.body.as_ref()) {
             (Some(SectionBody::Section5(b5)), Some(SectionBody::Section6(b6))) => (b5, b6),
             _ => return Err(GribError::InternalDataError),
         };
+        check_output_len(out, sect5_body.num_points() as usize)?;
+
+        check_bitmap_indicator(sect6_body)?;
+
+        let SimplePackingParams {
+            ref_val,
+            binary_scale: exp,
+            decimal_scale: dig,
+            nbit,
+            ..
+        } = read_params(sect5, &mut reader)?;
 
-        if sect6_body.bitmap_indicator != 255 {
-            return Err(GribError::DecodeError(
-                DecodeError::BitMapIndicatorUnsupported,
-            ));
+        // Based on the implementation of wgrib2, if nbits equals 0, return a constant
+        // field where the data value at each grid point is the reference value.
+        if nbit == 0 {
+            out.fill(ref_val);
+            return Ok(());
         }
 
-        let sect5_data = reader.read_sect_payload_as_slice(sect5)?;
-        let ref_val = read_as!(f32, sect5_data, 6);
-        let exp = read_as!(u16, sect5_data, 10).as_grib_int();
-        let dig = read_as!(u16, sect5_data, 12).as_grib_int();
-        let nbit = read_as!(u8, sect5_data, 14);
-        let value_type = read_as!(u8, sect5_data, 15);
-
-        if value_type != 0 {
-            return Err(GribError::DecodeError(
-                DecodeError::SimplePackingDecodeError(
-                    SimplePackingDecodeError::OriginalFieldValueTypeNotSupported,
-                ),
-            ));
+        if let Some(chunk_size) = opts.sect7_chunk_size {
+            let payload_offset = sect7.offset + SECT_HEADER_SIZE;
+            let payload_size = sect7.size - SECT_HEADER_SIZE;
+            reader
+                .seek(SeekFrom::Start(payload_offset as u64))
+                .map_err(ParseError::from)?;
+            let mut iter = ChunkedNBitwiseIterator::new(
+                (&mut *reader).take(payload_size as u64),
+                usize::from(nbit),
+                chunk_size,
+            );
+            let result = fill_from_iter(
+                out,
+                SimplePackingDecodeIterator::new(iter.by_ref().take(out.len()), ref_val, exp, dig),
+            );
+            return match iter.take_error() {
+                Some(e) => Err(ParseError::from(e).into()),
+                None => result,
+            };
         }
 
         let sect7_data = reader.read_sect_payload_as_slice(sect7)?;
+        // Trailing bits padding Section 7 to a whole octet may form extra values
+        // when `nbit` is smaller than 8.
+        let iter = NBitwiseIterator::new(&sect7_data, usize::from(nbit)).take(out.len());
+        fill_from_iter(
+            out,
+            SimplePackingDecodeIterator::new(iter, ref_val, exp, dig),
+        )
+    }
+}
+
+impl SimplePackingDecoder {
+    /// Decodes values reading Section 7 in blocks of `chunk_size` octets and
+    /// passes an iterator over them to `f`, so that the whole field is never
+    /// held in memory.
+    pub(crate) fn decode_with<R: Grib2Read, T>(
+        sect5: &SectionInfo,
+        sect7: &SectionInfo,
+        mut reader: RefMut<R>,
+        chunk_size: usize,
+        f: impl FnOnce(&mut dyn Iterator<Item = f32>) -> Result<T, GribError>,
+    ) -> Result<T, GribError> {
+        let num_points = match sect5.body.as_ref() {
+            Some(SectionBody::Section5(b5)) => b5.num_points() as usize,
+            _ => return Err(GribError::InternalDataError),
+        };
+        let SimplePackingParams {
+            ref_val,
+            binary_scale: exp,
+            decimal_scale: dig,
+            nbit,
+            ..
+        } = read_params(sect5, &mut reader)?;
 
-        // Based on the implementation of wgrib2, if nbits equals 0, return a constant
-        // field where the data value at each grid point is the reference value.
         if nbit == 0 {
-            let decoded = vec![ref_val; sect5_body.num_points() as usize];
-            return Ok(decoded.into_boxed_slice());
+            return f(&mut std::iter::repeat_n(ref_val, num_points));
         }
 
-        let iter = NBitwiseIterator::new(&sect7_data, usize::from(nbit));
-        let decoded = SimplePackingDecodeIterator::new(iter, ref_val, exp, dig).collect::<Vec<_>>();
-        if decoded.len() != sect5_body.num_points() as usize {
-            return Err(GribError::DecodeError(
-                DecodeError::SimplePackingDecodeError(SimplePackingDecodeError::LengthMismatch),
-            ));
+        let payload_offset = sect7.offset + SECT_HEADER_SIZE;
+        let payload_size = sect7.size - SECT_HEADER_SIZE;
+        reader
+            .seek(SeekFrom::Start(payload_offset as u64))
+            .map_err(ParseError::from)?;
+        let mut iter = ChunkedNBitwiseIterator::new(
+            (&mut *reader).take(payload_size as u64),
+            usize::from(nbit),
+            chunk_size,
+        );
+        let result = f(&mut SimplePackingDecodeIterator::new(
+            iter.by_ref().take(num_points),
+            ref_val,
+            exp,
+            dig,
+        ));
+        match iter.take_error() {
+            Some(e) => Err(ParseError::from(e).into()),
+            None => result,
         }
-        Ok(decoded.into_boxed_slice())
     }
 }
 
+/// Reads parameters of simple packing from Section 5 and checks that values
+/// can be decoded with them.
+fn read_params<R: Grib2Read>(
+    sect5: &SectionInfo,
+    reader: &mut RefMut<R>,
+) -> Result<SimplePackingParams, GribError> {
+    let sect5_data = reader.read_sect_payload_as_slice(sect5)?;
+    let params =
+        SimplePackingParams::from_payload(&sect5_data).ok_or(GribError::InternalDataError)?;
+
+    if params.value_type != 0 {
+        return Err(GribError::DecodeError(
+            DecodeError::SimplePackingDecodeError(
+                SimplePackingDecodeError::OriginalFieldValueTypeNotSupported,
+            ),
+        ));
+    }
+
+    check_nbit(params.nbit)?;
+    Ok(params)
+}
+
 pub(crate) struct SimplePackingDecodeIterator<I> {
     iter: I,
     ref_val: f32,
@@ -95,28 +211,34 @@ impl<I: Iterator<Item = N>, N: ToPrimitive> Iterator for SimplePackingDecodeIter
         match self.iter.next() {
             Some(encoded) => {
                 let encoded = encoded.to_f32().unwrap();
-                let diff = (encoded * 2_f32.powi(self.exp)) as f32;
-                let dig_factor = 10_f32.powi(-self.dig);
-                let value: f32 = (self.ref_val + diff) * dig_factor;
-                Some(value)
+                Some(unpack_value(encoded, self.ref_val, self.exp, self.dig))
             }
             _ => None,
         }
     }
 }
 
+#[inline]
+pub(crate) fn unpack_value(encoded: f32, ref_val: f32, exp: i32, dig: i32) -> f32 {
+    let diff = encoded * 2_f32.powi(exp);
+    let dig_factor = 10_f32.powi(-dig);
+    (ref_val + diff) * dig_factor
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use std::convert::TryInto;
     use std::fs::File;
     use std::io::{BufReader, Cursor, Read};
 
     use crate::context::from_reader;
+    use crate::utils::GribInt;
 
     #[test]
     fn decode_simple_packing() {
-        let ref_val_bytes = vec![0x35, 0x3e, 0x6b, 0xf6];
+        let ref_val_bytes = [0x35, 0x3e, 0x6b, 0xf6];
         let exp = 0x801a;
         let dig = 0x0000;
         let input: Vec<u8> = vec![0x00, 0x06, 0x00, 0x0d];
@@ -165,8 +287,82 @@ mod tests {
 
         let reader = grib.reader.borrow_mut();
 
-        let actual = SimplePackingDecoder::decode(sect5, sect6, sect7, reader).unwrap();
+        let actual =
+            SimplePackingDecoder::decode(sect5, sect6, sect7, reader, &DecodeOptions::default())
+                .unwrap();
         let expected = vec![0f32; 0x002d0000].into_boxed_slice();
         assert_eq!(actual, expected);
     }
+
+    fn decode_with_nbit(
+        nbit: u8,
+        num_points: u32,
+        sect7_payload: &[u8],
+    ) -> Result<Box<[f32]>, GribError> {
+        decode_with_nbit_and_options(nbit, num_points, sect7_payload, &DecodeOptions::default())
+    }
+
+    fn decode_with_nbit_and_options(
+        nbit: u8,
+        num_points: u32,
+        sect7_payload: &[u8],
+        opts: &DecodeOptions,
+    ) -> Result<Box<[f32]>, GribError> {
+        let mut sect5_payload = num_points.to_be_bytes().to_vec();
+        sect5_payload.extend_from_slice(&[0x00, 0x00]); // template number
+        sect5_payload.extend_from_slice(&1_f32.to_be_bytes()); // reference value
+        sect5_payload.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]); // scale factors
+        sect5_payload.extend_from_slice(&[nbit, 0x00]);
+
+        let (sect5, sect6, sect7, reader) = sections_for_test(sect5_payload, sect7_payload);
+        SimplePackingDecoder::decode(&sect5, &sect6, &sect7, reader.borrow_mut(), opts)
+    }
+
+    #[test]
+    fn decode_simple_packing_reading_sect7_in_chunks() {
+        let values = (0..100).map(|i| i * 77 % 8192).collect::<Vec<u32>>();
+        let mut sect7_payload = Vec::new();
+        crate::utils::pack_bits(&values, 13, &mut sect7_payload);
+        let expected = values.iter().map(|v| *v as f32 + 1.).collect::<Vec<_>>();
+
+        for chunk_size in [1, 2, 3, 7, 4096] {
+            let opts = DecodeOptions {
+                sect7_chunk_size: Some(chunk_size),
+                ..Default::default()
+            };
+            assert_eq!(
+                &decode_with_nbit_and_options(13, 100, &sect7_payload, &opts).unwrap()[..],
+                &expected[..]
+            );
+            assert_eq!(
+                decode_with_nbit_and_options(13, 100, &sect7_payload[..150], &opts),
+                Err(GribError::DecodeError(
+                    DecodeError::SimplePackingDecodeError(SimplePackingDecodeError::LengthMismatch)
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn decode_simple_packing_with_boundary_nbit() {
+        assert_eq!(&decode_with_nbit(0, 3, &[]).unwrap()[..], [1., 1., 1.]);
+        assert_eq!(
+            &decode_with_nbit(1, 3, &[0b1010_0000]).unwrap()[..],
+            [2., 1., 2.]
+        );
+        assert_eq!(
+            &decode_with_nbit(32, 2, &[0, 0, 0, 1, 0, 0x01, 0, 0]).unwrap()[..],
+            [2., 65537.]
+        );
+        for nbit in [33, 255] {
+            assert_eq!(
+                decode_with_nbit(nbit, 1, &[0; 64]),
+                Err(GribError::DecodeError(
+                    DecodeError::SimplePackingDecodeError(
+                        SimplePackingDecodeError::BitsPerValueTooLarge { nbit }
+                    )
+                ))
+            );
+        }
+    }
 }
@@ -0,0 +1,206 @@
+use std::cell::RefMut;
+use std::convert::TryInto;
+use std::iter;
+
+use crate::context::{SectionBody, SectionInfo};
+use crate::datatypes::SimplePackingParams;
+use crate::decoders::common::*;
+use crate::decoders::simple::*;
+use crate::error::*;
+use crate::grid::SpectralTruncation;
+use crate::reader::Grib2Read;
+use crate::utils::{read_as, GribInt, NBitwiseIterator};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SpectralDecodeError {
+    /// The number of values in Section 5 (`actual`) is not twice the number
+    /// of coefficients of the truncation in Section 3 (`expected`).
+    TruncationMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    /// The number of unpacked values (`actual`) does not match the
+    /// sub-truncation of complex packing (`expected`).
+    UnpackedSubsetMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    /// Precision of unpacked values other than 32-bit and 64-bit IEEE
+    /// floating point numbers (see Code Table 5.7)
+    UnpackedPrecisionUnsupported(u8),
+    LengthMismatch,
+}
+
+/// Decodes spherical harmonic coefficients packed with Data Representation
+/// Template 5.50 (simple packing) or 5.51 (complex packing).
+///
+/// Returned values are the real and imaginary parts of coefficients in turn,
+/// in the order of [`SpectralTruncation::wavenumbers`]. Values packed using
+/// the Laplacian operator in complex packing are scaled back.
+pub fn decode_spectral<R: Grib2Read>(
+    truncation: &SpectralTruncation,
+    sect5: &SectionInfo,
+    sect6: &SectionInfo,
+    sect7: &SectionInfo,
+    mut reader: RefMut<R>,
+) -> Result<Box<[f32]>, GribError> {
+    let (sect5_body, sect6_body) = match (sect5.body.as_ref(), sect6.body.as_ref()) {
+        (Some(SectionBody::Section5(b5)), Some(SectionBody::Section6(b6))) => (b5, b6),
+        _ => return Err(GribError::InternalDataError),
+    };
+    if sect6_body.bitmap_indicator != 255 {
+        return Err(GribError::DecodeError(
+            DecodeError::BitMapIndicatorUnsupported,
+        ));
+    }
+
+    let num_values = sect5_body.num_points() as usize;
+    let expected = truncation.num_coefficients() * 2;
+    if num_values != expected {
+        return Err(SpectralDecodeError::TruncationMismatch {
+            expected,
+            actual: num_values,
+        }
+        .into());
+    }
+
+    let sect5_data = reader.read_sect_payload_as_slice(sect5)?;
+    let sect7_data = reader.read_sect_payload_as_slice(sect7)?;
+    let decoded = match sect5_body.repr_tmpl_num() {
+        50 => decode_simple_spectral(&sect5_data, &sect7_data, num_values)?,
+        51 => decode_complex_spectral(truncation, &sect5_data, &sect7_data, num_values)?,
+        n => {
+            return Err(GribError::Unsupported(format!(
+                "spherical harmonic coefficients in Data Representation Template 5.{}",
+                n
+            )))
+        }
+    };
+    Ok(decoded.into_boxed_slice())
+}
+
+impl From<SpectralDecodeError> for GribError {
+    fn from(e: SpectralDecodeError) -> Self {
+        Self::DecodeError(DecodeError::SpectralDecodeError(e))
+    }
+}
+
+/// Returns an iterator over `num_values` values packed with simple packing
+/// in `sect7_data`.
+fn unpack_simple<'a>(
+    params: &SimplePackingParams,
+    sect7_data: &'a [u8],
+    num_values: usize,
+) -> Result<impl Iterator<Item = f32> + 'a, GribError> {
+    check_nbit(params.nbit)?;
+    let encoded: Box<dyn Iterator<Item = u32>> = if params.nbit == 0 {
+        Box::new(iter::repeat(0))
+    } else {
+        Box::new(NBitwiseIterator::new(sect7_data, usize::from(params.nbit)))
+    };
+    Ok(SimplePackingDecodeIterator::new(
+        encoded.take(num_values),
+        params.ref_val,
+        params.binary_scale,
+        params.decimal_scale,
+    ))
+}
+
+fn decode_simple_spectral(
+    sect5_data: &[u8],
+    sect7_data: &[u8],
+    num_values: usize,
+) -> Result<Vec<f32>, GribError> {
+    let params =
+        SimplePackingParams::from_payload(sect5_data).ok_or(GribError::InternalDataError)?;
+    // The real part of the (0,0) coefficient is not packed and stored in
+    // octets 21-24 of Section 5.
+    let buf = sect5_data.get(15..19).ok_or(GribError::InternalDataError)?;
+    let first = read_as!(f32, buf, 0);
+
+    let num_packed = num_values.saturating_sub(1);
+    let decoded = iter::once(first)
+        .chain(unpack_simple(&params, sect7_data, num_packed)?)
+        .collect::<Vec<_>>();
+    if decoded.len() != num_values {
+        return Err(SpectralDecodeError::LengthMismatch.into());
+    }
+    Ok(decoded)
+}
+
+fn decode_complex_spectral(
+    truncation: &SpectralTruncation,
+    sect5_data: &[u8],
+    sect7_data: &[u8],
+    num_values: usize,
+) -> Result<Vec<f32>, GribError> {
+    let params =
+        SimplePackingParams::from_payload(sect5_data).ok_or(GribError::InternalDataError)?;
+    let buf = sect5_data.get(15..30).ok_or(GribError::InternalDataError)?;
+    let laplacian = f64::from(read_as!(u32, buf, 0).as_grib_int()) * 1e-6;
+    let subset = SpectralTruncation {
+        j: read_as!(u16, buf, 4).into(),
+        k: read_as!(u16, buf, 6).into(),
+        m: read_as!(u16, buf, 8).into(),
+        ..truncation.clone()
+    };
+    let num_unpacked = read_as!(u32, buf, 10) as usize;
+    let precision = buf[14];
+
+    let in_subset = |m: u32, n: u32| m <= subset.m && n <= subset.max_n(m);
+    let expected = truncation
+        .wavenumbers()
+        .filter(|(m, n)| in_subset(*m, *n))
+        .count()
+        * 2;
+    if num_unpacked != expected {
+        return Err(SpectralDecodeError::UnpackedSubsetMismatch {
+            expected,
+            actual: num_unpacked,
+        }
+        .into());
+    }
+
+    // Values of the subset are stored as they are before the packed values.
+    let size = match precision {
+        1 => 4,
+        2 => 8,
+        n => return Err(SpectralDecodeError::UnpackedPrecisionUnsupported(n).into()),
+    };
+    let unpacked_len = num_unpacked * size;
+    let (unpacked, packed) = match (
+        sect7_data.get(..unpacked_len),
+        sect7_data.get(unpacked_len..),
+    ) {
+        (Some(unpacked), Some(packed)) => (unpacked, packed),
+        _ => return Err(SpectralDecodeError::LengthMismatch.into()),
+    };
+    let mut unpacked = unpacked.chunks(size).map(|bytes| match size {
+        4 => read_as!(f32, bytes, 0),
+        _ => read_as!(f64, bytes, 0) as f32,
+    });
+    let mut packed = unpack_simple(&params, packed, num_values - num_unpacked)?;
+
+    let mut decoded = Vec::with_capacity(num_values);
+    for (m, n) in truncation.wavenumbers() {
+        let (re, im) = if in_subset(m, n) {
+            (unpacked.next(), unpacked.next())
+        } else {
+            // Values have been multiplied by (n(n+1))^P before packing.
+            let factor = if n == 0 {
+                1.
+            } else {
+                (f64::from(n) * f64::from(n + 1)).powf(-laplacian) as f32
+            };
+            (
+                packed.next().map(|v| v * factor),
+                packed.next().map(|v| v * factor),
+            )
+        };
+        match (re, im) {
+            (Some(re), Some(im)) => decoded.extend_from_slice(&[re, im]),
+            _ => return Err(SpectralDecodeError::LengthMismatch.into()),
+        }
+    }
+    Ok(decoded)
+}
@@ -0,0 +1,386 @@
+//! Rewriting of values of fields in existing messages.
+
+use std::collections::BTreeMap;
+
+use crate::context::{parse_message, SectionBody, SectionInfo};
+use crate::datatypes::SimplePackingParams;
+use crate::error::*;
+use crate::utils::pack_bits;
+
+/// Number of bits per value used when the width of data values of the
+/// original field is not known, e.g. for complex packing, or when it is 0 for
+/// a constant field.
+const DEFAULT_NBIT: u8 = 16;
+
+/// Editor of values of fields in a GRIB2 message.
+///
+/// Values are re-encoded with simple packing (Template 5.0), keeping the
+/// decimal scale factor of the original field where it has one, and Sections
+/// 5 and 7 and the total length of the message are rewritten accordingly.
+/// The number of bits per value of the original field is kept only for
+/// simple packing and JPEG 2000 code stream format (Template 5.40), where it
+/// is the width of data values; 16 bits are used otherwise. The other sections,
+/// including the bit-map in Section 6, are kept as they are.
+///
+/// ```no_run
+/// let bytes = std::fs::read("input.grib2").unwrap();
+/// let mut editor = grib::editor::Grib2MessageEditor::new(&bytes).unwrap();
+/// let mut values = editor.values(0).unwrap();
+/// values[0] = 0.;
+/// editor.replace_data(0, &values).unwrap();
+/// std::fs::write("output.grib2", editor.to_bytes().unwrap()).unwrap();
+/// ```
+pub struct Grib2MessageEditor {
+    message: Vec<u8>,
+    // new Sections 5 and 7 keyed by the index of the original Section 5
+    replacements: BTreeMap<usize, (Vec<u8>, Vec<u8>)>,
+}
+
+impl Grib2MessageEditor {
+    /// Creates an editor of the first message in `bytes`.
+    pub fn new(bytes: &[u8]) -> Result<Self, GribError> {
+        let grib2 = parse_message(bytes)?;
+        let end = grib2
+            .sections()
+            .last()
+            .map(|sect| sect.offset + sect.size)
+            .ok_or(GribError::InternalDataError)?;
+        Ok(Self {
+            message: bytes[..end].to_vec(),
+            replacements: BTreeMap::new(),
+        })
+    }
+
+    /// Decodes values of the submessage specified by the index `i` in the
+    /// original message.
+    pub fn values(&self, i: usize) -> Result<Box<[f32]>, GribError> {
+        parse_message(&self.message)?.get_values(i)
+    }
+
+    /// Replaces values of the submessage specified by the index `i` with
+    /// `values`, which must have a value for each grid point.
+    ///
+    /// If a bit-map applies, values at grid points where the bit-map is not
+    /// set are ignored. The other values must be finite.
+    pub fn replace_data(&mut self, i: usize, values: &[f32]) -> Result<(), GribError> {
+        let grib2 = parse_message(&self.message)?;
+        let submessage = grib2
+            .submessages()
+            .nth(i)
+            .ok_or(GribError::InternalDataError)?;
+        let num_grid_points = submessage.grid_def().num_points() as usize;
+        if values.len() != num_grid_points {
+            return Err(EncodeError::LengthMismatch(values.len(), num_grid_points).into());
+        }
+
+        let bitmap = match &submessage.6.body.body {
+            Some(SectionBody::Section6(body)) => match body.bitmap_indicator {
+                0 => body.bitmap.as_deref(),
+                255 => None,
+                _ => {
+                    return Err(GribError::Unsupported(
+                        "re-encoding values with a predefined bit-map".to_owned(),
+                    ))
+                }
+            },
+            _ => return Err(GribError::InternalDataError),
+        };
+        if let Some(bitmap) = bitmap {
+            if bitmap.len() * 8 < num_grid_points {
+                return Err(EncodeError::BitMapTooShort(bitmap.len(), num_grid_points).into());
+            }
+        }
+        let is_set = |index: usize| match bitmap {
+            None => true,
+            Some(bitmap) => bitmap[index / 8] & (0b1000_0000 >> (index % 8)) != 0,
+        };
+        // the index of the grid point is reported rather than that among
+        // points where the bit-map is set
+        if let Some(index) =
+            (0..values.len()).find(|index| is_set(*index) && !values[*index].is_finite())
+        {
+            return Err(EncodeError::NonFiniteValue { index }.into());
+        }
+        let values = values
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| is_set(*index))
+            .map(|(_, value)| *value)
+            .collect::<Vec<_>>();
+
+        let repr_def = submessage.repr_def();
+        // `nbit` of complex packing is the width of group reference values,
+        // not of data values
+        let data_params = repr_def
+            .simple_packing()
+            .or_else(|| repr_def.jpeg2000_packing().map(|params| params.simple));
+        let decimal_scale = data_params
+            .as_ref()
+            .map(|params| params.decimal_scale)
+            .or_else(|| {
+                repr_def
+                    .complex_packing()
+                    .map(|params| params.simple.decimal_scale)
+            })
+            .unwrap_or(0);
+        let nbit = data_params
+            .map(|params| params.nbit)
+            .filter(|nbit| (1..=32).contains(nbit))
+            .unwrap_or(DEFAULT_NBIT);
+        let (params, encoded) = encode_simple(&values, decimal_scale, nbit);
+
+        let mut sect5_payload = (values.len() as u32).to_be_bytes().to_vec();
+        sect5_payload.extend_from_slice(&0_u16.to_be_bytes());
+        sect5_payload.extend_from_slice(&params.to_bytes());
+        let mut sect7_payload = Vec::new();
+        pack_bits(&encoded, params.nbit, &mut sect7_payload);
+
+        self.replacements.insert(
+            submessage.5.index,
+            (
+                with_header(5, &sect5_payload),
+                with_header(7, &sect7_payload),
+            ),
+        );
+        Ok(())
+    }
+
+    /// Returns the bytes of the message with values replaced.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, GribError> {
+        let grib2 = parse_message(&self.message)?;
+        let sections = grib2.sections();
+        let raw = |sect: &SectionInfo| &self.message[sect.offset..sect.offset + sect.size];
+
+        let mut bytes = Vec::with_capacity(self.message.len());
+        let mut pending_sect7 = None;
+        for (index, sect) in sections.iter().enumerate() {
+            match (self.replacements.get(&index), sect.num) {
+                (Some((sect5, sect7)), _) => {
+                    bytes.extend_from_slice(sect5);
+                    pending_sect7 = Some(sect7);
+                }
+                (None, 7) => match pending_sect7.take() {
+                    Some(sect7) => bytes.extend_from_slice(sect7),
+                    None => bytes.extend_from_slice(raw(sect)),
+                },
+                _ => bytes.extend_from_slice(raw(sect)),
+            }
+        }
+        let total_length = bytes.len() as u64;
+        bytes[8..16].copy_from_slice(&total_length.to_be_bytes());
+        Ok(bytes)
+    }
+}
+
+/// Packs `values` with simple packing using `decimal_scale` and `nbit` bits
+/// per value, choosing the reference value and the binary scale factor to
+/// cover the range of values.
+fn encode_simple(values: &[f32], decimal_scale: i16, nbit: u8) -> (SimplePackingParams, Vec<u32>) {
+    let dig_factor = 10_f64.powi(i32::from(decimal_scale));
+    let scaled = values
+        .iter()
+        .map(|value| f64::from(*value) * dig_factor)
+        .collect::<Vec<_>>();
+    let min = scaled.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = scaled.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let ref_val = if min.is_finite() { min as f32 } else { 0. };
+
+    let range = max - f64::from(ref_val);
+    let max_encoded = (u64::MAX >> (64 - nbit)) as f64;
+    if values.is_empty() || range <= 0. {
+        let params = SimplePackingParams {
+            ref_val,
+            binary_scale: 0,
+            decimal_scale,
+            nbit: 0,
+            value_type: 0,
+        };
+        return (params, Vec::new());
+    }
+    let binary_scale = (range / max_encoded).log2().ceil() as i16;
+    let bin_factor = 2_f64.powi(-i32::from(binary_scale));
+    let encoded = scaled
+        .iter()
+        .map(|value| {
+            ((value - f64::from(ref_val)) * bin_factor)
+                .round()
+                .clamp(0., max_encoded) as u32
+        })
+        .collect();
+    let params = SimplePackingParams {
+        ref_val,
+        binary_scale,
+        decimal_scale,
+        nbit,
+        value_type: 0,
+    };
+    (params, encoded)
+}
+
+fn with_header(num: u8, payload: &[u8]) -> Vec<u8> {
+    let mut sect = ((payload.len() + 5) as u32).to_be_bytes().to_vec();
+    sect.push(num);
+    sect.extend_from_slice(payload);
+    sect
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::from_slice;
+    use crate::utils::bytes_from_xz_file;
+
+    fn icon_bytes() -> Vec<u8> {
+        std::fs::read("testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2")
+            .unwrap()
+    }
+
+    #[test]
+    fn replace_one_value_and_read_again() {
+        let bytes = icon_bytes();
+        let mut editor = Grib2MessageEditor::new(&bytes).unwrap();
+        assert_eq!(editor.to_bytes().unwrap(), bytes);
+
+        let mut values = editor.values(0).unwrap();
+        assert!(values.iter().all(|value| *value == 0.));
+        values[1000] = 12.5;
+        editor.replace_data(0, &values).unwrap();
+
+        let edited = editor.to_bytes().unwrap();
+        let grib2 = from_slice(&edited).unwrap();
+        let submessage = grib2.iter().next().unwrap();
+        assert_eq!(submessage.indicator().total_length, edited.len() as u64);
+        assert_eq!(submessage.repr_def().repr_tmpl_num(), 0);
+        assert_eq!(
+            submessage.repr_def().simple_packing().unwrap().nbit,
+            DEFAULT_NBIT
+        );
+        let actual = grib2.get_values(0).unwrap();
+        assert_eq!(actual.len(), values.len());
+        for (index, (actual, expected)) in actual.iter().zip(values.iter()).enumerate() {
+            assert!(
+                (actual - expected).abs() < 1e-3,
+                "{}: {} != {}",
+                index,
+                actual,
+                expected
+            );
+        }
+        assert_eq!(actual[1000], 12.5);
+    }
+
+    #[test]
+    fn reject_invalid_values() {
+        let bytes = icon_bytes();
+        let mut editor = Grib2MessageEditor::new(&bytes).unwrap();
+        let mut values = editor.values(0).unwrap().into_vec();
+        values[10] = f32::NAN;
+        assert_eq!(
+            editor.replace_data(0, &values),
+            Err(GribError::EncodeError(EncodeError::NonFiniteValue {
+                index: 10
+            }))
+        );
+        let len = values.len();
+        assert_eq!(
+            editor.replace_data(0, &values[1..]),
+            Err(GribError::EncodeError(EncodeError::LengthMismatch(
+                len - 1,
+                len
+            )))
+        );
+        assert_eq!(editor.to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn replace_complex_packed_values_with_default_nbit() {
+        let bytes = bytes_from_xz_file(
+            "testdata/Z__C_RJTD_20190605000000_MEPS_GPV_Rjp_L-pall_FH00-15_grib2.bin.0-20.xz",
+        );
+        let mut editor = Grib2MessageEditor::new(&bytes).unwrap();
+        let values = editor.values(0).unwrap();
+        editor.replace_data(0, &values).unwrap();
+
+        let edited = editor.to_bytes().unwrap();
+        let grib2 = from_slice(&edited).unwrap();
+        let params = grib2.iter().next().unwrap().repr_def().simple_packing();
+        assert_eq!(params.unwrap().nbit, DEFAULT_NBIT);
+        let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let tolerance = (max - min) / f32::from(u16::MAX);
+        for (actual, expected) in grib2.get_values(0).unwrap().iter().zip(values.iter()) {
+            assert!((actual - expected).abs() <= tolerance);
+        }
+    }
+
+    // ICON data with a bit-map set at every other grid point
+    fn icon_bytes_with_bitmap() -> Vec<u8> {
+        icon_bytes_with_shortened_bitmap(0)
+    }
+
+    // the bit-map lacks the last `missing_octets` octets needed for the grid
+    fn icon_bytes_with_shortened_bitmap(missing_octets: usize) -> Vec<u8> {
+        let bytes = icon_bytes();
+        let grib2 = parse_message(&bytes).unwrap();
+        let num_grid_points = grib2.iter().next().unwrap().grid_def().num_points() as usize;
+        let mut sect6_payload = vec![0];
+        sect6_payload.resize(
+            1 + num_grid_points.div_ceil(8) - missing_octets,
+            0b1010_1010,
+        );
+
+        let mut edited = Vec::new();
+        for sect in grib2.sections() {
+            let mut raw = bytes[sect.offset..sect.offset + sect.size].to_vec();
+            match sect.num {
+                5 => raw[5..9].copy_from_slice(&(num_grid_points as u32 / 2).to_be_bytes()),
+                6 => raw = with_header(6, &sect6_payload),
+                _ => {}
+            }
+            edited.extend_from_slice(&raw);
+        }
+        let total_length = edited.len() as u64;
+        edited[8..16].copy_from_slice(&total_length.to_be_bytes());
+        edited
+    }
+
+    #[test]
+    fn report_grid_index_of_non_finite_value_with_bitmap() {
+        let bytes = icon_bytes_with_bitmap();
+        let mut editor = Grib2MessageEditor::new(&bytes).unwrap();
+        let mut values = editor.values(0).unwrap().into_vec();
+        assert!(values[3].is_nan());
+
+        values[2] = 1.5;
+        editor.replace_data(0, &values).unwrap();
+        let actual = from_slice(&editor.to_bytes().unwrap())
+            .unwrap()
+            .get_values(0)
+            .unwrap();
+        assert!((actual[2] - 1.5).abs() < 1e-3);
+        assert!(actual[3].is_nan());
+
+        values[4] = f32::INFINITY;
+        assert_eq!(
+            editor.replace_data(0, &values),
+            Err(GribError::EncodeError(EncodeError::NonFiniteValue {
+                index: 4
+            }))
+        );
+    }
+
+    #[test]
+    fn replace_values_with_too_short_bitmap() {
+        let bytes = icon_bytes_with_shortened_bitmap(1);
+        let mut editor = Grib2MessageEditor::new(&bytes).unwrap();
+        let num_grid_points = 2949120;
+        let values = vec![0.; num_grid_points];
+        assert_eq!(
+            editor.replace_data(0, &values),
+            Err(GribError::EncodeError(EncodeError::BitMapTooShort(
+                num_grid_points / 8 - 1,
+                num_grid_points
+            )))
+        );
+    }
+}
@@ -2,14 +2,24 @@ use std::error::Error;
 use std::fmt::{self, Display, Formatter};
 use std::io;
 
+use crate::context::TemplateInfo;
 use crate::decoders::*;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum GribError {
     InternalDataError,
+    /// Data uses features, such as templates, which are not supported yet.
+    Unsupported(String),
     ParseError(ParseError),
     ValidationError(ValidationError),
     DecodeError(DecodeError),
+    GridError(GridError),
+    GridMismatchError,
+    WriteError(String),
+    EncodeError(EncodeError),
+    /// Arguments given by the caller are invalid, e.g. no submessages are
+    /// selected where at least one is needed.
+    InvalidArgument(String),
 }
 
 impl Error for GribError {
@@ -36,13 +46,37 @@ impl From<DecodeError> for GribError {
     }
 }
 
+impl From<EncodeError> for GribError {
+    fn from(e: EncodeError) -> Self {
+        Self::EncodeError(e)
+    }
+}
+
+impl From<GridError> for GribError {
+    fn from(e: GridError) -> Self {
+        Self::GridError(e)
+    }
+}
+
+impl From<NetcdfError> for GribError {
+    fn from(e: NetcdfError) -> Self {
+        Self::WriteError(e.to_string())
+    }
+}
+
 impl Display for GribError {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match self {
             Self::InternalDataError => write!(f, "Something unexpected happend"),
+            Self::Unsupported(s) => write!(f, "Unsupported: {}", s),
             Self::ParseError(e) => write!(f, "{}", e),
             Self::ValidationError(e) => write!(f, "{}", e),
             Self::DecodeError(e) => write!(f, "{:#?}", e),
+            Self::GridError(e) => write!(f, "{}", e),
+            Self::GridMismatchError => write!(f, "Grids of data do not match"),
+            Self::WriteError(s) => write!(f, "Write error: {}", s),
+            Self::EncodeError(e) => write!(f, "{}", e),
+            Self::InvalidArgument(s) => write!(f, "Invalid argument: {}", s),
         }
     }
 }
@@ -55,13 +89,34 @@ pub enum ParseError {
         note = "This error was used only in reading Section 0 and no more used"
     )]
     FileTypeCheckError(String),
-    NotGRIB,
+    /// The data does not start with "GRIB" (found: the first 4 octets).
+    NotGRIB {
+        found: [u8; 4],
+    },
     GRIBVersionMismatch(u8),
     UnknownSectionNumber(u8),
     EndSectionMismatch,
     UnexpectedEndOfData(usize),
     InvalidSectionOrder(usize),
     NoGridDefinition(usize),
+    /// The total length of a message declared in Section 0 exceeds the
+    /// limit, which typically results from a corrupted or byte-swapped
+    /// header.
+    ImplausibleMessageSize {
+        declared: u64,
+    },
+    /// The size of a section declared in its header is smaller than the
+    /// header itself.
+    SectionTooShort {
+        section_num: u8,
+        minimum: usize,
+        declared: usize,
+    },
+    /// Templates used in a submessage (first and second) cannot be used
+    /// together.
+    IncompatibleTemplates(TemplateInfo, TemplateInfo),
+    /// "GRIB" is not found within the octets scanned.
+    GribMagicNotFound,
 }
 
 impl Error for ParseError {
@@ -76,7 +131,9 @@ impl Display for ParseError {
             Self::ReadError(s) => write!(f, "Read error: {}", s),
             #[allow(deprecated)]
             Self::FileTypeCheckError(s) => write!(f, "Error in checking file type: {}", s),
-            Self::NotGRIB => write!(f, "Not GRIB data"),
+            Self::NotGRIB { found } => {
+                write!(f, "Not GRIB data (found \"{}\")", display_magic(found))
+            }
             Self::GRIBVersionMismatch(i) => write!(f, "Not GRIB version 2: {}", i),
             Self::UnknownSectionNumber(s) => write!(f, "Unknown section number: {}", s),
             Self::EndSectionMismatch => write!(f, "Content of End Section is not valid"),
@@ -89,6 +146,24 @@ impl Display for ParseError {
             Self::NoGridDefinition(i) => {
                 write!(f, "Grid Definition Section not found at {}", i)
             }
+            Self::ImplausibleMessageSize { declared } => {
+                write!(f, "Implausible message size: {}", declared)
+            }
+            Self::SectionTooShort {
+                section_num,
+                minimum,
+                declared,
+            } => write!(
+                f,
+                "Size of Section {} ({}) is smaller than the minimum ({})",
+                section_num, declared, minimum
+            ),
+            Self::IncompatibleTemplates(first, second) => write!(
+                f,
+                "Template {} cannot be used with template {}",
+                first, second
+            ),
+            Self::GribMagicNotFound => write!(f, "\"GRIB\" not found in the data"),
         }
     }
 }
@@ -134,3 +209,289 @@ impl Display for ValidationError {
         }
     }
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GridError {
+    /// The latitude of the last grid point computed from the first one and
+    /// the increment (first) does not match the declared one (second).
+    /// Values are in units of 10^-6 degree.
+    InconsistentLatitudes(i64, i64),
+    /// The longitude of the last grid point computed from the first one and
+    /// the increment (first) does not match the declared one (second).
+    /// Values are in units of 10^-6 degree.
+    InconsistentLongitudes(i64, i64),
+    /// The number of octets for each number of points in the optional list
+    /// of a quasi-regular grid is not from 1 to 4.
+    InvalidPointListOctets(u8),
+    /// The grid has no points since Ni or Nj is 0.
+    EmptyGrid,
+}
+
+impl Error for GridError {
+    fn description(&self) -> &str {
+        "grid error"
+    }
+}
+
+impl Display for GridError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::InconsistentLatitudes(computed, declared) => write!(
+                f,
+                "Latitude of the last grid point computed from increments ({}) does not match the declared one ({})",
+                computed, declared
+            ),
+            Self::InconsistentLongitudes(computed, declared) => write!(
+                f,
+                "Longitude of the last grid point computed from increments ({}) does not match the declared one ({})",
+                computed, declared
+            ),
+            Self::InvalidPointListOctets(octets) => write!(
+                f,
+                "Number of octets for the optional list of numbers of points ({}) is not from 1 to 4",
+                octets
+            ),
+            Self::EmptyGrid => write!(f, "Grid has no points"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ResampleError {
+    /// Resampling from the grid of the source field is not supported.
+    UnsupportedSourceGrid,
+    /// The number of values does not match that of grid points in the source
+    /// field (first: values, second: grid points).
+    LengthMismatch(usize, usize),
+}
+
+impl Error for ResampleError {
+    fn description(&self) -> &str {
+        "resample error"
+    }
+}
+
+impl Display for ResampleError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::UnsupportedSourceGrid => {
+                write!(f, "Resampling from the source grid is not supported")
+            }
+            Self::LengthMismatch(values, points) => write!(
+                f,
+                "Number of values ({}) does not match that of grid points ({})",
+                values, points
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ShapeError {
+    /// The number of values (first) does not match that of grid points
+    /// (second).
+    LengthMismatch(usize, usize),
+}
+
+impl Error for ShapeError {
+    fn description(&self) -> &str {
+        "shape error"
+    }
+}
+
+impl Display for ShapeError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::LengthMismatch(values, points) => write!(
+                f,
+                "Number of values ({}) does not match that of grid points ({})",
+                values, points
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EncodeError {
+    /// The number of values (first) does not match that of grid points
+    /// (second).
+    LengthMismatch(usize, usize),
+    /// A value to be encoded at the grid point `index` is NaN or infinite.
+    NonFiniteValue { index: usize },
+    /// The bit-map has too few octets (first) to cover all grid points
+    /// (second).
+    BitMapTooShort(usize, usize),
+}
+
+impl Error for EncodeError {
+    fn description(&self) -> &str {
+        "encode error"
+    }
+}
+
+impl Display for EncodeError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::LengthMismatch(values, points) => write!(
+                f,
+                "Number of values ({}) does not match that of grid points ({})",
+                values, points
+            ),
+            Self::NonFiniteValue { index } => {
+                write!(f, "Value at {} to be encoded is not finite", index)
+            }
+            Self::BitMapTooShort(octets, points) => write!(
+                f,
+                "Bit-map of {} octets is too short for {} grid points",
+                octets, points
+            ),
+        }
+    }
+}
+
+/// Reasons why decoded fields cannot be arranged on a common grid by the
+/// writers in the `export` module.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum LayoutError {
+    /// Fields are not on a regular latitude/longitude grid.
+    UnsupportedGrid,
+    /// Fields are not on the same grid.
+    GridMismatch,
+    /// The number of values of a field (first) does not match that of grid
+    /// points (second).
+    LengthMismatch(usize, usize),
+    /// A field has no valid time or no parameter, which are needed to place
+    /// it in the output.
+    MissingMetadata,
+    /// More than one field has the same parameter and valid time.
+    DuplicateField,
+}
+
+impl Error for LayoutError {
+    fn description(&self) -> &str {
+        "layout error"
+    }
+}
+
+impl Display for LayoutError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::UnsupportedGrid => write!(f, "Grid other than Template 3.0 is not supported"),
+            Self::GridMismatch => write!(f, "Fields are not on the same grid"),
+            Self::LengthMismatch(values, points) => write!(
+                f,
+                "Number of values ({}) does not match that of grid points ({})",
+                values, points
+            ),
+            Self::MissingMetadata => write!(f, "Valid time or parameter of a field is missing"),
+            Self::DuplicateField => {
+                write!(
+                    f,
+                    "More than one field has the same parameter and valid time"
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ZarrError {
+    /// Writing files of the store failed.
+    IoError(String),
+    LayoutError(LayoutError),
+}
+
+impl Error for ZarrError {
+    fn description(&self) -> &str {
+        "zarr error"
+    }
+}
+
+impl From<io::Error> for ZarrError {
+    fn from(e: io::Error) -> Self {
+        Self::IoError(e.to_string())
+    }
+}
+
+impl From<LayoutError> for ZarrError {
+    fn from(e: LayoutError) -> Self {
+        Self::LayoutError(e)
+    }
+}
+
+impl Display for ZarrError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(s) => write!(f, "I/O error: {}", s),
+            Self::LayoutError(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum NetcdfError {
+    /// Writing the file failed.
+    IoError(String),
+    LayoutError(LayoutError),
+}
+
+impl Error for NetcdfError {
+    fn description(&self) -> &str {
+        "netcdf error"
+    }
+}
+
+impl From<io::Error> for NetcdfError {
+    fn from(e: io::Error) -> Self {
+        Self::IoError(e.to_string())
+    }
+}
+
+impl From<LayoutError> for NetcdfError {
+    fn from(e: LayoutError) -> Self {
+        Self::LayoutError(e)
+    }
+}
+
+impl Display for NetcdfError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::IoError(s) => write!(f, "I/O error: {}", s),
+            Self::LayoutError(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Shows octets which are expected to be a magic number such as "GRIB",
+/// with printable ASCII characters as they are and the others in hex.
+pub fn display_magic(bytes: &[u8; 4]) -> String {
+    bytes
+        .iter()
+        .map(|b| {
+            if b.is_ascii_graphic() || *b == b' ' {
+                char::from(*b).to_string()
+            } else {
+                format!("\\x{:02x}", b)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn magic_displayed_in_readable_form() {
+        assert_eq!(display_magic(b"BUFR"), "BUFR");
+        assert_eq!(display_magic(&[0xfd, b'7', b'z', b'X']), "\\xfd7zX");
+        assert_eq!(
+            display_magic(&[0x1f, 0x8b, 0x08, 0x00]),
+            "\\x1f\\x8b\\x08\\x00"
+        );
+        assert_eq!(
+            ParseError::NotGRIB { found: *b"BUFR" }.to_string(),
+            "Not GRIB data (found \"BUFR\")"
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! Writers of decoded fields in formats for other tools.
+
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::error::LayoutError;
+use crate::field::DecodedField;
+use crate::grid::{Grid, GridTemplate0};
+
+#[cfg(feature = "netcdf")]
+mod netcdf;
+#[cfg(feature = "zarr")]
+mod zarr;
+
+#[cfg(feature = "netcdf")]
+pub use self::netcdf::*;
+#[cfg(feature = "zarr")]
+pub use self::zarr::*;
+
+/// Parameter identified by the discipline and the parameter category and
+/// number
+type ParamKey = (u8, u8, u8);
+
+/// Decoded fields arranged by parameter and valid time on a common regular
+/// latitude/longitude grid, which is shared by writers of gridded formats.
+struct FieldLayout<'a> {
+    grid: &'a GridTemplate0,
+    times: Vec<DateTime<Utc>>,
+    params: BTreeMap<ParamKey, BTreeMap<DateTime<Utc>, &'a DecodedField>>,
+}
+
+impl<'a> FieldLayout<'a> {
+    /// Arranges `fields`, or returns `None` if there are no fields.
+    fn new(fields: &'a [DecodedField]) -> Result<Option<Self>, LayoutError> {
+        let first = match fields.first() {
+            Some(field) => field,
+            None => return Ok(None),
+        };
+        let grid = match &first.grid {
+            Grid::LatLon(tmpl) => tmpl,
+            _ => return Err(LayoutError::UnsupportedGrid),
+        };
+        if fields.iter().any(|field| field.grid != first.grid) {
+            return Err(LayoutError::GridMismatch);
+        }
+        let num_points = grid.ni as usize * grid.nj as usize;
+
+        let mut times = BTreeSet::new();
+        let mut params = BTreeMap::<_, BTreeMap<DateTime<Utc>, &DecodedField>>::new();
+        for field in fields {
+            let (valid_time, parameter) = match (field.valid_time, &field.parameter) {
+                (Some(time), Some(parameter)) => (time, parameter),
+                _ => return Err(LayoutError::MissingMetadata),
+            };
+            if field.data.len() != num_points {
+                return Err(LayoutError::LengthMismatch(field.data.len(), num_points));
+            }
+            times.insert(valid_time);
+            let key = (parameter.discipline, parameter.category, parameter.number);
+            if params
+                .entry(key)
+                .or_default()
+                .insert(valid_time, field)
+                .is_some()
+            {
+                return Err(LayoutError::DuplicateField);
+            }
+        }
+
+        Ok(Some(Self {
+            grid,
+            times: times.into_iter().collect(),
+            params,
+        }))
+    }
+
+    /// Returns the name of the variable for a parameter.
+    fn var_name((discipline, category, number): ParamKey) -> String {
+        format!("param_{}_{}_{}", discipline, category, number)
+    }
+
+    /// Returns a textual representation of a parameter taken from one of its
+    /// fields.
+    fn describe(by_time: &BTreeMap<DateTime<Utc>, &DecodedField>) -> String {
+        by_time
+            .values()
+            .next()
+            .and_then(|field| field.parameter.as_ref())
+            .map(|parameter| parameter.describe())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(feature = "zarr")]
+impl FieldLayout<'_> {
+    fn ni(&self) -> usize {
+        self.grid.ni as usize
+    }
+
+    fn nj(&self) -> usize {
+        self.grid.nj as usize
+    }
+
+    /// Latitudes of rows in the order of scanning
+    fn lats(&self) -> Vec<f64> {
+        (0..self.nj()).map(|j| self.grid.lat_deg_of(j)).collect()
+    }
+
+    /// Longitudes of columns in the order of scanning
+    fn lons(&self) -> Vec<f64> {
+        (0..self.ni()).map(|i| self.grid.lon_deg_of(i)).collect()
+    }
+
+    /// Returns the value of `field` at the `i`-th point of the `j`-th row,
+    /// or NaN if the point is outside the grid or has no valid data.
+    fn value_at(&self, field: &DecodedField, i: usize, j: usize) -> f32 {
+        self.grid
+            .linear_index(i, j)
+            .map(|index| field.data[index])
+            .filter(|value| *value != field.fill_value)
+            .unwrap_or(f32::NAN)
+    }
+}
+
+#[cfg(test)]
+mod test_helpers {
+    use chrono::{TimeZone, Utc};
+
+    use crate::field::{DecodedField, ParameterInfo};
+    use crate::grid::{Grid, GridTemplate0};
+
+    // 3 x 2 grid from (10N, 0E) to (9N, 2E) with increments of 1 degree
+    fn small_grid() -> Grid {
+        Grid::LatLon(GridTemplate0 {
+            earth_shape: 6,
+            ni: 3,
+            nj: 2,
+            first_point_lat: 10_000_000,
+            first_point_lon: 0,
+            resolution_and_component_flags: 0b00110000,
+            last_point_lat: 9_000_000,
+            last_point_lon: 2_000_000,
+            i_direction_inc: 1_000_000,
+            j_direction_inc: 1_000_000,
+            scanning_mode: 0b00000000,
+        })
+    }
+
+    pub(super) fn field(number: u8, hour: u32, data: Vec<f32>) -> DecodedField {
+        DecodedField {
+            data: data.into_boxed_slice(),
+            grid: small_grid(),
+            ref_time: None,
+            valid_time: Some(Utc.with_ymd_and_hms(2021, 5, 18, hour, 0, 0).unwrap()),
+            parameter: Some(ParameterInfo {
+                discipline: 0,
+                category: 0,
+                number,
+            }),
+            fill_value: -999.,
+        }
+    }
+}
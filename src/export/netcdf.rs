@@ -0,0 +1,587 @@
+use chrono::{DateTime, Utc};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use super::{FieldLayout, ParamKey};
+use crate::context::Grib2;
+use crate::error::{GribError, NetcdfError};
+use crate::field::DecodedField;
+use crate::grid::{Grid, GridTemplate0};
+use crate::reader::Grib2Read;
+
+const NC_DIMENSION: u32 = 0x0a;
+const NC_VARIABLE: u32 = 0x0b;
+const NC_ATTRIBUTE: u32 = 0x0c;
+const NC_CHAR: u32 = 2;
+const NC_FLOAT: u32 = 5;
+const NC_DOUBLE: u32 = 6;
+
+const DIM_TIME: u32 = 0;
+const DIM_LAT: u32 = 1;
+const DIM_LON: u32 = 2;
+
+/// Decodes the submessages of `grib2` and writes them into a NetCDF file at
+/// `output` in the format of [`write_netcdf`], returning the number of
+/// submessages written.
+///
+/// Dimensions and variables are determined from metadata of the submessages
+/// without decoding them, and then records are written time by time,
+/// decoding one submessage at a time, so that decoded values of the whole
+/// file are never held in memory.
+///
+/// The grid of the first submessage on a regular latitude/longitude grid is
+/// used. Submessages on other grids or without a valid time or a parameter
+/// are skipped, as are those with the same parameter and valid time as a
+/// preceding one.
+pub fn decode_to_netcdf<R: Grib2Read, P: AsRef<Path>>(
+    grib2: &Grib2<R>,
+    output: P,
+) -> Result<usize, GribError> {
+    let mut grid = None;
+    let mut times = BTreeSet::new();
+    let mut params = BTreeMap::<ParamKey, (String, BTreeMap<DateTime<Utc>, usize>)>::new();
+    for (index, submessage) in grib2.iter().enumerate() {
+        let tmpl = match submessage.grid() {
+            Ok(Grid::LatLon(tmpl)) => tmpl,
+            _ => continue,
+        };
+        if *grid.get_or_insert_with(|| tmpl.clone()) != tmpl {
+            continue;
+        }
+        let (valid_time, parameter) = match (submessage.valid_time(), submessage.parameter()) {
+            (Some(time), Some(parameter)) => (time, parameter),
+            _ => continue,
+        };
+        let key = (parameter.discipline, parameter.category, parameter.number);
+        let (_, by_time) = params
+            .entry(key)
+            .or_insert_with(|| (parameter.describe(), BTreeMap::new()));
+        by_time.entry(valid_time).or_insert(index);
+        times.insert(valid_time);
+    }
+
+    let out = BufWriter::new(File::create(output).map_err(NetcdfError::from)?);
+    let grid = match grid {
+        Some(grid) => grid,
+        None => {
+            write_empty_file(out)?;
+            return Ok(0);
+        }
+    };
+    let times = times.into_iter().collect::<Vec<_>>();
+    let vars = params
+        .iter()
+        .map(|(key, (description, _))| (*key, description.clone()))
+        .collect::<Vec<_>>();
+    let mut count = 0;
+    write_file::<_, GribError, _>(out, &grid, &times, &vars, |key, time| {
+        match params[&key].1.get(&time) {
+            Some(index) => {
+                count += 1;
+                let values = grib2.get_values(*index)?;
+                Ok(Some((Cow::Owned(values.into_vec()), f32::NAN)))
+            }
+            None => Ok(None),
+        }
+    })?;
+    Ok(count)
+}
+
+/// Writes decoded fields into a file at `path` in the NetCDF classic format
+/// with 64-bit offsets (CDF-2), which can be read by the NetCDF library and
+/// tools built on it.
+///
+/// The file has 1-dimensional coordinate variables `lat`, `lon` and `time`,
+/// where `time` is the unlimited dimension, and one 3-dimensional variable
+/// of `time` x `lat` x `lon` per parameter, named
+/// `param_<discipline>_<category>_<number>`. Times are valid times in
+/// seconds since the Unix epoch, following the CF conventions.
+///
+/// All fields must be on the same regular latitude/longitude grid and have a
+/// valid time and a parameter. Values are stored as `float`, with grid
+/// points without valid data and times for which a parameter has no field
+/// set to NaN, which is declared as `_FillValue`.
+pub fn write_netcdf(path: &Path, fields: &[DecodedField]) -> Result<(), NetcdfError> {
+    let out = BufWriter::new(File::create(path)?);
+    let layout = match FieldLayout::new(fields)? {
+        Some(layout) => layout,
+        None => return write_empty_file(out),
+    };
+    let vars = layout
+        .params
+        .iter()
+        .map(|(key, by_time)| (*key, FieldLayout::describe(by_time)))
+        .collect::<Vec<_>>();
+    write_file(out, layout.grid, &layout.times, &vars, |key, time| {
+        Ok(layout.params[&key]
+            .get(&time)
+            .map(|field| (Cow::Borrowed(&field.data[..]), field.fill_value)))
+    })
+}
+
+/// Writes a file without dimensions, attributes or variables.
+fn write_empty_file<W: Write>(mut out: W) -> Result<(), NetcdfError> {
+    let mut header = Header::new(0);
+    header.put_u32s(&[0; 6]);
+    out.write_all(&header.buf)?;
+    Ok(out.flush()?)
+}
+
+/// Values of a field on the grid and the value at grid points without valid
+/// data
+type FieldValues<'a> = (Cow<'a, [f32]>, f32);
+
+/// Writes a file with one record per time in `times`, each of which holds the
+/// values of the parameters in `params` returned by `values_of`, or NaN for a
+/// parameter without values at the time.
+fn write_file<'a, W, E, F>(
+    mut out: W,
+    grid: &GridTemplate0,
+    times: &[DateTime<Utc>],
+    params: &[(ParamKey, String)],
+    mut values_of: F,
+) -> Result<(), E>
+where
+    W: Write,
+    E: From<NetcdfError>,
+    F: FnMut(ParamKey, DateTime<Utc>) -> Result<Option<FieldValues<'a>>, E>,
+{
+    let (ni, nj) = (grid.ni as usize, grid.nj as usize);
+
+    let mut vars = vec![
+        Variable {
+            name: "lat".to_owned(),
+            dims: vec![DIM_LAT],
+            attrs: vec![
+                Attribute::text("standard_name", "latitude"),
+                Attribute::text("units", "degrees_north"),
+            ],
+            nc_type: NC_DOUBLE,
+            size: nj * 8,
+        },
+        Variable {
+            name: "lon".to_owned(),
+            dims: vec![DIM_LON],
+            attrs: vec![
+                Attribute::text("standard_name", "longitude"),
+                Attribute::text("units", "degrees_east"),
+            ],
+            nc_type: NC_DOUBLE,
+            size: ni * 8,
+        },
+        Variable {
+            name: "time".to_owned(),
+            dims: vec![DIM_TIME],
+            attrs: vec![
+                Attribute::text("standard_name", "time"),
+                Attribute::text("units", "seconds since 1970-01-01 00:00:00"),
+                Attribute::text("calendar", "proleptic_gregorian"),
+            ],
+            nc_type: NC_DOUBLE,
+            size: 8,
+        },
+    ];
+    for (key, description) in params {
+        vars.push(Variable {
+            name: FieldLayout::var_name(*key),
+            dims: vec![DIM_TIME, DIM_LAT, DIM_LON],
+            attrs: vec![
+                Attribute::text("long_name", description),
+                Attribute::float("_FillValue", f32::NAN),
+            ],
+            nc_type: NC_FLOAT,
+            size: nj * ni * 4,
+        });
+    }
+
+    let io_error = |e: std::io::Error| E::from(NetcdfError::from(e));
+
+    // The header is built twice since offsets of data depend on its size,
+    // which does not depend on the offsets themselves.
+    let header_len = build_header(nj, ni, times.len(), &vars, 0).len() as u64;
+    let header = build_header(nj, ni, times.len(), &vars, header_len);
+    out.write_all(&header).map_err(io_error)?;
+
+    let lats = (0..nj).map(|j| grid.lat_deg_of(j));
+    let lons = (0..ni).map(|i| grid.lon_deg_of(i));
+    for value in lats.chain(lons) {
+        out.write_all(&value.to_be_bytes()).map_err(io_error)?;
+    }
+    for time in times {
+        out.write_all(&(time.timestamp() as f64).to_be_bytes())
+            .map_err(io_error)?;
+        for (key, _) in params {
+            match values_of(*key, *time)? {
+                Some((data, fill_value)) => {
+                    for j in 0..nj {
+                        for i in 0..ni {
+                            let value = grid
+                                .linear_index(i, j)
+                                .and_then(|index| data.get(index).copied())
+                                .filter(|value| *value != fill_value)
+                                .unwrap_or(f32::NAN);
+                            out.write_all(&value.to_be_bytes()).map_err(io_error)?;
+                        }
+                    }
+                }
+                None => {
+                    for _ in 0..nj * ni {
+                        out.write_all(&f32::NAN.to_be_bytes()).map_err(io_error)?;
+                    }
+                }
+            }
+        }
+    }
+    out.flush().map_err(io_error)
+}
+
+struct Variable {
+    name: String,
+    dims: Vec<u32>,
+    attrs: Vec<Attribute>,
+    nc_type: u32,
+    /// Size in octets of the whole variable, or of one record for record
+    /// variables
+    size: usize,
+}
+
+impl Variable {
+    fn is_record(&self) -> bool {
+        self.dims.first() == Some(&DIM_TIME)
+    }
+}
+
+struct Attribute {
+    name: String,
+    nc_type: u32,
+    len: usize,
+    values: Vec<u8>,
+}
+
+impl Attribute {
+    fn text(name: &str, value: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            nc_type: NC_CHAR,
+            len: value.len(),
+            values: value.as_bytes().to_vec(),
+        }
+    }
+
+    fn float(name: &str, value: f32) -> Self {
+        Self {
+            name: name.to_owned(),
+            nc_type: NC_FLOAT,
+            len: 1,
+            values: value.to_be_bytes().to_vec(),
+        }
+    }
+}
+
+fn build_header(
+    nj: usize,
+    ni: usize,
+    num_records: usize,
+    vars: &[Variable],
+    data_start: u64,
+) -> Vec<u8> {
+    let mut header = Header::new(num_records);
+
+    header.put_u32s(&[NC_DIMENSION, 3]);
+    header.put_name("time");
+    header.put_u32s(&[0]); // unlimited
+    header.put_name("lat");
+    header.put_u32s(&[nj as u32]);
+    header.put_name("lon");
+    header.put_u32s(&[ni as u32]);
+
+    header.put_u32s(&[NC_ATTRIBUTE, 1]);
+    header.put_attr(&Attribute::text("Conventions", "CF-1.6"));
+
+    header.put_u32s(&[NC_VARIABLE, vars.len() as u32]);
+    let non_record_size = vars
+        .iter()
+        .filter(|var| !var.is_record())
+        .map(|var| var.size as u64)
+        .sum::<u64>();
+    let mut non_record_begin = data_start;
+    let mut record_begin = data_start + non_record_size;
+    for var in vars {
+        header.put_name(&var.name);
+        header.put_u32s(&[var.dims.len() as u32]);
+        header.put_u32s(&var.dims);
+        header.put_u32s(&[NC_ATTRIBUTE, var.attrs.len() as u32]);
+        for attr in var.attrs.iter() {
+            header.put_attr(attr);
+        }
+        // sizes not fitting in 32 bits are written as the largest value, as
+        // allowed for the last variable
+        let vsize = u32::try_from(var.size).unwrap_or(u32::MAX);
+        header.put_u32s(&[var.nc_type, vsize]);
+        let begin = if var.is_record() {
+            &mut record_begin
+        } else {
+            &mut non_record_begin
+        };
+        header.buf.extend_from_slice(&begin.to_be_bytes());
+        *begin += var.size as u64;
+    }
+    header.buf
+}
+
+struct Header {
+    buf: Vec<u8>,
+}
+
+impl Header {
+    fn new(num_records: usize) -> Self {
+        let mut buf = b"CDF\x02".to_vec();
+        buf.extend_from_slice(&(num_records as u32).to_be_bytes());
+        Self { buf }
+    }
+
+    fn put_u32s(&mut self, values: &[u32]) {
+        for value in values {
+            self.buf.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+
+    fn put_padded(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+        let padding = (4 - bytes.len() % 4) % 4;
+        self.buf.extend(std::iter::repeat_n(0, padding));
+    }
+
+    fn put_name(&mut self, name: &str) {
+        self.put_u32s(&[name.len() as u32]);
+        self.put_padded(name.as_bytes());
+    }
+
+    fn put_attr(&mut self, attr: &Attribute) {
+        self.put_name(&attr.name);
+        self.put_u32s(&[attr.nc_type, attr.len as u32]);
+        self.put_padded(&attr.values);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::LayoutError;
+    use crate::export::test_helpers::field;
+
+    #[derive(Debug)]
+    struct VarInfo {
+        name: String,
+        dims: Vec<u32>,
+        attrs: Vec<(String, u32, Vec<u8>)>,
+        vsize: u32,
+        begin: u64,
+    }
+
+    // Minimal reader of headers written by `write_netcdf`
+    struct HeaderReader<'a> {
+        bytes: &'a [u8],
+        pos: usize,
+    }
+
+    impl HeaderReader<'_> {
+        fn take(&mut self, len: usize) -> &[u8] {
+            let slice = &self.bytes[self.pos..self.pos + len];
+            self.pos += len.div_ceil(4) * 4;
+            slice
+        }
+
+        fn u32(&mut self) -> u32 {
+            u32::from_be_bytes(self.take(4).try_into().unwrap())
+        }
+
+        fn name(&mut self) -> String {
+            let len = self.u32() as usize;
+            String::from_utf8(self.take(len).to_vec()).unwrap()
+        }
+
+        fn attrs(&mut self) -> Vec<(String, u32, Vec<u8>)> {
+            assert_eq!(self.u32(), NC_ATTRIBUTE);
+            (0..self.u32())
+                .map(|_| {
+                    let name = self.name();
+                    let nc_type = self.u32();
+                    let len = self.u32() as usize;
+                    let size = if nc_type == NC_CHAR { len } else { len * 4 };
+                    (name, nc_type, self.take(size).to_vec())
+                })
+                .collect()
+        }
+    }
+
+    fn read_header(bytes: &[u8]) -> (u32, Vec<(String, u32)>, Vec<VarInfo>) {
+        assert_eq!(&bytes[..4], b"CDF\x02");
+        let mut reader = HeaderReader { bytes, pos: 4 };
+        let num_records = reader.u32();
+        assert_eq!(reader.u32(), NC_DIMENSION);
+        let dims = (0..reader.u32())
+            .map(|_| (reader.name(), reader.u32()))
+            .collect();
+        reader.attrs();
+        assert_eq!(reader.u32(), NC_VARIABLE);
+        let vars = (0..reader.u32())
+            .map(|_| {
+                let name = reader.name();
+                let num_dims = reader.u32();
+                let dims = (0..num_dims).map(|_| reader.u32()).collect();
+                let attrs = reader.attrs();
+                let _nc_type = reader.u32();
+                let vsize = reader.u32();
+                let begin = u64::from_be_bytes(reader.take(8).try_into().unwrap());
+                VarInfo {
+                    name,
+                    dims,
+                    attrs,
+                    vsize,
+                    begin,
+                }
+            })
+            .collect();
+        (num_records, dims, vars)
+    }
+
+    fn f32s_at(bytes: &[u8], offset: u64, len: usize) -> Vec<f32> {
+        bytes[offset as usize..offset as usize + len * 4]
+            .chunks(4)
+            .map(|quad| f32::from_be_bytes(quad.try_into().unwrap()))
+            .collect()
+    }
+
+    fn f64s_at(bytes: &[u8], offset: u64, len: usize) -> Vec<f64> {
+        bytes[offset as usize..offset as usize + len * 8]
+            .chunks(8)
+            .map(|octets| f64::from_be_bytes(octets.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn write_fields_into_netcdf_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.nc");
+        let fields = vec![
+            field(0, 0, vec![0., 1., 2., 3., 4., 5.]),
+            field(0, 6, vec![6., 7., 8., 9., -999., 11.]),
+            field(2, 6, vec![0.; 6]),
+        ];
+        write_netcdf(&path, &fields).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+
+        let (num_records, dims, vars) = read_header(&bytes);
+        assert_eq!(num_records, 2);
+        assert_eq!(
+            dims,
+            vec![
+                ("time".to_owned(), 0),
+                ("lat".to_owned(), 2),
+                ("lon".to_owned(), 3)
+            ]
+        );
+        let names = vars.iter().map(|var| var.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["lat", "lon", "time", "param_0_0_0", "param_0_0_2"]);
+        assert_eq!(vars[3].dims, vec![DIM_TIME, DIM_LAT, DIM_LON]);
+        assert_eq!(vars[3].attrs[0].2, b"Temperature");
+        assert_eq!(vars[3].attrs[1].0, "_FillValue");
+        assert!(f32s_at(&vars[3].attrs[1].2, 0, 1)[0].is_nan());
+
+        assert_eq!(f64s_at(&bytes, vars[0].begin, 2), vec![10., 9.]);
+        assert_eq!(f64s_at(&bytes, vars[1].begin, 3), vec![0., 1., 2.]);
+        assert_eq!(vars[2].begin, vars[1].begin + 24);
+
+        let record_size = vars[2..]
+            .iter()
+            .map(|var| u64::from(var.vsize))
+            .sum::<u64>();
+        assert_eq!(record_size, 8 + 24 + 24);
+        assert_eq!(bytes.len() as u64, vars[2].begin + 2 * record_size);
+        assert_eq!(
+            f64s_at(&bytes, vars[2].begin + record_size, 1),
+            vec![1621317600.]
+        );
+        assert_eq!(
+            f32s_at(&bytes, vars[3].begin, 6),
+            vec![0., 1., 2., 3., 4., 5.]
+        );
+        let values = f32s_at(&bytes, vars[3].begin + record_size, 6);
+        assert_eq!(values[..4], [6., 7., 8., 9.]);
+        assert!(values[4].is_nan()); // fill value
+        assert!(f32s_at(&bytes, vars[4].begin, 6).iter().all(|v| v.is_nan()));
+        assert_eq!(f32s_at(&bytes, vars[4].begin + record_size, 6), vec![0.; 6]);
+    }
+
+    #[test]
+    fn decode_file_into_netcdf_file() {
+        let buf = crate::utils::bytes_from_xz_file(
+            "testdata/Z__C_RJTD_20170221120000_MSG_GPV_Gll0p5deg_Pys_B20170221120000_F2017022115-2017022212_grib2.bin.xz",
+        );
+        let grib2 = crate::from_slice(&buf).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.nc");
+        let count = decode_to_netcdf(&grib2, &path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(count, 16);
+
+        // 2 parameters at 8 times
+        let (num_records, dims, vars) = read_header(&bytes);
+        assert_eq!(num_records, 8);
+        assert_eq!(
+            dims,
+            vec![
+                ("time".to_owned(), 0),
+                ("lat".to_owned(), 61),
+                ("lon".to_owned(), 81)
+            ]
+        );
+        let names = vars.iter().map(|var| var.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            ["lat", "lon", "time", "param_0_13_192", "param_0_13_193"]
+        );
+
+        let record_size = vars[2..]
+            .iter()
+            .map(|var| u64::from(var.vsize))
+            .sum::<u64>();
+        assert_eq!(bytes.len() as u64, vars[2].begin + 8 * record_size);
+        let first_time = grib2.iter().next().unwrap().valid_time().unwrap();
+        assert_eq!(
+            f64s_at(&bytes, vars[2].begin, 1),
+            vec![first_time.timestamp() as f64]
+        );
+        // submessage 3 holds the second parameter at the second time
+        let expected = grib2.get_values(3).unwrap();
+        let actual = f32s_at(&bytes, vars[4].begin + record_size, 61 * 81);
+        assert!(actual
+            .iter()
+            .zip(expected.iter())
+            .all(|(a, e)| a == e || (a.is_nan() && e.is_nan())));
+    }
+
+    #[test]
+    fn write_no_fields_into_netcdf_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.nc");
+        write_netcdf(&path, &[]).unwrap();
+        let mut expected = b"CDF\x02".to_vec();
+        expected.extend_from_slice(&[0; 28]);
+        assert_eq!(std::fs::read(&path).unwrap(), expected);
+    }
+
+    #[test]
+    fn write_duplicate_fields_into_netcdf_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let fields = vec![field(0, 0, vec![0.; 6]), field(0, 0, vec![1.; 6])];
+        assert_eq!(
+            write_netcdf(&dir.path().join("out.nc"), &fields),
+            Err(NetcdfError::LayoutError(LayoutError::DuplicateField))
+        );
+    }
+}
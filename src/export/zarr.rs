@@ -0,0 +1,409 @@
+use std::fs;
+use std::path::Path;
+
+use super::FieldLayout;
+use crate::error::ZarrError;
+use crate::field::DecodedField;
+
+/// Options for [`write_zarr_with_options`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZarrOptions {
+    /// Numbers of points in a chunk along latitude and longitude. Defaults to
+    /// 256 x 256.
+    pub chunk_shape: (usize, usize),
+    /// Compressor applied to chunks of all arrays. Defaults to
+    /// [`ZarrCompressor::None`].
+    pub compressor: ZarrCompressor,
+}
+
+impl Default for ZarrOptions {
+    fn default() -> Self {
+        Self {
+            chunk_shape: (256, 256),
+            compressor: ZarrCompressor::None,
+        }
+    }
+}
+
+/// Compressors of chunks written by [`write_zarr_with_options`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZarrCompressor {
+    /// Chunks are stored uncompressed.
+    None,
+    /// Chunks are compressed with Zstandard at the specified level, which
+    /// is the `zstd` codec of numcodecs. Requires the `zarr-zstd` feature.
+    #[cfg(feature = "zarr-zstd")]
+    Zstd { level: i32 },
+}
+
+impl ZarrCompressor {
+    fn to_json(self) -> String {
+        match self {
+            Self::None => "null".to_owned(),
+            #[cfg(feature = "zarr-zstd")]
+            Self::Zstd { level } => format!("{{\"id\": \"zstd\", \"level\": {}}}", level),
+        }
+    }
+
+    fn compress(self, bytes: Vec<u8>) -> Result<Vec<u8>, ZarrError> {
+        match self {
+            Self::None => Ok(bytes),
+            #[cfg(feature = "zarr-zstd")]
+            Self::Zstd { level } => Ok(zstd::bulk::compress(&bytes, level)?),
+        }
+    }
+}
+
+/// Writes decoded fields into a Zarr (version 2) store at `store_path` with
+/// the default options.
+///
+/// See [`write_zarr_with_options`] for the layout of the store.
+pub fn write_zarr(store_path: &Path, fields: &[DecodedField]) -> Result<(), ZarrError> {
+    write_zarr_with_options(store_path, fields, &ZarrOptions::default())
+}
+
+/// Writes decoded fields into a Zarr (version 2) store at `store_path`.
+///
+/// The store is a group with 1-dimensional coordinate arrays `lat`, `lon` and
+/// `time`, and one 3-dimensional array of `time` x `lat` x `lon` per
+/// parameter, named `param_<discipline>_<category>_<number>`. Times are valid
+/// times in seconds since the Unix epoch, following the CF conventions.
+/// Dimension names are recorded in the `_ARRAY_DIMENSIONS` attribute so that
+/// xarray can open the store.
+///
+/// All fields must be on the same regular latitude/longitude grid and have a
+/// valid time and a parameter. Values are stored as little-endian `f32`,
+/// with grid points without valid data set to NaN, and chunks are compressed
+/// with [`ZarrOptions::compressor`].
+/// Chunks of times for which a parameter has no field are not written and
+/// read as NaN.
+pub fn write_zarr_with_options(
+    store_path: &Path,
+    fields: &[DecodedField],
+    opts: &ZarrOptions,
+) -> Result<(), ZarrError> {
+    fs::create_dir_all(store_path)?;
+    fs::write(store_path.join(".zgroup"), "{\n    \"zarr_format\": 2\n}\n")?;
+
+    let layout = match FieldLayout::new(fields)? {
+        Some(layout) => layout,
+        None => return Ok(()),
+    };
+    let (ni, nj) = (layout.ni(), layout.nj());
+    let times = &layout.times;
+
+    let compressor = opts.compressor;
+    let lats = layout.lats();
+    write_coordinate(
+        store_path,
+        compressor,
+        "lat",
+        "<f8",
+        "\"NaN\"",
+        f64_bytes(&lats),
+        lats.len(),
+        &[("standard_name", "latitude"), ("units", "degrees_north")],
+    )?;
+    let lons = layout.lons();
+    write_coordinate(
+        store_path,
+        compressor,
+        "lon",
+        "<f8",
+        "\"NaN\"",
+        f64_bytes(&lons),
+        lons.len(),
+        &[("standard_name", "longitude"), ("units", "degrees_east")],
+    )?;
+    let seconds = times
+        .iter()
+        .flat_map(|t| t.timestamp().to_le_bytes())
+        .collect::<Vec<_>>();
+    write_coordinate(
+        store_path,
+        compressor,
+        "time",
+        "<i8",
+        "null",
+        seconds,
+        times.len(),
+        &[
+            ("standard_name", "time"),
+            ("units", "seconds since 1970-01-01 00:00:00"),
+            ("calendar", "proleptic_gregorian"),
+        ],
+    )?;
+
+    let (chunk_nj, chunk_ni) = (opts.chunk_shape.0.max(1), opts.chunk_shape.1.max(1));
+    for (key, by_time) in &layout.params {
+        let name = FieldLayout::var_name(*key);
+        let array_path = store_path.join(&name);
+        fs::create_dir_all(&array_path)?;
+        fs::write(
+            array_path.join(".zarray"),
+            zarray_json(
+                &[times.len(), nj, ni],
+                &[1, chunk_nj, chunk_ni],
+                "<f4",
+                "\"NaN\"",
+                compressor,
+            ),
+        )?;
+        let description = FieldLayout::describe(by_time);
+        fs::write(
+            array_path.join(".zattrs"),
+            zattrs_json(&["time", "lat", "lon"], &[("long_name", &description)]),
+        )?;
+
+        for (t, time) in times.iter().enumerate() {
+            let field = match by_time.get(time) {
+                Some(field) => field,
+                None => continue,
+            };
+            for cj in 0..nj.div_ceil(chunk_nj) {
+                for ci in 0..ni.div_ceil(chunk_ni) {
+                    let mut chunk = Vec::with_capacity(chunk_nj * chunk_ni * 4);
+                    for j in cj * chunk_nj..(cj + 1) * chunk_nj {
+                        for i in ci * chunk_ni..(ci + 1) * chunk_ni {
+                            let value = layout.value_at(field, i, j);
+                            chunk.extend_from_slice(&value.to_le_bytes());
+                        }
+                    }
+                    fs::write(
+                        array_path.join(format!("{}.{}.{}", t, cj, ci)),
+                        compressor.compress(chunk)?,
+                    )?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn write_coordinate(
+    store_path: &Path,
+    compressor: ZarrCompressor,
+    name: &str,
+    dtype: &str,
+    fill_value: &str,
+    bytes: Vec<u8>,
+    len: usize,
+    attrs: &[(&str, &str)],
+) -> Result<(), ZarrError> {
+    let array_path = store_path.join(name);
+    fs::create_dir_all(&array_path)?;
+    fs::write(
+        array_path.join(".zarray"),
+        zarray_json(&[len], &[len.max(1)], dtype, fill_value, compressor),
+    )?;
+    fs::write(array_path.join(".zattrs"), zattrs_json(&[name], attrs))?;
+    if len > 0 {
+        fs::write(array_path.join("0"), compressor.compress(bytes)?)?;
+    }
+    Ok(())
+}
+
+fn f64_bytes(values: &[f64]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn zarray_json(
+    shape: &[usize],
+    chunks: &[usize],
+    dtype: &str,
+    fill_value: &str,
+    compressor: ZarrCompressor,
+) -> String {
+    let join = |values: &[usize]| {
+        values
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    format!(
+        "{{
+    \"chunks\": [{}],
+    \"compressor\": {},
+    \"dtype\": \"{}\",
+    \"fill_value\": {},
+    \"filters\": null,
+    \"order\": \"C\",
+    \"shape\": [{}],
+    \"zarr_format\": 2
+}}
+",
+        join(chunks),
+        compressor.to_json(),
+        dtype,
+        fill_value,
+        join(shape)
+    )
+}
+
+fn zattrs_json(dimensions: &[&str], attrs: &[(&str, &str)]) -> String {
+    let dimensions = dimensions
+        .iter()
+        .map(|name| json_string(name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut entries = vec![format!("    \"_ARRAY_DIMENSIONS\": [{}]", dimensions)];
+    entries.extend(
+        attrs
+            .iter()
+            .map(|(key, value)| format!("    {}: {}", json_string(key), json_string(value))),
+    );
+    format!("{{\n{}\n}}\n", entries.join(",\n"))
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::LayoutError;
+    use crate::export::test_helpers::field;
+
+    fn read_json(path: &Path) -> serde_json::Value {
+        serde_json::from_slice(&fs::read(path).unwrap()).unwrap()
+    }
+
+    fn read_f32s(path: &Path) -> Vec<f32> {
+        fs::read(path)
+            .unwrap()
+            .chunks(4)
+            .map(|quad| f32::from_le_bytes(quad.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn write_fields_into_zarr_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("out.zarr");
+        let fields = vec![
+            field(0, 0, vec![0., 1., 2., 3., 4., 5.]),
+            field(0, 6, vec![6., 7., 8., 9., -999., 11.]),
+            field(2, 6, vec![0.; 6]),
+        ];
+        let opts = ZarrOptions {
+            chunk_shape: (1, 2),
+            ..Default::default()
+        };
+        write_zarr_with_options(&store, &fields, &opts).unwrap();
+
+        assert_eq!(read_json(&store.join(".zgroup"))["zarr_format"], 2);
+
+        let zarray = read_json(&store.join("lat").join(".zarray"));
+        assert_eq!(zarray["shape"], serde_json::json!([2]));
+        assert_eq!(zarray["dtype"], "<f8");
+        let lats = fs::read(store.join("lat").join("0")).unwrap();
+        assert_eq!(&lats[8..], &9_f64.to_le_bytes());
+
+        let zattrs = read_json(&store.join("time").join(".zattrs"));
+        assert_eq!(zattrs["_ARRAY_DIMENSIONS"], serde_json::json!(["time"]));
+        assert_eq!(zattrs["units"], "seconds since 1970-01-01 00:00:00");
+        let times = fs::read(store.join("time").join("0")).unwrap();
+        assert_eq!(&times[..8], &1621296000_i64.to_le_bytes());
+
+        let array = store.join("param_0_0_0");
+        let zarray = read_json(&array.join(".zarray"));
+        assert_eq!(zarray["shape"], serde_json::json!([2, 2, 3]));
+        assert_eq!(zarray["chunks"], serde_json::json!([1, 1, 2]));
+        assert_eq!(zarray["fill_value"], "NaN");
+        assert_eq!(
+            read_json(&array.join(".zattrs"))["long_name"],
+            "Temperature"
+        );
+        assert_eq!(read_f32s(&array.join("0.1.0")), vec![3., 4.]);
+        let edge = read_f32s(&array.join("1.1.1"));
+        assert_eq!(edge[0], 11.);
+        assert!(edge[1].is_nan()); // padding
+        assert!(read_f32s(&array.join("1.1.0"))[1].is_nan()); // fill value
+
+        // chunks for times without fields are not written
+        let array = store.join("param_0_0_2");
+        assert!(!array.join("0.0.0").exists());
+        assert!(array.join("1.0.0").exists());
+    }
+
+    fn read_with_zarrs<T: zarrs::array::ElementOwned>(store: &Path, name: &str) -> Vec<T> {
+        let storage = std::sync::Arc::new(zarrs::filesystem::FilesystemStore::new(store).unwrap());
+        let array = zarrs::array::Array::open(storage, &format!("/{}", name)).unwrap();
+        array
+            .retrieve_array_subset::<Vec<T>>(&array.subset_all())
+            .unwrap()
+    }
+
+    fn assert_store_readable_with_zarrs(opts: &ZarrOptions) {
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("out.zarr");
+        let fields = vec![
+            field(0, 0, vec![0., 1., 2., 3., 4., 5.]),
+            field(0, 6, vec![6., 7., 8., 9., -999., 11.]),
+        ];
+        write_zarr_with_options(&store, &fields, opts).unwrap();
+
+        assert_eq!(read_with_zarrs::<f64>(&store, "lat"), vec![10., 9.]);
+        assert_eq!(read_with_zarrs::<f64>(&store, "lon"), vec![0., 1., 2.]);
+        assert_eq!(
+            read_with_zarrs::<i64>(&store, "time"),
+            vec![1621296000, 1621317600]
+        );
+        let values = read_with_zarrs::<f32>(&store, "param_0_0_0");
+        assert_eq!(values[..10], [0., 1., 2., 3., 4., 5., 6., 7., 8., 9.]);
+        assert!(values[10].is_nan());
+        assert_eq!(values[11], 11.);
+    }
+
+    #[test]
+    fn read_zarr_store_with_zarrs() {
+        assert_store_readable_with_zarrs(&ZarrOptions {
+            chunk_shape: (1, 2),
+            ..Default::default()
+        });
+    }
+
+    #[cfg(feature = "zarr-zstd")]
+    #[test]
+    fn read_zstd_compressed_zarr_store_with_zarrs() {
+        let opts = ZarrOptions {
+            chunk_shape: (1, 2),
+            compressor: ZarrCompressor::Zstd { level: 3 },
+        };
+        assert_store_readable_with_zarrs(&opts);
+
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("out.zarr");
+        write_zarr_with_options(&store, &[field(0, 0, vec![0.; 6])], &opts).unwrap();
+        let zarray = read_json(&store.join("param_0_0_0").join(".zarray"));
+        assert_eq!(
+            zarray["compressor"],
+            serde_json::json!({"id": "zstd", "level": 3})
+        );
+    }
+
+    #[test]
+    fn write_duplicate_fields_into_zarr_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let fields = vec![field(0, 0, vec![0.; 6]), field(0, 0, vec![1.; 6])];
+        assert_eq!(
+            write_zarr(dir.path(), &fields),
+            Err(ZarrError::LayoutError(LayoutError::DuplicateField))
+        );
+    }
+}
@@ -0,0 +1,472 @@
+use chrono::{DateTime, Utc};
+
+use crate::codetables::{CodeTable4_2, Lookup};
+use crate::context::Grib2;
+use crate::error::{GribError, ResampleError};
+use crate::grid::{Grid, GridTemplate0};
+use crate::reader::Grib2Read;
+
+/// Parameter of a submessage identified by the discipline and the parameter
+/// category and number (see Code Table 4.2)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ParameterInfo {
+    /// Discipline (see Code Table 0.0)
+    pub discipline: u8,
+    /// Parameter category (see Code Table 4.1)
+    pub category: u8,
+    /// Parameter number (see Code Table 4.2)
+    pub number: u8,
+}
+
+impl ParameterInfo {
+    /// Returns a textual representation of the parameter.
+    pub fn describe(&self) -> String {
+        CodeTable4_2::new(self.discipline, self.category)
+            .lookup(usize::from(self.number))
+            .to_string()
+    }
+}
+
+/// Latitude and longitude in degrees of a grid point and the value at it
+pub type GridValue = (f64, f64, f32);
+
+/// Decoded values of a submessage together with the metadata needed to
+/// interpret them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedField {
+    /// Decoded values in the scanning order of the grid
+    pub data: Box<[f32]>,
+    /// Grid on which the values are defined
+    pub grid: Grid,
+    /// Reference time of data, or `None` if it is missing
+    pub ref_time: Option<DateTime<Utc>>,
+    /// Forecast valid time, or `None` if it cannot be determined
+    pub valid_time: Option<DateTime<Utc>>,
+    /// Parameter of the values, or `None` if the template does not have one
+    pub parameter: Option<ParameterInfo>,
+    /// Value stored at grid points without valid data
+    pub fill_value: f32,
+}
+
+impl DecodedField {
+    /// Resamples the values onto the `target` grid with bilinear
+    /// interpolation.
+    ///
+    /// Target points coinciding with source grid points take the source
+    /// values as they are. Target points outside the source grid, or next to
+    /// source points without valid data, are set to the fill value.
+    ///
+    /// Only regular latitude/longitude source grids are supported.
+    pub fn resample_to_grid(&self, target: &GridTemplate0) -> Result<DecodedField, ResampleError> {
+        let source = match &self.grid {
+            Grid::LatLon(tmpl) => tmpl,
+            _ => return Err(ResampleError::UnsupportedSourceGrid),
+        };
+        let num_points = self.grid.num_points();
+        if self.data.len() != num_points {
+            return Err(ResampleError::LengthMismatch(self.data.len(), num_points));
+        }
+
+        let target_num_points = target.ni as usize * target.nj as usize;
+        let data = (0..target_num_points)
+            .map(|index| {
+                let (lat, lon) = target.latlon_deg_of(index);
+                source
+                    .position_of(lat, lon)
+                    .and_then(|(x, y)| self.interpolate(source, x, y))
+                    .unwrap_or(self.fill_value)
+            })
+            .collect::<Vec<_>>();
+
+        Ok(DecodedField {
+            data: data.into_boxed_slice(),
+            grid: Grid::LatLon(target.clone()),
+            ref_time: self.ref_time,
+            valid_time: self.valid_time,
+            parameter: self.parameter.clone(),
+            fill_value: self.fill_value,
+        })
+    }
+
+    fn interpolate(&self, source: &GridTemplate0, x: f64, y: f64) -> Option<f32> {
+        const EPS: f64 = 1e-6;
+        let (ni, nj) = (source.ni as usize, source.nj as usize);
+        let (i0, j0) = (x.floor() as usize, y.floor() as usize);
+        let (fx, fy) = (x - i0 as f64, y - j0 as f64);
+        // Neighbors with zero weights are not read, so that exact matches and
+        // points on the edges do not need points beyond them.
+        let (i1, fx) = if fx < EPS {
+            (i0, 0.)
+        } else {
+            ((i0 + 1) % ni, fx)
+        };
+        let (j1, fy) = if fy < EPS || j0 + 1 >= nj {
+            (j0, 0.)
+        } else {
+            (j0 + 1, fy)
+        };
+
+        let value_at = |i: usize, j: usize| {
+            let value = *self.data.get(source.linear_index(i, j)?)?;
+            if value.is_nan() || value == self.fill_value {
+                None
+            } else {
+                Some(f64::from(value))
+            }
+        };
+        let v00 = value_at(i0, j0)?;
+        let v10 = if fx == 0. { v00 } else { value_at(i1, j0)? };
+        let (v01, v11) = if fy == 0. {
+            (v00, v10)
+        } else {
+            let v01 = value_at(i0, j1)?;
+            let v11 = if fx == 0. { v01 } else { value_at(i1, j1)? };
+            (v01, v11)
+        };
+
+        let v0 = v00 + (v10 - v00) * fx;
+        let v1 = v01 + (v11 - v01) * fx;
+        Some((v0 + (v1 - v0) * fy) as f32)
+    }
+}
+
+/// Values of ensemble members for the same parameter, level and time, on a
+/// common latitude/longitude grid.
+///
+/// Statistics are computed point by point. A grid point where any member has
+/// no valid data (NaN) is treated as missing in all statistics, so that the
+/// results at every point are computed from the same set of members.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForecastEnsemble {
+    /// Decoded values of members, each in the scanning order of the grid
+    pub members: Vec<Box<[f32]>>,
+    /// Grid shared by all members
+    pub grid: GridTemplate0,
+}
+
+impl ForecastEnsemble {
+    /// Decodes submessages specified by `indices` as members of an ensemble.
+    ///
+    /// All the submessages must be on the same latitude/longitude grid.
+    /// [`GribError::InvalidArgument`] is returned if `indices` is empty or
+    /// contains an index out of range.
+    ///
+    /// Selecting submessages of the same parameter, level and time is up to
+    /// the caller, e.g. with [`Grib2::filter_by_ensemble`].
+    pub fn from_submessages<R: Grib2Read>(
+        grib2: &Grib2<R>,
+        indices: &[usize],
+    ) -> Result<Self, GribError> {
+        let submessages = indices
+            .iter()
+            .map(|i| {
+                grib2.submessages().nth(*i).ok_or_else(|| {
+                    GribError::InvalidArgument(format!("submessage index {} is out of range", i))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let first = submessages
+            .first()
+            .ok_or_else(|| GribError::InvalidArgument("no members are specified".to_owned()))?;
+        if !submessages.iter().all(|s| s.is_same_grid_as(first)) {
+            return Err(GribError::GridMismatchError);
+        }
+        let grid = match first.grid()? {
+            Grid::LatLon(tmpl) => tmpl,
+            _ => {
+                return Err(GribError::Unsupported(
+                    "ensembles on grids other than Template 3.0".to_owned(),
+                ))
+            }
+        };
+
+        let members = indices
+            .iter()
+            .map(|i| grib2.get_values(*i))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { members, grid })
+    }
+
+    /// Returns the ensemble mean at each grid point.
+    pub fn mean(&self) -> Box<[f32]> {
+        self.map_points(|values| {
+            let sum = values.iter().map(|v| f64::from(*v)).sum::<f64>();
+            (sum / values.len() as f64) as f32
+        })
+    }
+
+    /// Returns the ensemble spread, i.e. the population standard deviation of
+    /// members, at each grid point.
+    pub fn std_dev(&self) -> Box<[f32]> {
+        self.map_points(|values| {
+            let n = values.len() as f64;
+            let mean = values.iter().map(|v| f64::from(*v)).sum::<f64>() / n;
+            let var = values
+                .iter()
+                .map(|v| (f64::from(*v) - mean).powi(2))
+                .sum::<f64>()
+                / n;
+            var.sqrt() as f32
+        })
+    }
+
+    /// Returns the fraction of members whose values exceed `threshold` at
+    /// each grid point, ranging from 0 to 1.
+    pub fn exceedance_probability(&self, threshold: f32) -> Box<[f32]> {
+        self.map_points(|values| {
+            let count = values.iter().filter(|v| **v > threshold).count();
+            count as f32 / values.len() as f32
+        })
+    }
+
+    fn map_points<F>(&self, f: F) -> Box<[f32]>
+    where
+        F: Fn(&[f32]) -> f32,
+    {
+        let num_points = self.members.iter().map(|m| m.len()).min().unwrap_or(0);
+        let mut values = Vec::with_capacity(self.members.len());
+        (0..num_points)
+            .map(|index| {
+                values.clear();
+                values.extend(self.members.iter().map(|m| m[index]));
+                if values.is_empty() || values.iter().any(|v| v.is_nan()) {
+                    f32::NAN
+                } else {
+                    f(&values)
+                }
+            })
+            .collect()
+    }
+}
+
+/// Values and coordinates of the four corner points of a grid, for checking
+/// the orientation of decoded values.
+///
+/// Rows run along the i axis, and the first and last points and rows are
+/// those in the scanning directions (see [`Grid::linear_index`]).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CornerValues {
+    /// First point of the first row
+    pub first: GridValue,
+    /// Last point of the first row
+    pub first_row_last: GridValue,
+    /// First point of the last row
+    pub last_row_first: GridValue,
+    /// Last point of the last row
+    pub last: GridValue,
+}
+
+/// Values of fields on a common grid stacked into a three-dimensional array.
+///
+/// Values are stored in row-major order with the shape `(n, Nj, Ni)`, where
+/// `n` is the number of stacked fields. Within each layer, rows and points
+/// are ordered in the positive j and i directions of the scanning mode (see
+/// [`Grid::linear_index`]), regardless of the order of decoded values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldStack {
+    /// Grid shared by all fields
+    pub grid: Grid,
+    /// Shape of the array, i.e. the number of fields, Nj and Ni
+    pub shape: (usize, usize, usize),
+    /// Stacked values
+    pub values: Box<[f32]>,
+}
+
+impl FieldStack {
+    /// Returns values of the `k`-th field as a `Nj` x `Ni` array in row-major
+    /// order, or `None` if `k` is out of range.
+    pub fn layer(&self, k: usize) -> Option<&[f32]> {
+        let (n, nj, ni) = self.shape;
+        if k >= n {
+            return None;
+        }
+        let size = nj * ni;
+        Some(&self.values[k * size..(k + 1) * size])
+    }
+
+    pub(crate) fn from_layers(grid: Grid, layers: &[Box<[f32]>]) -> Result<Self, GribError> {
+        let (ni, nj) = grid.regular_shape().ok_or_else(|| {
+            GribError::Unsupported("stacking fields on quasi-regular grids".to_owned())
+        })?;
+        let mut values = Vec::with_capacity(layers.len() * nj * ni);
+        for layer in layers {
+            for j in 0..nj {
+                for i in 0..ni {
+                    let value = grid
+                        .linear_index(i, j)
+                        .and_then(|index| layer.get(index))
+                        .ok_or(GribError::InternalDataError)?;
+                    values.push(*value);
+                }
+            }
+        }
+        Ok(Self {
+            grid,
+            shape: (layers.len(), nj, ni),
+            values: values.into_boxed_slice(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 4 x 3 grid from (10N, 0E) to (8N, 3E) with increments of 1 degree
+    fn small_grid() -> GridTemplate0 {
+        GridTemplate0 {
+            earth_shape: 6,
+            ni: 4,
+            nj: 3,
+            first_point_lat: 10_000_000,
+            first_point_lon: 0,
+            resolution_and_component_flags: 0b00110000,
+            last_point_lat: 8_000_000,
+            last_point_lon: 3_000_000,
+            i_direction_inc: 1_000_000,
+            j_direction_inc: 1_000_000,
+            scanning_mode: 0b00000000,
+        }
+    }
+
+    fn small_field() -> DecodedField {
+        DecodedField {
+            data: (0..12).map(|v| v as f32).collect(),
+            grid: Grid::LatLon(small_grid()),
+            ref_time: None,
+            valid_time: None,
+            parameter: None,
+            fill_value: f32::NAN,
+        }
+    }
+
+    fn small_ensemble() -> ForecastEnsemble {
+        let members = [
+            [1., 2., f32::NAN, 0., 0., 0., 0., 0., 0., 0., 0., 0.],
+            [3., 2., 5., 0., 0., 0., 0., 0., 0., 0., 0., 4.],
+            [2., 2., 6., 0., 0., 0., 0., 0., 0., 0., 0., 8.],
+        ];
+        ForecastEnsemble {
+            members: members
+                .iter()
+                .map(|m| m.to_vec().into_boxed_slice())
+                .collect(),
+            grid: small_grid(),
+        }
+    }
+
+    #[test]
+    fn ensemble_mean_and_spread() {
+        let ensemble = small_ensemble();
+        let mean = ensemble.mean();
+        assert_eq!(mean.len(), 12);
+        assert_eq!(&mean[..2], &[2., 2.]);
+        assert!(mean[2].is_nan());
+        assert_eq!(mean[11], 4.);
+
+        let std_dev = ensemble.std_dev();
+        assert!((std_dev[0] - (2_f32 / 3.).sqrt()).abs() < 1e-6);
+        assert_eq!(std_dev[1], 0.);
+        assert!(std_dev[2].is_nan());
+        assert!((std_dev[11] - (32_f32 / 3.).sqrt()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn ensemble_exceedance_probability() {
+        let ensemble = small_ensemble();
+        let prob = ensemble.exceedance_probability(1.5);
+        assert_eq!(&prob[..2], &[2. / 3., 1.]);
+        assert!(prob[2].is_nan());
+        assert_eq!(prob[3], 0.);
+        assert_eq!(prob[11], 2. / 3.);
+    }
+
+    #[test]
+    fn resampling_to_same_grid() {
+        let field = small_field();
+        let resampled = field.resample_to_grid(&small_grid()).unwrap();
+        assert_eq!(resampled.data, field.data);
+        assert_eq!(resampled.grid, field.grid);
+    }
+
+    #[test]
+    fn resampling_to_shifted_grid() {
+        let field = small_field();
+        let target = GridTemplate0 {
+            ni: 4,
+            nj: 2,
+            first_point_lat: 9_500_000,
+            first_point_lon: 500_000,
+            last_point_lat: 8_500_000,
+            last_point_lon: 3_500_000,
+            ..small_grid()
+        };
+        let resampled = field.resample_to_grid(&target).unwrap();
+        let actual = resampled.data.to_vec();
+        assert_eq!(&actual[..3], &[2.5, 3.5, 4.5]);
+        assert!(actual[3].is_nan()); // outside
+        assert_eq!(&actual[4..7], &[6.5, 7.5, 8.5]);
+        assert!(actual[7].is_nan()); // outside
+    }
+
+    #[test]
+    fn resampling_with_missing_values_and_fill_value() {
+        let mut field = small_field();
+        field.data[5] = -999.;
+        field.fill_value = -999.;
+        let target = GridTemplate0 {
+            ni: 2,
+            nj: 1,
+            first_point_lat: 9_500_000,
+            first_point_lon: 500_000,
+            last_point_lat: 9_500_000,
+            last_point_lon: 1_500_000,
+            ..small_grid()
+        };
+        let resampled = field.resample_to_grid(&target).unwrap();
+        assert_eq!(resampled.data.to_vec(), vec![-999., -999.]);
+    }
+
+    #[test]
+    fn resampling_across_meridian_of_global_grid() {
+        let source = GridTemplate0 {
+            ni: 4,
+            nj: 1,
+            first_point_lat: 0,
+            first_point_lon: 0,
+            last_point_lat: 0,
+            last_point_lon: 270_000_000,
+            i_direction_inc: 90_000_000,
+            ..small_grid()
+        };
+        let field = DecodedField {
+            data: vec![0., 1., 2., 3.].into_boxed_slice(),
+            grid: Grid::LatLon(source.clone()),
+            ..small_field()
+        };
+        let target = GridTemplate0 {
+            ni: 1,
+            first_point_lon: 315_000_000,
+            last_point_lon: 315_000_000,
+            ..source
+        };
+        let resampled = field.resample_to_grid(&target).unwrap();
+        assert_eq!(resampled.data.to_vec(), vec![1.5]);
+    }
+
+    #[test]
+    fn resampling_from_unsupported_grid() {
+        let field = DecodedField {
+            grid: Grid::ReducedLatLon(crate::grid::ReducedGridTemplate0 {
+                template: small_grid(),
+                row_lengths: vec![4, 4, 4],
+                list_interpretation: 1,
+            }),
+            ..small_field()
+        };
+        assert_eq!(
+            field.resample_to_grid(&small_grid()),
+            Err(ResampleError::UnsupportedSourceGrid)
+        );
+    }
+}
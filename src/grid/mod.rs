@@ -0,0 +1,1349 @@
+use std::convert::TryInto;
+
+use crate::datatypes::GridDefinition;
+use crate::error::*;
+use crate::utils::{read_as, GribInt};
+
+pub use self::rotated::{GridTemplate1, GridTemplate2, GridTemplate3, Rotation, Stretching};
+pub use self::spectral::SpectralTruncation;
+pub use crate::utils::gauss_legendre_latitudes;
+
+mod rotated;
+mod spectral;
+
+/// Offset of the Grid Definition Template in the payload of Section 3.
+pub(crate) const START_OF_GRID_TEMPLATE: usize = 9;
+
+/// Geometry of a grid defined in the Grid Definition Section (Section 3).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Grid {
+    /// Template 3.0 (latitude/longitude grid)
+    LatLon(GridTemplate0),
+    /// Template 3.0 with a list of numbers of points in rows appended
+    /// (quasi-regular latitude/longitude grid)
+    ReducedLatLon(ReducedGridTemplate0),
+    /// Template 3.1 (rotated latitude/longitude grid)
+    RotatedLatLon(GridTemplate1),
+    /// Template 3.2 (stretched latitude/longitude grid)
+    StretchedLatLon(GridTemplate2),
+    /// Template 3.3 (stretched and rotated latitude/longitude grid)
+    StretchedRotatedLatLon(GridTemplate3),
+}
+
+impl Grid {
+    pub fn from_grid_def(grid_def: &GridDefinition) -> Result<Self, GribError> {
+        if let Some(num) = grid_def.predetermined_grid_number() {
+            return Err(GribError::Unsupported(format!(
+                "predetermined grid {}",
+                num
+            )));
+        }
+        match grid_def.grid_tmpl_num() {
+            0 if grid_def.has_optional_point_list() => Ok(Self::ReducedLatLon(
+                ReducedGridTemplate0::from_grid_def(grid_def)?,
+            )),
+            0 => Ok(Self::LatLon(GridTemplate0::from_grid_def(grid_def)?)),
+            1 => Ok(Self::RotatedLatLon(GridTemplate1::from_grid_def(grid_def)?)),
+            2 => Ok(Self::StretchedLatLon(GridTemplate2::from_grid_def(
+                grid_def,
+            )?)),
+            3 => Ok(Self::StretchedRotatedLatLon(GridTemplate3::from_grid_def(
+                grid_def,
+            )?)),
+            n => Err(GribError::Unsupported(format!(
+                "Grid Definition Template 3.{}",
+                n
+            ))),
+        }
+    }
+
+    /// Parses the grid definition like [`Grid::from_grid_def`] and then
+    /// checks its consistency with [`Grid::validate`].
+    pub fn from_grid_def_validated(grid_def: &GridDefinition) -> Result<Self, GribError> {
+        let grid = Self::from_grid_def(grid_def)?;
+        grid.validate()?;
+        Ok(grid)
+    }
+
+    /// Checks if the last grid point computed from the first grid point and
+    /// increments matches the declared one.
+    ///
+    /// Disagreement of more than half an increment, which makes computed
+    /// coordinates drift away from actual ones, is reported as an error.
+    pub fn validate(&self) -> Result<(), GridError> {
+        match self {
+            Self::LatLon(tmpl) => tmpl.validate(),
+            Self::ReducedLatLon(tmpl) => tmpl.template.validate(),
+            Self::RotatedLatLon(GridTemplate1 { lat_lon, .. })
+            | Self::StretchedLatLon(GridTemplate2 { lat_lon, .. })
+            | Self::StretchedRotatedLatLon(GridTemplate3 { lat_lon, .. }) => lat_lon.validate(),
+        }
+    }
+
+    /// Returns the number of grid points.
+    pub fn num_points(&self) -> usize {
+        match self {
+            Self::LatLon(tmpl) => tmpl.ni as usize * tmpl.nj as usize,
+            Self::ReducedLatLon(tmpl) => tmpl.num_points(),
+            Self::RotatedLatLon(GridTemplate1 { lat_lon, .. })
+            | Self::StretchedLatLon(GridTemplate2 { lat_lon, .. })
+            | Self::StretchedRotatedLatLon(GridTemplate3 { lat_lon, .. }) => {
+                lat_lon.ni as usize * lat_lon.nj as usize
+            }
+        }
+    }
+
+    /// Returns latitudes and longitudes of grid points in degrees, in the
+    /// same order as decoded values.
+    pub fn latlons(&self) -> Vec<(f32, f32)> {
+        match self {
+            Self::LatLon(tmpl) => tmpl.latlons(),
+            Self::ReducedLatLon(tmpl) => tmpl.latlons(),
+            Self::RotatedLatLon(tmpl) => tmpl.latlons(),
+            Self::StretchedLatLon(tmpl) => tmpl.latlons(),
+            Self::StretchedRotatedLatLon(tmpl) => tmpl.latlons(),
+        }
+    }
+
+    /// Returns the latitude and longitude in degrees of the grid point at
+    /// `index` in decoded values, or `None` if the index is out of range.
+    ///
+    /// This is the same as the element of [`Grid::latlons`] at `index`, but
+    /// coordinates of the other points are not computed.
+    pub fn latlon(&self, index: usize) -> Option<(f32, f32)> {
+        match self {
+            Self::LatLon(tmpl) => tmpl.latlon(index),
+            Self::ReducedLatLon(tmpl) => tmpl.latlon(index),
+            Self::RotatedLatLon(tmpl) => tmpl.latlon(index),
+            Self::StretchedLatLon(tmpl) => tmpl.latlon(index),
+            Self::StretchedRotatedLatLon(tmpl) => tmpl.latlon(index),
+        }
+    }
+
+    /// Returns latitudes of rows and longitudes of columns in degrees, in the
+    /// order of scanning, for grids where coordinates of points are
+    /// separable into the two axes.
+    ///
+    /// `None` is returned for quasi-regular, rotated and stretched grids,
+    /// whose points are not on a product of geographic axes.
+    pub fn axes(&self) -> Option<(Vec<f32>, Vec<f32>)> {
+        match self {
+            Self::LatLon(tmpl) => Some(tmpl.axes()),
+            _ => None,
+        }
+    }
+
+    /// Returns weights of grid points proportional to the areas they
+    /// represent, in the same order as decoded values, for computing
+    /// area-weighted statistics.
+    ///
+    /// The weight is the cosine of the latitude of the point. For a
+    /// quasi-regular grid, it is scaled up in rows with fewer points. For
+    /// rotated and stretched grids, the latitude in the transformed system is
+    /// used, so that changes in resolution caused by stretching are not taken
+    /// into account.
+    pub fn area_weights(&self) -> Vec<f32> {
+        match self {
+            Self::LatLon(tmpl) => tmpl.area_weights(),
+            Self::ReducedLatLon(tmpl) => tmpl.area_weights(),
+            Self::RotatedLatLon(GridTemplate1 { lat_lon, .. })
+            | Self::StretchedLatLon(GridTemplate2 { lat_lon, .. })
+            | Self::StretchedRotatedLatLon(GridTemplate3 { lat_lon, .. }) => lat_lon.area_weights(),
+        }
+    }
+
+    /// Returns the index in decoded values of the grid point nearest to the
+    /// specified location, or `None` if the location is outside the grid.
+    pub fn nearest(&self, lat: f32, lon: f32) -> Option<usize> {
+        match self {
+            Self::LatLon(tmpl) => tmpl.nearest(lat, lon),
+            Self::ReducedLatLon(tmpl) => tmpl.nearest(lat, lon),
+            Self::RotatedLatLon(tmpl) => tmpl.nearest(lat, lon),
+            Self::StretchedLatLon(tmpl) => tmpl.nearest(lat, lon),
+            Self::StretchedRotatedLatLon(tmpl) => tmpl.nearest(lat, lon),
+        }
+    }
+
+    /// Returns the numbers of points along the i and j axes, or `None` for
+    /// quasi-regular grids whose rows have different numbers of points.
+    pub(crate) fn regular_shape(&self) -> Option<(usize, usize)> {
+        match self {
+            Self::LatLon(tmpl)
+            | Self::RotatedLatLon(GridTemplate1 { lat_lon: tmpl, .. })
+            | Self::StretchedLatLon(GridTemplate2 { lat_lon: tmpl, .. })
+            | Self::StretchedRotatedLatLon(GridTemplate3 { lat_lon: tmpl, .. }) => {
+                Some((tmpl.ni as usize, tmpl.nj as usize))
+            }
+            Self::ReducedLatLon(_) => None,
+        }
+    }
+
+    /// Returns the index in decoded values of the `i`-th point of the `j`-th
+    /// row, honoring the scanning mode, or `None` if the point is outside the
+    /// grid.
+    ///
+    /// Indices `i` and `j` count points in the positive i and j directions
+    /// of the scanning mode respectively, regardless of whether points in the
+    /// i direction are consecutive or rows are scanned boustrophedonically.
+    /// For a quasi-regular grid, `j` is the row and `i` is the point within
+    /// that row.
+    pub fn linear_index(&self, i: usize, j: usize) -> Option<usize> {
+        match self {
+            Self::LatLon(tmpl) => tmpl.linear_index(i, j),
+            Self::ReducedLatLon(tmpl) => tmpl.linear_index(i, j),
+            Self::RotatedLatLon(GridTemplate1 { lat_lon, .. })
+            | Self::StretchedLatLon(GridTemplate2 { lat_lon, .. })
+            | Self::StretchedRotatedLatLon(GridTemplate3 { lat_lon, .. }) => {
+                lat_lon.linear_index(i, j)
+            }
+        }
+    }
+
+    /// Converts the index in decoded values into indices `(i, j)` along the
+    /// i and j axes, or returns `None` if the index is out of range.
+    ///
+    /// This is the inverse of [`Grid::linear_index`].
+    pub fn grid_indices(&self, linear: usize) -> Option<(usize, usize)> {
+        match self {
+            Self::LatLon(tmpl) => tmpl.grid_indices(linear),
+            Self::ReducedLatLon(tmpl) => tmpl.grid_indices(linear),
+            Self::RotatedLatLon(GridTemplate1 { lat_lon, .. })
+            | Self::StretchedLatLon(GridTemplate2 { lat_lon, .. })
+            | Self::StretchedRotatedLatLon(GridTemplate3 { lat_lon, .. }) => {
+                lat_lon.grid_indices(linear)
+            }
+        }
+    }
+}
+
+/// Grid Definition Template 3.0 (latitude/longitude or equidistant
+/// cylindrical, or Plate Carree).
+///
+/// Latitudes, longitudes and increments are in units of 10^-6 degree.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GridTemplate0 {
+    /// Shape of the Earth (see Code Table 3.2)
+    pub earth_shape: u8,
+    /// Ni - number of points along a parallel
+    pub ni: u32,
+    /// Nj - number of points along a meridian
+    pub nj: u32,
+    /// La1 - latitude of first grid point
+    pub first_point_lat: i32,
+    /// Lo1 - longitude of first grid point
+    pub first_point_lon: i32,
+    /// Resolution and component flags (see Flag Table 3.3)
+    pub resolution_and_component_flags: u8,
+    /// La2 - latitude of last grid point
+    pub last_point_lat: i32,
+    /// Lo2 - longitude of last grid point
+    pub last_point_lon: i32,
+    /// Di - i direction increment
+    pub i_direction_inc: u32,
+    /// Dj - j direction increment
+    pub j_direction_inc: u32,
+    /// Scanning mode (see Flag Table 3.4)
+    pub scanning_mode: u8,
+}
+
+impl GridTemplate0 {
+    const SIZE: usize = 58;
+
+    pub fn from_grid_def(grid_def: &GridDefinition) -> Result<Self, GribError> {
+        let payload = grid_def.iter().as_slice();
+        if payload.len() < START_OF_GRID_TEMPLATE + Self::SIZE {
+            return Err(GribError::InternalDataError);
+        }
+        let buf = &payload[START_OF_GRID_TEMPLATE..];
+
+        Ok(Self {
+            earth_shape: buf[0],
+            ni: read_as!(u32, buf, 16),
+            nj: read_as!(u32, buf, 20),
+            first_point_lat: read_as!(u32, buf, 32).as_grib_int(),
+            first_point_lon: read_as!(u32, buf, 36).as_grib_int(),
+            resolution_and_component_flags: buf[40],
+            last_point_lat: read_as!(u32, buf, 41).as_grib_int(),
+            last_point_lon: read_as!(u32, buf, 45).as_grib_int(),
+            i_direction_inc: read_as!(u32, buf, 49),
+            j_direction_inc: read_as!(u32, buf, 53),
+            scanning_mode: buf[57],
+        })
+    }
+
+    /// Returns the number of rows, i.e. Nj.
+    #[inline]
+    pub fn num_rows(&self) -> u32 {
+        self.nj
+    }
+
+    /// Returns the number of columns, i.e. Ni.
+    #[inline]
+    pub fn num_cols(&self) -> u32 {
+        self.ni
+    }
+
+    /// Returns the increment along a meridian (Dj) in degrees.
+    #[inline]
+    pub fn lat_resolution_deg(&self) -> f64 {
+        f64::from(self.j_direction_inc) * 1e-6
+    }
+
+    /// Returns the increment along a parallel (Di) in degrees.
+    #[inline]
+    pub fn lon_resolution_deg(&self) -> f64 {
+        f64::from(self.i_direction_inc) * 1e-6
+    }
+
+    /// Returns the southernmost and northernmost latitudes in degrees.
+    #[inline]
+    pub fn lat_range(&self) -> (f64, f64) {
+        let first = f64::from(self.first_point_lat) * 1e-6;
+        let last = f64::from(self.last_point_lat) * 1e-6;
+        if self.j_positive() {
+            (first, last)
+        } else {
+            (last, first)
+        }
+    }
+
+    /// Returns the westernmost and easternmost longitudes in degrees.
+    ///
+    /// For grids crossing the meridian where longitudes wrap, the former can
+    /// be larger than the latter.
+    #[inline]
+    pub fn lon_range(&self) -> (f64, f64) {
+        let first = f64::from(self.first_point_lon) * 1e-6;
+        let last = f64::from(self.last_point_lon) * 1e-6;
+        if self.i_positive() {
+            (first, last)
+        } else {
+            (last, first)
+        }
+    }
+
+    /// Returns a human-readable description of the scanning mode, e.g.
+    /// `"i+, j-, i-consecutive"`, which tells the order of decoded values.
+    ///
+    /// `", boustrophedonic"` is appended if adjacent rows scan in the
+    /// opposite direction.
+    pub fn scanning_mode_name(&self) -> String {
+        let i = if self.i_positive() { "i+" } else { "i-" };
+        let j = if self.j_positive() { "j+" } else { "j-" };
+        let consecutive = if self.j_consecutive() {
+            "j-consecutive"
+        } else {
+            "i-consecutive"
+        };
+        let mut name = format!("{i}, {j}, {consecutive}");
+        if self.is_boustrophedon() {
+            name.push_str(", boustrophedonic");
+        }
+        name
+    }
+
+    /// Reshapes decoded values into `Nj` rows of `Ni` values.
+    ///
+    /// Regardless of the scanning mode, rows are ordered from north to south
+    /// and values in each row from west to east; values scanned in the j
+    /// direction first or in alternating directions are rearranged
+    /// accordingly.
+    pub fn reshape_to_2d(&self, data: &[f32]) -> Result<Vec<Vec<f32>>, ShapeError> {
+        let (ni, nj) = (self.ni as usize, self.nj as usize);
+        if data.len() != ni * nj {
+            return Err(ShapeError::LengthMismatch(data.len(), ni * nj));
+        }
+        let rows = (0..nj)
+            .map(|row| {
+                let j = if self.j_positive() { nj - 1 - row } else { row };
+                (0..ni)
+                    .map(|col| {
+                        let i = if self.i_positive() { col } else { ni - 1 - col };
+                        data[self.index_of(i, j)]
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(rows)
+    }
+
+    /// Returns latitudes in degrees of rows of
+    /// [`GridTemplate0::reshape_to_2d`], i.e. from north to south.
+    pub fn latitudes_1d(&self) -> Vec<f64> {
+        let nj = self.nj as usize;
+        (0..nj)
+            .map(|row| {
+                let j = if self.j_positive() { nj - 1 - row } else { row };
+                self.lat_deg_of(j)
+            })
+            .collect()
+    }
+
+    /// Returns longitudes in degrees of columns of
+    /// [`GridTemplate0::reshape_to_2d`], i.e. from west to east.
+    pub fn longitudes_1d(&self) -> Vec<f64> {
+        let ni = self.ni as usize;
+        (0..ni)
+            .map(|col| {
+                let i = if self.i_positive() { col } else { ni - 1 - col };
+                self.lon_deg_of(i)
+            })
+            .collect()
+    }
+
+    /// Returns `true` if the i direction increment is given.
+    #[inline]
+    fn i_direction_inc_given(&self) -> bool {
+        self.resolution_and_component_flags & 0b00100000 != 0
+    }
+
+    /// Returns `true` if the j direction increment is given.
+    #[inline]
+    fn j_direction_inc_given(&self) -> bool {
+        self.resolution_and_component_flags & 0b00010000 != 0
+    }
+
+    /// Returns `true` if points are scanned in the +i (west to east)
+    /// direction.
+    #[inline]
+    fn i_positive(&self) -> bool {
+        self.scanning_mode & 0b10000000 == 0
+    }
+
+    /// Returns `true` if points are scanned in the +j (south to north)
+    /// direction.
+    #[inline]
+    fn j_positive(&self) -> bool {
+        self.scanning_mode & 0b01000000 != 0
+    }
+
+    /// Returns `true` if adjacent points in the j direction are consecutive.
+    #[inline]
+    fn j_consecutive(&self) -> bool {
+        self.scanning_mode & 0b00100000 != 0
+    }
+
+    /// Returns `true` if adjacent rows scan in the opposite direction
+    /// (boustrophedonic ordering).
+    #[inline]
+    pub fn is_boustrophedon(&self) -> bool {
+        self.scanning_mode & 0b00010000 != 0
+    }
+
+    fn lat_of(&self, j: usize) -> f32 {
+        self.lat_deg_of(j) as f32
+    }
+
+    fn lon_of(&self, i: usize) -> f32 {
+        self.lon_deg_of(i) as f32
+    }
+
+    pub(crate) fn lat_deg_of(&self, j: usize) -> f64 {
+        let inc = f64::from(self.j_direction_inc) * j as f64;
+        let inc = if self.j_positive() { inc } else { -inc };
+        (f64::from(self.first_point_lat) + inc) * 1e-6
+    }
+
+    pub(crate) fn lon_deg_of(&self, i: usize) -> f64 {
+        let inc = f64::from(self.i_direction_inc) * i as f64;
+        let inc = if self.i_positive() { inc } else { -inc };
+        let lon = (f64::from(self.first_point_lon) + inc) * 1e-6;
+        if lon >= 360. {
+            lon - 360.
+        } else {
+            lon
+        }
+    }
+
+    /// Returns the latitude and longitude in degrees of the grid point at the
+    /// index in decoded values, in double precision.
+    pub(crate) fn latlon_deg_of(&self, index: usize) -> (f64, f64) {
+        let (i, j) = self.indices_of(index);
+        (self.lat_deg_of(j), self.lon_deg_of(i))
+    }
+
+    /// Converts indices along the i and j axes into the index in decoded
+    /// values, or returns `None` if they are out of the grid.
+    pub(crate) fn linear_index(&self, i: usize, j: usize) -> Option<usize> {
+        if i < self.ni as usize && j < self.nj as usize {
+            Some(self.index_of(i, j))
+        } else {
+            None
+        }
+    }
+
+    /// Converts the index in decoded values into indices along the i and j
+    /// axes, or returns `None` if the index is out of range.
+    fn grid_indices(&self, index: usize) -> Option<(usize, usize)> {
+        if index < self.ni as usize * self.nj as usize {
+            Some(self.indices_of(index))
+        } else {
+            None
+        }
+    }
+
+    /// Converts indices along the i and j axes into the index in decoded
+    /// values.
+    fn index_of(&self, i: usize, j: usize) -> usize {
+        let (ni, nj) = (self.ni as usize, self.nj as usize);
+        if self.j_consecutive() {
+            let j = if self.is_boustrophedon() && i % 2 == 1 {
+                nj - 1 - j
+            } else {
+                j
+            };
+            i * nj + j
+        } else {
+            let i = if self.is_boustrophedon() && j % 2 == 1 {
+                ni - 1 - i
+            } else {
+                i
+            };
+            j * ni + i
+        }
+    }
+
+    /// Converts the index in decoded values into indices along the i and j
+    /// axes.
+    fn indices_of(&self, index: usize) -> (usize, usize) {
+        let (ni, nj) = (self.ni as usize, self.nj as usize);
+        if self.j_consecutive() {
+            let (i, j) = (index / nj, index % nj);
+            let j = if self.is_boustrophedon() && i % 2 == 1 {
+                nj - 1 - j
+            } else {
+                j
+            };
+            (i, j)
+        } else {
+            let (j, i) = (index / ni, index % ni);
+            let i = if self.is_boustrophedon() && j % 2 == 1 {
+                ni - 1 - i
+            } else {
+                i
+            };
+            (i, j)
+        }
+    }
+
+    fn validate(&self) -> Result<(), GridError> {
+        if self.i_direction_inc_given() && self.ni > 1 {
+            let span = i64::from(self.i_direction_inc) * (i64::from(self.ni) - 1);
+            let span = if self.i_positive() { span } else { -span };
+            let computed = (i64::from(self.first_point_lon) + span).rem_euclid(360_000_000);
+            let declared = i64::from(self.last_point_lon).rem_euclid(360_000_000);
+            let diff = (computed - declared).abs();
+            let diff = diff.min(360_000_000 - diff);
+            if diff * 2 > i64::from(self.i_direction_inc) {
+                return Err(GridError::InconsistentLongitudes(computed, declared));
+            }
+        }
+
+        if self.j_direction_inc_given() && self.nj > 1 {
+            let span = i64::from(self.j_direction_inc) * (i64::from(self.nj) - 1);
+            let span = if self.j_positive() { span } else { -span };
+            let computed = i64::from(self.first_point_lat) + span;
+            let declared = i64::from(self.last_point_lat);
+            if (computed - declared).abs() * 2 > i64::from(self.j_direction_inc) {
+                return Err(GridError::InconsistentLatitudes(computed, declared));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn latlons(&self) -> Vec<(f32, f32)> {
+        let num_points = self.ni as usize * self.nj as usize;
+        (0..num_points)
+            .map(|index| {
+                let (i, j) = self.indices_of(index);
+                (self.lat_of(j), self.lon_of(i))
+            })
+            .collect()
+    }
+
+    fn latlon(&self, index: usize) -> Option<(f32, f32)> {
+        let (i, j) = self.grid_indices(index)?;
+        Some((self.lat_of(j), self.lon_of(i)))
+    }
+
+    fn axes(&self) -> (Vec<f32>, Vec<f32>) {
+        let lats = (0..self.nj as usize).map(|j| self.lat_of(j)).collect();
+        let lons = (0..self.ni as usize).map(|i| self.lon_of(i)).collect();
+        (lats, lons)
+    }
+
+    fn area_weights(&self) -> Vec<f32> {
+        let num_points = self.ni as usize * self.nj as usize;
+        (0..num_points)
+            .map(|index| {
+                let (_, j) = self.indices_of(index);
+                cos_lat(self.lat_deg_of(j))
+            })
+            .collect()
+    }
+
+    /// Returns the position of the specified location in units of increments
+    /// from the first grid point along the i and j axes, or `None` if the
+    /// location is outside the grid.
+    ///
+    /// For grids covering all longitudes, the i position can be between
+    /// `ni - 1` and `ni`, i.e. between the last column and the first one.
+    pub(crate) fn position_of(&self, lat: f64, lon: f64) -> Option<(f64, f64)> {
+        const EPS: f64 = 1e-6;
+        let (ni, nj) = (f64::from(self.ni), f64::from(self.nj));
+        let di = f64::from(self.i_direction_inc) * 1e-6;
+        let dj = f64::from(self.j_direction_inc) * 1e-6;
+        let (lat1, lon1) = (
+            f64::from(self.first_point_lat) * 1e-6,
+            f64::from(self.first_point_lon) * 1e-6,
+        );
+
+        let dx = if self.i_positive() {
+            lon - lon1
+        } else {
+            lon1 - lon
+        };
+        let x = dx.rem_euclid(360.) / di;
+        let is_global = ((ni * di) - 360.).abs() < EPS;
+        let x = if x > 360. / di - EPS {
+            // slightly west of the first column due to rounding errors
+            0.
+        } else if x <= ni - 1. + EPS {
+            x.min(ni - 1.)
+        } else if is_global {
+            x
+        } else {
+            return None;
+        };
+
+        let dy = if self.j_positive() {
+            lat - lat1
+        } else {
+            lat1 - lat
+        };
+        let y = dy / dj;
+        if !(-EPS..=nj - 1. + EPS).contains(&y) {
+            return None;
+        }
+        let y = y.clamp(0., nj - 1.);
+
+        Some((x, y))
+    }
+
+    fn nearest(&self, lat: f32, lon: f32) -> Option<usize> {
+        let (lat, lon) = (f64::from(lat), f64::from(lon));
+        let (ni, nj) = (self.ni as usize, self.nj as usize);
+        let di = f64::from(self.i_direction_inc) * 1e-6;
+        let dj = f64::from(self.j_direction_inc) * 1e-6;
+        let (lat1, lon1) = (
+            f64::from(self.first_point_lat) * 1e-6,
+            f64::from(self.first_point_lon) * 1e-6,
+        );
+
+        let dx = if self.i_positive() {
+            lon - lon1
+        } else {
+            lon1 - lon
+        };
+        let dx = dx.rem_euclid(360.);
+        let i = (dx / di).round() as usize;
+        let i = if i < ni {
+            i
+        } else if 360. - dx <= di / 2. {
+            // wrapping around to the first column
+            0
+        } else {
+            return None;
+        };
+
+        let dy = if self.j_positive() {
+            lat - lat1
+        } else {
+            lat1 - lat
+        };
+        if dy < -dj / 2. {
+            return None;
+        }
+        let j = (dy / dj).round() as usize;
+        if j >= nj {
+            return None;
+        }
+
+        Some(self.index_of(i, j))
+    }
+}
+
+/// Grid Definition Template 3.0 used for a quasi-regular grid, where the
+/// number of points varies from row to row.
+///
+/// Only grids whose rows are parallels, i.e. where adjacent points in the i
+/// direction are consecutive, are supported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReducedGridTemplate0 {
+    /// Template 3.0 in which Ni and Di are typically missing
+    pub template: GridTemplate0,
+    /// Numbers of points in rows, in the order of scanning
+    pub row_lengths: Vec<u32>,
+    /// Interpretation of the list of numbers of points (see Code Table 3.11)
+    pub list_interpretation: u8,
+}
+
+impl ReducedGridTemplate0 {
+    pub fn from_grid_def(grid_def: &GridDefinition) -> Result<Self, GribError> {
+        let template = GridTemplate0::from_grid_def(grid_def)?;
+        if template.j_consecutive() {
+            return Err(GribError::Unsupported(
+                "quasi-regular grid with consecutive points in the j direction".to_owned(),
+            ));
+        }
+
+        let payload = grid_def.iter().as_slice();
+        let octets = match grid_def.num_octets_for_optional_list() {
+            n @ 1..=4 => usize::from(n),
+            n => return Err(GridError::InvalidPointListOctets(n).into()),
+        };
+        let start = START_OF_GRID_TEMPLATE + GridTemplate0::SIZE;
+        let end = start + octets * template.nj as usize;
+        if payload.len() < end {
+            return Err(GribError::InternalDataError);
+        }
+        let row_lengths = payload[start..end]
+            .chunks(octets)
+            .map(|bytes| {
+                bytes
+                    .iter()
+                    .fold(0_u32, |acc, b| (acc << 8) | u32::from(*b))
+            })
+            .collect();
+
+        Ok(Self {
+            template,
+            row_lengths,
+            list_interpretation: grid_def.optional_list_interpretation(),
+        })
+    }
+
+    fn num_points(&self) -> usize {
+        self.row_lengths.iter().map(|n| *n as usize).sum()
+    }
+
+    fn linear_index(&self, i: usize, j: usize) -> Option<usize> {
+        let n = *self.row_lengths.get(j)? as usize;
+        if i >= n {
+            return None;
+        }
+        let i = if self.template.is_boustrophedon() && j % 2 == 1 {
+            n - 1 - i
+        } else {
+            i
+        };
+        let offset: usize = self.row_lengths[..j].iter().map(|n| *n as usize).sum();
+        Some(offset + i)
+    }
+
+    fn grid_indices(&self, index: usize) -> Option<(usize, usize)> {
+        let mut offset = 0;
+        for (j, n) in self.row_lengths.iter().enumerate() {
+            let n = *n as usize;
+            if index < offset + n {
+                let i = index - offset;
+                let i = if self.template.is_boustrophedon() && j % 2 == 1 {
+                    n - 1 - i
+                } else {
+                    i
+                };
+                return Some((i, j));
+            }
+            offset += n;
+        }
+        None
+    }
+
+    /// Returns the longitude of the first point and the increment in the row
+    /// with `n` points, in degrees.
+    fn lon_start_and_inc(&self, n: u32) -> (f64, f64) {
+        let tmpl = &self.template;
+        let lon1 = f64::from(tmpl.first_point_lon) * 1e-6;
+        let lon2 = f64::from(tmpl.last_point_lon) * 1e-6;
+        let inc = if self.list_interpretation == 1 {
+            // numbers of points correspond to full coordinate circles
+            360. / f64::from(n.max(1))
+        } else {
+            let span = if tmpl.i_positive() {
+                lon2 - lon1
+            } else {
+                lon1 - lon2
+            };
+            if n > 1 {
+                span.rem_euclid(360.) / f64::from(n - 1)
+            } else {
+                0.
+            }
+        };
+        (lon1, inc)
+    }
+
+    fn lon_of(&self, n: u32, i: usize) -> f32 {
+        let (lon1, inc) = self.lon_start_and_inc(n);
+        let inc = inc * i as f64;
+        let inc = if self.template.i_positive() {
+            inc
+        } else {
+            -inc
+        };
+        let lon = (lon1 + inc).rem_euclid(360.);
+        lon as f32
+    }
+
+    fn area_weights(&self) -> Vec<f32> {
+        let max_len = self.row_lengths.iter().copied().max().unwrap_or_default();
+        let mut weights = Vec::with_capacity(self.num_points());
+        for (j, n) in self.row_lengths.iter().enumerate() {
+            let weight = cos_lat(self.template.lat_deg_of(j)) * max_len as f32 / *n as f32;
+            weights.extend(std::iter::repeat_n(weight, *n as usize));
+        }
+        weights
+    }
+
+    fn latlons(&self) -> Vec<(f32, f32)> {
+        let tmpl = &self.template;
+        let mut latlons = Vec::with_capacity(self.num_points());
+        for (j, n) in self.row_lengths.iter().enumerate() {
+            let lat = tmpl.lat_of(j);
+            let row = (0..*n as usize).map(|i| {
+                let i = if tmpl.is_boustrophedon() && j % 2 == 1 {
+                    *n as usize - 1 - i
+                } else {
+                    i
+                };
+                (lat, self.lon_of(*n, i))
+            });
+            latlons.extend(row);
+        }
+        latlons
+    }
+
+    fn latlon(&self, index: usize) -> Option<(f32, f32)> {
+        let (i, j) = self.grid_indices(index)?;
+        Some((self.template.lat_of(j), self.lon_of(self.row_lengths[j], i)))
+    }
+
+    fn nearest(&self, lat: f32, lon: f32) -> Option<usize> {
+        let tmpl = &self.template;
+        let dj = f64::from(tmpl.j_direction_inc) * 1e-6;
+        let lat1 = f64::from(tmpl.first_point_lat) * 1e-6;
+        let dy = if tmpl.j_positive() {
+            f64::from(lat) - lat1
+        } else {
+            lat1 - f64::from(lat)
+        };
+        if dy < -dj / 2. {
+            return None;
+        }
+        let j = (dy / dj).round() as usize;
+        let n = *self.row_lengths.get(j)?;
+        if n == 0 {
+            return None;
+        }
+
+        let (lon1, di) = self.lon_start_and_inc(n);
+        let dx = if tmpl.i_positive() {
+            f64::from(lon) - lon1
+        } else {
+            lon1 - f64::from(lon)
+        };
+        let dx = dx.rem_euclid(360.);
+        let i = if di > 0. {
+            (dx / di).round() as usize
+        } else {
+            0
+        };
+        let i = if i < n as usize {
+            i
+        } else if 360. - dx <= di / 2. {
+            // wrapping around to the first column
+            0
+        } else {
+            return None;
+        };
+
+        self.linear_index(i, j)
+    }
+}
+
+/// Returns the cosine of the latitude, which is clamped to be non-negative
+/// against rounding errors near the poles.
+fn cos_lat(lat_deg: f64) -> f32 {
+    lat_deg.to_radians().cos().max(0.) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use crate::context::from_reader;
+
+    fn cmc_glb_grid_def() -> GridDefinition {
+        let f =
+            File::open("testdata/CMC_glb_TMP_ISBL_1_latlon.24x.24_2021051800_P000.grib2").unwrap();
+        let f = BufReader::new(f);
+        let grib2 = from_reader(f).unwrap();
+        let submessage = grib2.iter().next().unwrap();
+        submessage.grid_def().clone()
+    }
+
+    fn cmc_glb_grid() -> Grid {
+        Grid::from_grid_def(&cmc_glb_grid_def()).unwrap()
+    }
+
+    #[test]
+    fn grid_definition_source_of_fixture() {
+        let grid_def = cmc_glb_grid_def();
+        assert_eq!(grid_def.source(), 0);
+        assert_eq!(grid_def.num_octets_for_optional_list(), 0);
+        assert_eq!(grid_def.optional_list_interpretation(), 0);
+        assert!(!grid_def.has_optional_point_list());
+        assert!(!grid_def.is_predetermined_grid());
+        assert!(matches!(cmc_glb_grid(), Grid::LatLon(_)));
+
+        let mut payload = grid_def.iter().copied().collect::<Vec<_>>();
+        payload[0] = 1;
+        let grid_def = GridDefinition::from_payload(payload.into_boxed_slice()).unwrap();
+        assert_eq!(
+            Grid::from_grid_def(&grid_def),
+            Err(GribError::Unsupported("predetermined grid 0".to_owned()))
+        );
+    }
+
+    #[test]
+    fn grid_template_0_from_grid_def() {
+        let actual = cmc_glb_grid();
+        let expected = Grid::LatLon(GridTemplate0 {
+            earth_shape: 6,
+            ni: 1500,
+            nj: 751,
+            first_point_lat: -90000000,
+            first_point_lon: 180000000,
+            resolution_and_component_flags: 48,
+            last_point_lat: 90000000,
+            last_point_lon: 179760000,
+            i_direction_inc: 240000,
+            j_direction_inc: 240000,
+            scanning_mode: 64,
+        });
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn unsupported_grid_template() {
+        let f = File::open(
+            "testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+        )
+        .unwrap();
+        let f = BufReader::new(f);
+        let grib2 = from_reader(f).unwrap();
+        let submessage = grib2.iter().next().unwrap();
+        assert_eq!(
+            Grid::from_grid_def(submessage.grid_def()),
+            Err(GribError::Unsupported(
+                "Grid Definition Template 3.101".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn grid_template_0_as_hash_key() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        fn hash_of(tmpl: &GridTemplate0) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            tmpl.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let Grid::LatLon(tmpl) = cmc_glb_grid() else {
+            panic!("unexpected grid type");
+        };
+        let same = tmpl.clone();
+        assert_eq!(tmpl, same);
+        assert_eq!(hash_of(&tmpl), hash_of(&same));
+
+        let different = GridTemplate0 {
+            j_direction_inc: 250000,
+            ..tmpl.clone()
+        };
+        assert_ne!(tmpl, different);
+
+        let mut counts = std::collections::HashMap::new();
+        for key in [tmpl, same, different] {
+            *counts.entry(key).or_insert(0) += 1;
+        }
+        assert_eq!(counts.len(), 2);
+    }
+
+    #[test]
+    fn grid_template_0_accessors() {
+        let Grid::LatLon(tmpl) = cmc_glb_grid() else {
+            panic!("unexpected grid type");
+        };
+        assert_eq!(tmpl.num_rows(), 751);
+        assert_eq!(tmpl.num_cols(), 1500);
+        assert_eq!(tmpl.lat_resolution_deg(), 0.24);
+        assert_eq!(tmpl.lon_resolution_deg(), 0.24);
+        assert_eq!(tmpl.lat_range(), (-90., 90.));
+        assert_eq!(tmpl.lon_range(), (180., 179.76));
+    }
+
+    #[test]
+    fn latlons_of_grid_template_0() {
+        let grid = cmc_glb_grid();
+        let latlons = grid.latlons();
+        assert_eq!(latlons.len(), grid.num_points());
+        assert_eq!(latlons.first(), Some(&(-90., 180.)));
+        assert_eq!(latlons[1], (-90., 180.24));
+        assert_eq!(latlons[1500], (-89.76, 180.));
+        assert_eq!(latlons.last(), Some(&(90., 179.76)));
+    }
+
+    #[test]
+    fn nearest_in_grid_template_0() {
+        let grid = cmc_glb_grid();
+        assert_eq!(grid.nearest(-90., 180.), Some(0));
+        assert_eq!(grid.nearest(-89.8, 180.2), Some(1501));
+        assert_eq!(grid.nearest(-90., 179.9), Some(0));
+        assert_eq!(grid.nearest(-90., 179.8), Some(1499));
+        assert_eq!(grid.nearest(90., 179.76), Some(1126499));
+        assert_eq!(grid.nearest(35.7, 139.7), Some(524 * 1500 + 1332));
+        assert_eq!(grid.nearest(90.2, 0.), None);
+        assert_eq!(grid.nearest(-90.2, 0.), None);
+    }
+
+    #[test]
+    fn validate_consistent_grid_template_0() {
+        let grid = cmc_glb_grid();
+        assert_eq!(grid.validate(), Ok(()));
+        assert!(Grid::from_grid_def_validated(&cmc_glb_grid_def()).is_ok());
+    }
+
+    #[test]
+    fn validate_inconsistent_grid_template_0() {
+        let grid_def = cmc_glb_grid_def();
+        let mut payload = grid_def.iter().as_slice().to_vec();
+        // Di = 0.25 degrees, which does not divide the longitude span into Ni - 1 steps
+        let di_pos = START_OF_GRID_TEMPLATE + 49;
+        payload[di_pos..di_pos + 4].copy_from_slice(&250000_u32.to_be_bytes());
+        let grid_def = GridDefinition::from_payload(payload.into_boxed_slice()).unwrap();
+
+        let grid = Grid::from_grid_def(&grid_def).unwrap();
+        assert_eq!(
+            grid.validate(),
+            Err(GridError::InconsistentLongitudes(194750000, 179760000))
+        );
+        assert_eq!(
+            Grid::from_grid_def_validated(&grid_def),
+            Err(GribError::GridError(GridError::InconsistentLongitudes(
+                194750000, 179760000
+            )))
+        );
+    }
+
+    fn reduced_grid_def() -> GridDefinition {
+        let grid_def = cmc_glb_grid_def();
+        let mut payload = grid_def.iter().as_slice()[..START_OF_GRID_TEMPLATE + 58].to_vec();
+        payload[1..5].copy_from_slice(&16_u32.to_be_bytes()); // number of data points
+        payload[5] = 2; // number of octets for the optional list
+        payload[6] = 1; // numbers correspond to full coordinate circles
+        let buf = &mut payload[START_OF_GRID_TEMPLATE..];
+        buf[16..20].copy_from_slice(&u32::MAX.to_be_bytes()); // Ni
+        buf[20..24].copy_from_slice(&3_u32.to_be_bytes()); // Nj
+        buf[32..36].copy_from_slice(&0_u32.to_be_bytes()); // La1
+        buf[36..40].copy_from_slice(&0_u32.to_be_bytes()); // Lo1
+        buf[40] = 0b00010000; // Di not given
+        buf[41..45].copy_from_slice(&(1_u32 << 31 | 20000000).to_be_bytes()); // La2
+        buf[45..49].copy_from_slice(&315000000_u32.to_be_bytes()); // Lo2
+        buf[49..53].copy_from_slice(&u32::MAX.to_be_bytes()); // Di
+        buf[53..57].copy_from_slice(&10000000_u32.to_be_bytes()); // Dj
+        buf[57] = 0b00000000;
+        for n in [4_u16, 8, 4] {
+            payload.extend_from_slice(&n.to_be_bytes());
+        }
+        GridDefinition::from_payload(payload.into_boxed_slice()).unwrap()
+    }
+
+    #[test]
+    fn reduced_grid_template_0_from_grid_def() {
+        let grid = Grid::from_grid_def(&reduced_grid_def()).unwrap();
+        let Grid::ReducedLatLon(tmpl) = &grid else {
+            panic!("unexpected grid type");
+        };
+        assert_eq!(tmpl.row_lengths, vec![4, 8, 4]);
+        assert_eq!(tmpl.list_interpretation, 1);
+        assert_eq!(grid.num_points(), 16);
+        assert_ne!(
+            grid.num_points(),
+            tmpl.template.ni as usize * tmpl.template.nj as usize
+        );
+        assert_eq!(grid.validate(), Ok(()));
+    }
+
+    #[test]
+    fn reduced_grid_template_0_with_invalid_octets_for_point_list() {
+        for octets in [0, 5] {
+            let mut payload = reduced_grid_def().iter().as_slice().to_vec();
+            payload[5] = octets;
+            let grid_def = GridDefinition::from_payload(payload.into_boxed_slice()).unwrap();
+            assert_eq!(
+                ReducedGridTemplate0::from_grid_def(&grid_def),
+                Err(GribError::GridError(GridError::InvalidPointListOctets(
+                    octets
+                )))
+            );
+        }
+    }
+
+    #[test]
+    fn latlons_of_reduced_grid_template_0() {
+        let grid = Grid::from_grid_def(&reduced_grid_def()).unwrap();
+        let latlons = grid.latlons();
+        assert_eq!(latlons.len(), grid.num_points());
+        assert_eq!(latlons[..4], [(0., 0.), (0., 90.), (0., 180.), (0., 270.)]);
+        assert_eq!(latlons[4], (-10., 0.));
+        assert_eq!(latlons[5], (-10., 45.));
+        assert_eq!(latlons[11], (-10., 315.));
+        assert_eq!(
+            latlons[12..],
+            [(-20., 0.), (-20., 90.), (-20., 180.), (-20., 270.)]
+        );
+    }
+
+    #[test]
+    fn latlon_at_index_matching_latlons() {
+        for grid in [
+            cmc_glb_grid(),
+            Grid::from_grid_def(&reduced_grid_def()).unwrap(),
+        ] {
+            let latlons = grid.latlons();
+            for index in [0, 1, 5, 11, latlons.len() - 1] {
+                assert_eq!(grid.latlon(index), Some(latlons[index]));
+            }
+            assert_eq!(grid.latlon(latlons.len()), None);
+        }
+    }
+
+    #[test]
+    fn area_weights_of_grid_template_0() {
+        let grid = cmc_glb_grid();
+        let weights = grid.area_weights();
+        assert_eq!(weights.len(), grid.num_points());
+        assert!(weights[0].abs() < 1e-6);
+        assert_eq!(weights[375 * 1500], 1.);
+        assert_eq!(weights[375 * 1500 + 1499], 1.);
+        assert!(weights[750 * 1500].abs() < 1e-6);
+        assert!(weights.iter().all(|w| (0. ..=1.).contains(w)));
+    }
+
+    #[test]
+    fn axes_of_grid_template_0() {
+        let grid = cmc_glb_grid();
+        let (lats, lons) = grid.axes().unwrap();
+        assert_eq!(lats.len(), 751);
+        assert_eq!(lons.len(), 1500);
+        assert_eq!((lats[0], lats[750]), (-90., 90.));
+        assert_eq!((lons[0], lons[1499]), (180., 179.76));
+
+        let latlons = grid.latlons();
+        assert_eq!(latlons[1501], (lats[1], lons[1]));
+
+        let grid = Grid::from_grid_def(&reduced_grid_def()).unwrap();
+        assert_eq!(grid.axes(), None);
+    }
+
+    #[test]
+    fn area_weights_of_reduced_grid_template_0() {
+        let grid = Grid::from_grid_def(&reduced_grid_def()).unwrap();
+        let weights = grid.area_weights();
+        assert_eq!(weights.len(), grid.num_points());
+        assert_eq!(weights[..4], [2.; 4]);
+        assert_eq!(weights[4], 10_f64.to_radians().cos() as f32);
+        assert_eq!(weights[12], 20_f64.to_radians().cos() as f32 * 2.);
+    }
+
+    #[test]
+    fn nearest_in_reduced_grid_template_0() {
+        let grid = Grid::from_grid_def(&reduced_grid_def()).unwrap();
+        assert_eq!(grid.nearest(1., 80.), Some(1));
+        assert_eq!(grid.nearest(-9., 50.), Some(5));
+        assert_eq!(grid.nearest(-10., 350.), Some(4));
+        assert_eq!(grid.nearest(-19., 260.), Some(15));
+        assert_eq!(grid.nearest(6., 0.), None);
+        assert_eq!(grid.nearest(-26., 0.), None);
+    }
+
+    fn small_grid(scanning_mode: u8) -> Grid {
+        Grid::LatLon(GridTemplate0 {
+            earth_shape: 6,
+            ni: 3,
+            nj: 2,
+            first_point_lat: 0,
+            first_point_lon: 0,
+            resolution_and_component_flags: 0b00110000,
+            last_point_lat: 1_000_000,
+            last_point_lon: 2_000_000,
+            i_direction_inc: 1_000_000,
+            j_direction_inc: 1_000_000,
+            scanning_mode,
+        })
+    }
+
+    macro_rules! test_linear_index_with_scanning_mode {
+        ($(($name:ident, $scanning_mode:expr, $expected:expr),)*) => ($(
+            #[test]
+            fn $name() {
+                let grid = small_grid($scanning_mode);
+                let expected: [[usize; 3]; 2] = $expected;
+                for (j, row) in expected.iter().enumerate() {
+                    for (i, index) in row.iter().enumerate() {
+                        assert_eq!(grid.linear_index(i, j), Some(*index));
+                        assert_eq!(grid.grid_indices(*index), Some((i, j)));
+                    }
+                }
+                assert_eq!(grid.linear_index(3, 0), None);
+                assert_eq!(grid.linear_index(0, 2), None);
+                assert_eq!(grid.grid_indices(6), None);
+            }
+        )*);
+    }
+
+    test_linear_index_with_scanning_mode! {
+        (linear_index_with_i_consecutive, 0b00000000, [[0, 1, 2], [3, 4, 5]]),
+        (linear_index_with_i_negative, 0b10000000, [[0, 1, 2], [3, 4, 5]]),
+        (linear_index_with_j_positive, 0b01000000, [[0, 1, 2], [3, 4, 5]]),
+        (linear_index_with_j_consecutive, 0b00100000, [[0, 2, 4], [1, 3, 5]]),
+        (linear_index_with_boustrophedonic_rows, 0b00010000, [[0, 1, 2], [5, 4, 3]]),
+        (linear_index_with_boustrophedonic_columns, 0b00110000, [[0, 3, 4], [1, 2, 5]]),
+    }
+
+    #[test]
+    fn scanning_mode_names() {
+        let name_of = |scanning_mode| match small_grid(scanning_mode) {
+            Grid::LatLon(tmpl) => (tmpl.scanning_mode_name(), tmpl.is_boustrophedon()),
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            name_of(0b00000000),
+            ("i+, j-, i-consecutive".to_owned(), false)
+        );
+        assert_eq!(
+            name_of(0b01100100),
+            ("i+, j+, j-consecutive".to_owned(), false)
+        );
+        assert_eq!(
+            name_of(0b10010000),
+            ("i-, j-, i-consecutive, boustrophedonic".to_owned(), true)
+        );
+    }
+
+    #[test]
+    fn reshape_to_2d_with_all_scanning_modes() {
+        for (ni, nj) in [(3, 4), (4, 3)] {
+            for flags in 0..16_u8 {
+                let scanning_mode = flags << 4;
+                let tmpl = GridTemplate0 {
+                    earth_shape: 6,
+                    ni: ni as u32,
+                    nj: nj as u32,
+                    first_point_lat: 0,
+                    first_point_lon: 0,
+                    resolution_and_component_flags: 0b00110000,
+                    last_point_lat: 0,
+                    last_point_lon: 0,
+                    i_direction_inc: 1_000_000,
+                    j_direction_inc: 1_000_000,
+                    scanning_mode,
+                };
+                let i_negative = scanning_mode & 0b10000000 != 0;
+                let j_positive = scanning_mode & 0b01000000 != 0;
+                let j_consecutive = scanning_mode & 0b00100000 != 0;
+                let boustrophedonic = scanning_mode & 0b00010000 != 0;
+
+                // Each value is its position in the north-to-south,
+                // west-to-east order.
+                let (outer, inner) = if j_consecutive { (ni, nj) } else { (nj, ni) };
+                let mut data = Vec::new();
+                for o in 0..outer {
+                    for n in 0..inner {
+                        let n = if boustrophedonic && o % 2 == 1 {
+                            inner - 1 - n
+                        } else {
+                            n
+                        };
+                        let (i, j) = if j_consecutive { (o, n) } else { (n, o) };
+                        let col = if i_negative { ni - 1 - i } else { i };
+                        let row = if j_positive { nj - 1 - j } else { j };
+                        data.push((row * ni + col) as f32);
+                    }
+                }
+
+                let expected = (0..nj)
+                    .map(|row| (0..ni).map(|col| (row * ni + col) as f32).collect())
+                    .collect::<Vec<Vec<_>>>();
+                assert_eq!(
+                    tmpl.reshape_to_2d(&data),
+                    Ok(expected),
+                    "{ni}x{nj}, scanning mode {scanning_mode:#010b}"
+                );
+                assert_eq!(
+                    tmpl.reshape_to_2d(&data[1..]),
+                    Err(ShapeError::LengthMismatch(ni * nj - 1, ni * nj))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn coordinates_of_reshaped_values() {
+        for scanning_mode in [0b00000000, 0b11000000, 0b01110000] {
+            let tmpl = match small_grid(scanning_mode) {
+                Grid::LatLon(tmpl) => tmpl,
+                _ => unreachable!(),
+            };
+            let latlons = tmpl.latlons();
+            let lats = latlons.iter().map(|(lat, _)| *lat).collect::<Vec<_>>();
+            let lons = latlons.iter().map(|(_, lon)| *lon).collect::<Vec<_>>();
+            let lats = tmpl.reshape_to_2d(&lats).unwrap();
+            let lons = tmpl.reshape_to_2d(&lons).unwrap();
+            let lat_axis = tmpl.latitudes_1d();
+            let lon_axis = tmpl.longitudes_1d();
+            assert_eq!((lat_axis.len(), lon_axis.len()), (2, 3));
+            assert!(lat_axis[0] > lat_axis[1]);
+            for (j, lat) in lat_axis.iter().enumerate() {
+                for (i, lon) in lon_axis.iter().enumerate() {
+                    assert_eq!(lats[j][i], *lat as f32);
+                    assert_eq!(lons[j][i], *lon as f32);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn linear_index_in_reduced_grid_template_0() {
+        let grid = Grid::from_grid_def(&reduced_grid_def()).unwrap();
+        assert_eq!(grid.linear_index(0, 0), Some(0));
+        assert_eq!(grid.linear_index(3, 0), Some(3));
+        assert_eq!(grid.linear_index(0, 1), Some(4));
+        assert_eq!(grid.linear_index(7, 1), Some(11));
+        assert_eq!(grid.linear_index(3, 2), Some(15));
+        assert_eq!(grid.linear_index(4, 0), None);
+        assert_eq!(grid.linear_index(0, 3), None);
+        for index in 0..grid.num_points() {
+            let (i, j) = grid.grid_indices(index).unwrap();
+            assert_eq!(grid.linear_index(i, j), Some(index));
+        }
+        assert_eq!(grid.grid_indices(16), None);
+    }
+}
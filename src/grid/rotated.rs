@@ -0,0 +1,455 @@
+use std::convert::TryInto;
+
+use super::{GridTemplate0, START_OF_GRID_TEMPLATE};
+use crate::datatypes::GridDefinition;
+use crate::error::*;
+use crate::utils::{read_as, GribInt};
+
+/// Offset of parameters following the part common to Template 3.0 in the
+/// Grid Definition Template.
+const START_OF_EXTENSION: usize = START_OF_GRID_TEMPLATE + GridTemplate0::SIZE;
+
+/// Grid Definition Template 3.1 (rotated latitude/longitude).
+///
+/// Latitudes and longitudes in `lat_lon` are those in the rotated system.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridTemplate1 {
+    pub lat_lon: GridTemplate0,
+    pub rotation: Rotation,
+}
+
+impl GridTemplate1 {
+    const SIZE: usize = GridTemplate0::SIZE + Rotation::SIZE;
+
+    pub fn from_grid_def(grid_def: &GridDefinition) -> Result<Self, GribError> {
+        let buf = extension(grid_def, Self::SIZE)?;
+        Ok(Self {
+            lat_lon: GridTemplate0::from_grid_def(grid_def)?,
+            rotation: Rotation::from_buf(buf),
+        })
+    }
+
+    pub(super) fn latlons(&self) -> Vec<(f32, f32)> {
+        transform_latlons(&self.lat_lon, |lat, lon| {
+            self.rotation.to_geographic(lat, lon)
+        })
+    }
+
+    pub(super) fn latlon(&self, index: usize) -> Option<(f32, f32)> {
+        let latlon = self.lat_lon.latlon(index)?;
+        Some(transform_latlon(latlon, |lat, lon| {
+            self.rotation.to_geographic(lat, lon)
+        }))
+    }
+
+    pub(super) fn nearest(&self, lat: f32, lon: f32) -> Option<usize> {
+        let (lat, lon) = self
+            .rotation
+            .from_geographic(f64::from(lat), f64::from(lon));
+        self.lat_lon.nearest(lat as f32, lon as f32)
+    }
+}
+
+/// Grid Definition Template 3.2 (stretched latitude/longitude).
+///
+/// Latitudes and longitudes in `lat_lon` are those in the transformed
+/// system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GridTemplate2 {
+    pub lat_lon: GridTemplate0,
+    pub stretching: Stretching,
+}
+
+impl GridTemplate2 {
+    const SIZE: usize = GridTemplate0::SIZE + Stretching::SIZE;
+
+    pub fn from_grid_def(grid_def: &GridDefinition) -> Result<Self, GribError> {
+        let buf = extension(grid_def, Self::SIZE)?;
+        Ok(Self {
+            lat_lon: GridTemplate0::from_grid_def(grid_def)?,
+            stretching: Stretching::from_buf(buf),
+        })
+    }
+
+    pub(super) fn latlons(&self) -> Vec<(f32, f32)> {
+        transform_latlons(&self.lat_lon, |lat, lon| {
+            self.stretching.to_geographic(lat, lon)
+        })
+    }
+
+    pub(super) fn latlon(&self, index: usize) -> Option<(f32, f32)> {
+        let latlon = self.lat_lon.latlon(index)?;
+        Some(transform_latlon(latlon, |lat, lon| {
+            self.stretching.to_geographic(lat, lon)
+        }))
+    }
+
+    pub(super) fn nearest(&self, lat: f32, lon: f32) -> Option<usize> {
+        let (lat, lon) = self
+            .stretching
+            .from_geographic(f64::from(lat), f64::from(lon));
+        self.lat_lon.nearest(lat as f32, lon as f32)
+    }
+}
+
+/// Grid Definition Template 3.3 (stretched and rotated latitude/longitude).
+///
+/// Latitudes and longitudes in `lat_lon` are those in the transformed
+/// system. The pole of stretching is regarded as being specified in the
+/// rotated system.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridTemplate3 {
+    pub lat_lon: GridTemplate0,
+    pub rotation: Rotation,
+    pub stretching: Stretching,
+}
+
+impl GridTemplate3 {
+    const SIZE: usize = GridTemplate0::SIZE + Rotation::SIZE + Stretching::SIZE;
+
+    pub fn from_grid_def(grid_def: &GridDefinition) -> Result<Self, GribError> {
+        let buf = extension(grid_def, Self::SIZE)?;
+        Ok(Self {
+            lat_lon: GridTemplate0::from_grid_def(grid_def)?,
+            rotation: Rotation::from_buf(buf),
+            stretching: Stretching::from_buf(&buf[Rotation::SIZE..]),
+        })
+    }
+
+    pub(super) fn latlons(&self) -> Vec<(f32, f32)> {
+        transform_latlons(&self.lat_lon, |lat, lon| {
+            let (lat, lon) = self.stretching.to_geographic(lat, lon);
+            self.rotation.to_geographic(lat, lon)
+        })
+    }
+
+    pub(super) fn latlon(&self, index: usize) -> Option<(f32, f32)> {
+        let latlon = self.lat_lon.latlon(index)?;
+        Some(transform_latlon(latlon, |lat, lon| {
+            let (lat, lon) = self.stretching.to_geographic(lat, lon);
+            self.rotation.to_geographic(lat, lon)
+        }))
+    }
+
+    pub(super) fn nearest(&self, lat: f32, lon: f32) -> Option<usize> {
+        let (lat, lon) = self
+            .rotation
+            .from_geographic(f64::from(lat), f64::from(lon));
+        let (lat, lon) = self.stretching.from_geographic(lat, lon);
+        self.lat_lon.nearest(lat as f32, lon as f32)
+    }
+}
+
+/// Parameters of rotation of the latitude/longitude coordinate system.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rotation {
+    /// Latitude of the southern pole of projection in units of 10^-6 degree
+    pub south_pole_lat: i32,
+    /// Longitude of the southern pole of projection in units of 10^-6 degree
+    pub south_pole_lon: i32,
+    /// Angle of rotation of projection in degrees
+    pub angle: f32,
+}
+
+impl Rotation {
+    const SIZE: usize = 12;
+
+    fn from_buf(buf: &[u8]) -> Self {
+        Self {
+            south_pole_lat: read_as!(u32, buf, 0).as_grib_int(),
+            south_pole_lon: read_as!(u32, buf, 4).as_grib_int(),
+            angle: f32::from_bits(read_as!(u32, buf, 8)),
+        }
+    }
+
+    /// Converts a location in degrees in the rotated system into one in the
+    /// geographic system.
+    pub fn to_geographic(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let (sin_t, cos_t) = self.tilt().sin_cos();
+        let (x, y, z) = to_cartesian(lat, lon - f64::from(self.angle));
+        let (x, z) = (cos_t * x - sin_t * z, sin_t * x + cos_t * z);
+        let (lat, lon) = from_cartesian(x, y, z);
+        (
+            lat,
+            normalize_lon(lon + f64::from(self.south_pole_lon) * 1e-6),
+        )
+    }
+
+    /// Converts a location in degrees in the geographic system into one in
+    /// the rotated system.
+    pub fn from_geographic(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let (sin_t, cos_t) = self.tilt().sin_cos();
+        let (x, y, z) = to_cartesian(lat, lon - f64::from(self.south_pole_lon) * 1e-6);
+        let (x, z) = (cos_t * x + sin_t * z, -sin_t * x + cos_t * z);
+        let (lat, lon) = from_cartesian(x, y, z);
+        (lat, normalize_lon(lon + f64::from(self.angle)))
+    }
+
+    /// Returns the angle in radians between the poles of the geographic and
+    /// rotated systems.
+    fn tilt(&self) -> f64 {
+        (90. + f64::from(self.south_pole_lat) * 1e-6).to_radians()
+    }
+}
+
+/// Parameters of stretching of the latitude/longitude coordinate system,
+/// applied as the Schmidt transformation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stretching {
+    /// Latitude of the pole of stretching in units of 10^-6 degree
+    pub pole_lat: i32,
+    /// Longitude of the pole of stretching in units of 10^-6 degree
+    pub pole_lon: i32,
+    /// Stretching factor in units of 10^-6
+    pub factor: u32,
+}
+
+impl Stretching {
+    const SIZE: usize = 12;
+
+    fn from_buf(buf: &[u8]) -> Self {
+        Self {
+            pole_lat: read_as!(u32, buf, 0).as_grib_int(),
+            pole_lon: read_as!(u32, buf, 4).as_grib_int(),
+            factor: read_as!(u32, buf, 8),
+        }
+    }
+
+    /// Converts a location in degrees in the transformed system into one in
+    /// the system without stretching.
+    pub fn to_geographic(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let d = self.schmidt_coefficient();
+        let mu = lat.to_radians().sin();
+        let mu = (mu + d) / (1. + d * mu);
+        self.pole_frame()
+            .to_geographic(mu.clamp(-1., 1.).asin().to_degrees(), lon)
+    }
+
+    /// Converts a location in degrees in the system without stretching into
+    /// one in the transformed system.
+    pub fn from_geographic(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let (lat, lon) = self.pole_frame().from_geographic(lat, lon);
+        let d = self.schmidt_coefficient();
+        let mu = lat.to_radians().sin();
+        let mu = (mu - d) / (1. - d * mu);
+        (mu.clamp(-1., 1.).asin().to_degrees(), lon)
+    }
+
+    fn schmidt_coefficient(&self) -> f64 {
+        let c2 = (f64::from(self.factor) * 1e-6).powi(2);
+        (c2 - 1.) / (c2 + 1.)
+    }
+
+    /// Returns the rotation that brings the north pole to the pole of
+    /// stretching, which is the identity if the latter is at (90, 0).
+    fn pole_frame(&self) -> Rotation {
+        Rotation {
+            south_pole_lat: -self.pole_lat,
+            // computed in i64 not to overflow with out-of-range longitudes
+            south_pole_lon: (i64::from(self.pole_lon) + 180_000_000).rem_euclid(360_000_000) as i32,
+            angle: 180.,
+        }
+    }
+}
+
+fn extension(grid_def: &GridDefinition, tmpl_size: usize) -> Result<&[u8], GribError> {
+    let payload = grid_def.iter().as_slice();
+    if payload.len() < START_OF_GRID_TEMPLATE + tmpl_size {
+        return Err(GribError::InternalDataError);
+    }
+    Ok(&payload[START_OF_EXTENSION..])
+}
+
+fn transform_latlons<F>(lat_lon: &GridTemplate0, f: F) -> Vec<(f32, f32)>
+where
+    F: Fn(f64, f64) -> (f64, f64),
+{
+    lat_lon
+        .latlons()
+        .into_iter()
+        .map(|latlon| transform_latlon(latlon, &f))
+        .collect()
+}
+
+fn transform_latlon<F>((lat, lon): (f32, f32), f: F) -> (f32, f32)
+where
+    F: Fn(f64, f64) -> (f64, f64),
+{
+    let (lat, lon) = f(f64::from(lat), f64::from(lon));
+    (lat as f32, lon as f32)
+}
+
+fn to_cartesian(lat: f64, lon: f64) -> (f64, f64, f64) {
+    let (sin_lat, cos_lat) = lat.to_radians().sin_cos();
+    let (sin_lon, cos_lon) = lon.to_radians().sin_cos();
+    (cos_lat * cos_lon, cos_lat * sin_lon, sin_lat)
+}
+
+fn from_cartesian(x: f64, y: f64, z: f64) -> (f64, f64) {
+    (
+        z.clamp(-1., 1.).asin().to_degrees(),
+        y.atan2(x).to_degrees(),
+    )
+}
+
+fn normalize_lon(lon: f64) -> f64 {
+    lon.rem_euclid(360.)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::grid::Grid;
+
+    macro_rules! assert_almost_eq {
+        ($actual:expr, $expected:expr) => {{
+            let (actual, expected) = ($actual, $expected);
+            assert!(
+                (actual.0 - expected.0).abs() < 1e-4 && (actual.1 - expected.1).abs() < 1e-4,
+                "{:?} != {:?}",
+                actual,
+                expected
+            );
+        }};
+    }
+
+    fn lat_lon_grid_def(tmpl_num: u16, extension: &[u8]) -> GridDefinition {
+        let mut payload = vec![0, 0, 0, 0, 9, 0, 0];
+        payload.extend_from_slice(&tmpl_num.to_be_bytes());
+        let mut tmpl = vec![0; GridTemplate0::SIZE];
+        tmpl[16..20].copy_from_slice(&3_u32.to_be_bytes()); // Ni
+        tmpl[20..24].copy_from_slice(&3_u32.to_be_bytes()); // Nj
+        tmpl[32..36].copy_from_slice(&(1_u32 << 31 | 1000000).to_be_bytes()); // La1
+        tmpl[36..40].copy_from_slice(&(1_u32 << 31 | 1000000).to_be_bytes()); // Lo1
+        tmpl[40] = 0b00110000;
+        tmpl[41..45].copy_from_slice(&1000000_u32.to_be_bytes()); // La2
+        tmpl[45..49].copy_from_slice(&1000000_u32.to_be_bytes()); // Lo2
+        tmpl[49..53].copy_from_slice(&1000000_u32.to_be_bytes()); // Di
+        tmpl[53..57].copy_from_slice(&1000000_u32.to_be_bytes()); // Dj
+        tmpl[57] = 0b01000000;
+        payload.extend_from_slice(&tmpl);
+        payload.extend_from_slice(extension);
+        GridDefinition::from_payload(payload.into_boxed_slice()).unwrap()
+    }
+
+    fn rotation_bytes(south_pole_lat: i32, south_pole_lon: i32, angle: f32) -> Vec<u8> {
+        let to_grib_int = |v: i32| {
+            if v < 0 {
+                1_u32 << 31 | v.unsigned_abs()
+            } else {
+                v as u32
+            }
+        };
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&to_grib_int(south_pole_lat).to_be_bytes());
+        buf.extend_from_slice(&to_grib_int(south_pole_lon).to_be_bytes());
+        buf.extend_from_slice(&angle.to_bits().to_be_bytes());
+        buf
+    }
+
+    fn stretching_bytes(pole_lat: i32, pole_lon: i32, factor: u32) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(pole_lat as u32).to_be_bytes());
+        buf.extend_from_slice(&(pole_lon as u32).to_be_bytes());
+        buf.extend_from_slice(&factor.to_be_bytes());
+        buf
+    }
+
+    #[test]
+    fn rotation_of_cosmo_like_grid() {
+        // southern pole at 40S, 10E, i.e. northern pole at 40N, 170W
+        let rotation = Rotation {
+            south_pole_lat: -40000000,
+            south_pole_lon: 10000000,
+            angle: 0.,
+        };
+        assert_almost_eq!(rotation.to_geographic(0., 0.), (50., 10.));
+        assert_almost_eq!(rotation.to_geographic(0., 90.), (0., 100.));
+        assert_almost_eq!(rotation.to_geographic(90., 0.), (40., 190.));
+        assert_almost_eq!(rotation.to_geographic(-90., 0.), (-40., 10.));
+
+        let (lat, lon) = rotation.to_geographic(-5., -7.5);
+        assert_almost_eq!(rotation.from_geographic(lat, lon), (-5., 352.5));
+    }
+
+    #[test]
+    fn latlons_of_grid_template_1() {
+        let ext = rotation_bytes(-40000000, 10000000, 0.);
+        let grid = Grid::from_grid_def(&lat_lon_grid_def(1, &ext)).unwrap();
+        let Grid::RotatedLatLon(tmpl) = &grid else {
+            panic!("unexpected grid type");
+        };
+        assert_eq!(tmpl.rotation.south_pole_lat, -40000000);
+        assert_eq!(tmpl.rotation.south_pole_lon, 10000000);
+
+        let latlons = grid.latlons();
+        assert_eq!(latlons.len(), 9);
+        let (lat, lon) = latlons[4];
+        assert_almost_eq!((f64::from(lat), f64::from(lon)), (50., 10.));
+        assert_eq!(grid.nearest(50., 10.), Some(4));
+        assert_eq!(grid.nearest(50.9, 11.3), Some(8));
+        assert_eq!(grid.nearest(0., 10.), None);
+    }
+
+    #[test]
+    fn latlons_of_grid_template_2() {
+        // stretching factor of 2 with the pole of stretching at the north pole
+        let ext = stretching_bytes(90000000, 0, 2000000);
+        let grid = Grid::from_grid_def(&lat_lon_grid_def(2, &ext)).unwrap();
+        let latlons = grid.latlons();
+        let (lat, lon) = latlons[4];
+        // sin(lat) = (2^2 - 1) / (2^2 + 1) at the equator of the transformed system
+        assert_almost_eq!(
+            (f64::from(lat), f64::from(lon)),
+            (0.6_f64.asin().to_degrees(), 0.)
+        );
+        assert_eq!(grid.nearest(lat, lon), Some(4));
+
+        // no stretching
+        let ext = stretching_bytes(90000000, 0, 1000000);
+        let grid = Grid::from_grid_def(&lat_lon_grid_def(2, &ext)).unwrap();
+        let (lat, lon) = grid.latlons()[0];
+        assert_almost_eq!((f64::from(lat), f64::from(lon)), (-1., 359.));
+    }
+
+    #[test]
+    fn latlons_of_grid_template_3() {
+        let mut ext = rotation_bytes(-40000000, 10000000, 0.);
+        ext.extend(stretching_bytes(90000000, 0, 1000000));
+        let grid = Grid::from_grid_def(&lat_lon_grid_def(3, &ext)).unwrap();
+        let (lat, lon) = grid.latlons()[4];
+        assert_almost_eq!((f64::from(lat), f64::from(lon)), (50., 10.));
+        assert_eq!(grid.nearest(50., 10.), Some(4));
+        assert_eq!(grid.latlon(4), Some((lat, lon)));
+        assert_eq!(grid.latlon(9), None);
+    }
+
+    #[test]
+    fn stretching_with_out_of_range_pole_longitude() {
+        let stretching = |pole_lon| Stretching {
+            pole_lat: 45_000_000,
+            pole_lon,
+            factor: 2_000_000,
+        };
+        let (lat, lon) = stretching(i32::MAX).to_geographic(10., 20.);
+        let (expected_lat, expected_lon) =
+            stretching(i32::MAX - 5 * 360_000_000).to_geographic(10., 20.);
+        assert!((lat - expected_lat).abs() < 1e-9);
+        assert!(
+            (lon - expected_lon)
+                .rem_euclid(360.)
+                .min((expected_lon - lon).rem_euclid(360.))
+                < 1e-9
+        );
+    }
+
+    #[test]
+    fn too_short_grid_template_1() {
+        let grid_def = lat_lon_grid_def(1, &[0; 4]);
+        assert_eq!(
+            GridTemplate1::from_grid_def(&grid_def),
+            Err(GribError::InternalDataError)
+        );
+    }
+}
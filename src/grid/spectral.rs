@@ -0,0 +1,112 @@
+use std::convert::TryInto;
+
+use super::START_OF_GRID_TEMPLATE;
+use crate::datatypes::GridDefinition;
+use crate::error::*;
+use crate::utils::read_as;
+
+/// Pentagonal resolution parameters of spherical harmonic coefficients,
+/// which are common to Grid Definition Templates 3.50-3.53.
+///
+/// For each zonal wavenumber `m` in `0..=M`, coefficients are given for
+/// total wavenumbers `n` in `m..=min(J + m, K)`. Triangular truncation is the
+/// case where `J`, `K` and `M` are all equal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SpectralTruncation {
+    pub j: u32,
+    pub k: u32,
+    pub m: u32,
+    /// Spectral data representation type (see Code Table 3.6)
+    pub representation_type: u8,
+    /// Spectral data representation mode (see Code Table 3.7)
+    pub representation_mode: u8,
+}
+
+impl SpectralTruncation {
+    const SIZE: usize = 14;
+
+    pub fn from_grid_def(grid_def: &GridDefinition) -> Result<Self, GribError> {
+        let num = grid_def.grid_tmpl_num();
+        if !(50..=53).contains(&num) {
+            return Err(GribError::Unsupported(format!(
+                "spherical harmonic coefficients in Grid Definition Template 3.{}",
+                num
+            )));
+        }
+        let payload = grid_def.iter().as_slice();
+        let buf = payload
+            .get(START_OF_GRID_TEMPLATE..START_OF_GRID_TEMPLATE + Self::SIZE)
+            .ok_or(GribError::InternalDataError)?;
+        Ok(Self {
+            j: read_as!(u32, buf, 0),
+            k: read_as!(u32, buf, 4),
+            m: read_as!(u32, buf, 8),
+            representation_type: buf[12],
+            representation_mode: buf[13],
+        })
+    }
+
+    /// Returns the largest total wavenumber `n` for the zonal wavenumber
+    /// `m`.
+    pub fn max_n(&self, m: u32) -> u32 {
+        self.j.saturating_add(m).min(self.k)
+    }
+
+    /// Iterates over pairs of the zonal and total wavenumbers `(m, n)` in the
+    /// order in which coefficients are stored, i.e. with `n` varying fastest.
+    pub fn wavenumbers(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        (0..=self.m).flat_map(move |m| (m..=self.max_n(m)).map(move |n| (m, n)))
+    }
+
+    /// Returns the number of complex coefficients, each of which consists
+    /// of the real and imaginary parts.
+    pub fn num_coefficients(&self) -> usize {
+        (0..=self.m)
+            .map(|m| (self.max_n(m) + 1).saturating_sub(m) as usize)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn truncation(j: u32, k: u32, m: u32) -> SpectralTruncation {
+        SpectralTruncation {
+            j,
+            k,
+            m,
+            representation_type: 1,
+            representation_mode: 1,
+        }
+    }
+
+    #[test]
+    fn num_coefficients_of_triangular_truncation() {
+        for m in [0, 1, 2, 63, 639] {
+            let trunc = truncation(m, m, m);
+            let m = m as usize;
+            assert_eq!(trunc.num_coefficients(), (m + 1) * (m + 2) / 2);
+            assert_eq!(trunc.wavenumbers().count(), trunc.num_coefficients());
+        }
+    }
+
+    #[test]
+    fn wavenumbers_of_pentagonal_truncation() {
+        let trunc = truncation(2, 3, 2);
+        assert_eq!(
+            trunc.wavenumbers().collect::<Vec<_>>(),
+            vec![
+                (0, 0),
+                (0, 1),
+                (0, 2),
+                (1, 1),
+                (1, 2),
+                (1, 3),
+                (2, 2),
+                (2, 3)
+            ]
+        );
+        assert_eq!(trunc.num_coefficients(), 8);
+    }
+}
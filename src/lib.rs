@@ -2,9 +2,20 @@ pub mod codetables;
 pub mod context;
 pub mod datatypes;
 pub mod decoders;
+pub mod editor;
 pub mod error;
+#[cfg(any(feature = "zarr", feature = "netcdf"))]
+pub mod export;
+pub mod field;
+pub mod grid;
 pub mod parser;
 pub mod reader;
 mod utils;
 
-pub use context::{from_reader, from_slice};
+#[cfg(feature = "rayon")]
+pub use context::decode_all_rayon;
+pub use context::{
+    from_framed_reader, from_reader, from_reader_with_options, from_slice, from_stream,
+    iter_submessages, message_at_offset, parse_message, skip_to_grib, validate_submessage,
+    FrameFormat, SKIP_TO_GRIB_SCAN_LIMIT,
+};
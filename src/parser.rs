@@ -76,6 +76,7 @@ where
     sect1: SectionInfo,
     sect2: Option<SectionInfo>,
     sect3: SectionInfo,
+    sect6: SectionInfo,
 }
 
 impl<I> Grib2SubmessageStream<I>
@@ -105,6 +106,7 @@ where
             sect1: Default::default(),
             sect2: Default::default(),
             sect3: Default::default(),
+            sect6: Default::default(),
         }
     }
 
@@ -113,6 +115,7 @@ where
         self.sect1 = Default::default();
         self.sect2 = Default::default();
         self.sect3 = Default::default();
+        self.sect6 = Default::default();
     }
 }
 
@@ -125,7 +128,6 @@ where
     fn next(&mut self) -> Option<Result<(usize, usize, Submessage), ParseError>> {
         let mut sect4 = Default::default();
         let mut sect5 = Default::default();
-        let mut sect6 = Default::default();
         let mut sect7 = Default::default();
         loop {
             match self.iter.next()? {
@@ -150,7 +152,7 @@ where
                         sect5 = s;
                     }
                     6 => {
-                        sect6 = s;
+                        self.sect6 = s;
                     }
                     7 => {
                         sect7 = s;
@@ -166,7 +168,7 @@ where
                                 self.sect3.clone(),
                                 sect4,
                                 sect5,
-                                sect6,
+                                self.sect6.clone(),
                                 sect7,
                                 s,
                             ),
@@ -185,19 +187,20 @@ where
     }
 }
 
-pub(crate) struct Grib2SubmessageIndexStream<'cacher, I>
+pub(crate) struct Grib2SubmessageIndexStream<I>
 where
     I: Iterator,
 {
     iter: Grib2SubmessageValidator<I>,
-    sect_cacher: Option<&'cacher mut Vec<SectionInfo>>,
+    sect_cacher: Option<Vec<SectionInfo>>,
     sect0: usize,
     sect1: usize,
     sect2: Option<usize>,
     sect3: usize,
+    sect6: usize,
 }
 
-impl<'cacher, I> Grib2SubmessageIndexStream<'cacher, I>
+impl<I> Grib2SubmessageIndexStream<I>
 where
     I: Iterator,
 {
@@ -209,14 +212,27 @@ where
             sect1: Default::default(),
             sect2: Default::default(),
             sect3: Default::default(),
+            sect6: Default::default(),
         }
     }
 
-    pub(crate) fn with_cacher(mut self, cacher: &'cacher mut Vec<SectionInfo>) -> Self {
-        self.sect_cacher = Some(cacher);
+    pub(crate) fn with_cacher(mut self) -> Self {
+        self.sect_cacher = Some(Vec::new());
         self
     }
 
+    pub(crate) fn into_cached_sections(self) -> Vec<SectionInfo> {
+        self.sect_cacher.unwrap_or_default()
+    }
+
+    /// Takes sections cached so far, leaving the cache empty.
+    pub(crate) fn take_cached_sections(&mut self) -> Vec<SectionInfo> {
+        self.sect_cacher
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
     fn cache_sect(&mut self, sect: SectionInfo) {
         if let Some(cacher) = self.sect_cacher.as_mut() {
             cacher.push(sect);
@@ -228,10 +244,11 @@ where
         self.sect1 = Default::default();
         self.sect2 = Default::default();
         self.sect3 = Default::default();
+        self.sect6 = Default::default();
     }
 }
 
-impl<'cacher, I> Iterator for Grib2SubmessageIndexStream<'cacher, I>
+impl<I> Iterator for Grib2SubmessageIndexStream<I>
 where
     I: Iterator<Item = Result<SectionInfo, ParseError>>,
 {
@@ -240,7 +257,6 @@ where
     fn next(&mut self) -> Option<Result<Grib2SubmessageIndex, ParseError>> {
         let mut sect4 = Default::default();
         let mut sect5 = Default::default();
-        let mut sect6 = Default::default();
         let mut sect7 = Default::default();
         loop {
             match self.iter.next()? {
@@ -274,7 +290,7 @@ where
                         }
                         6 => {
                             self.cache_sect(s);
-                            sect6 = pos;
+                            self.sect6 = pos;
                         }
                         7 => {
                             self.cache_sect(s);
@@ -286,7 +302,7 @@ where
                                 submessage: submessage_count,
                                 sections: (
                                     self.sect0, self.sect1, self.sect2, self.sect3, sect4, sect5,
-                                    sect6, sect7, pos,
+                                    self.sect6, sect7, pos,
                                 ),
                             }));
 
@@ -315,6 +331,7 @@ where
     submessage_count: usize,
     state: Grib2SubmessageValidatorState,
     has_sect3: bool,
+    has_sect6: bool,
 }
 
 impl<I> Grib2SubmessageValidator<I>
@@ -329,6 +346,7 @@ where
             submessage_count: 0,
             state: Grib2SubmessageValidatorState::StartOfMessage,
             has_sect3: false,
+            has_sect6: false,
         }
     }
 
@@ -337,6 +355,7 @@ where
         self.submessage_count = 0;
         self.state = Grib2SubmessageValidatorState::StartOfMessage;
         self.has_sect3 = false;
+        self.has_sect6 = false;
     }
 
     fn new_unexpected_end_of_data_err(
@@ -424,6 +443,33 @@ where
         }
     }
 
+    // Section 6 may be omitted in a repeated submessage, in which case that of
+    // the previous submessage is used.
+    #[inline]
+    fn ensure_next_is_sect6_or_7(
+        &mut self,
+    ) -> Option<Result<(usize, usize, usize, SectionInfo), ParseError>> {
+        match self.iter.next() {
+            Some((pos, Ok(s))) => {
+                self.pos = pos;
+                match s.num {
+                    6 => {
+                        self.state = Grib2SubmessageValidatorState::EndOfSect(6);
+                        self.has_sect6 = true;
+                        Some(Ok((self.pos, self.message_count, self.submessage_count, s)))
+                    }
+                    7 if self.has_sect6 => {
+                        self.state = Grib2SubmessageValidatorState::EndOfSect(7);
+                        Some(Ok((self.pos, self.message_count, self.submessage_count, s)))
+                    }
+                    _ => self.new_invalid_section_order_err(),
+                }
+            }
+            Some((_, Err(e))) => self.wrap_parse_err(e),
+            None => self.new_unexpected_end_of_data_err(),
+        }
+    }
+
     #[inline]
     fn ensure_next_is_sect8(
         &mut self,
@@ -511,7 +557,7 @@ where
             }
             Grib2SubmessageValidatorState::EndOfSect(3) => self.ensure_next_is_sect_num(4),
             Grib2SubmessageValidatorState::EndOfSect(4) => self.ensure_next_is_sect_num(5),
-            Grib2SubmessageValidatorState::EndOfSect(5) => self.ensure_next_is_sect_num(6),
+            Grib2SubmessageValidatorState::EndOfSect(5) => self.ensure_next_is_sect6_or_7(),
             Grib2SubmessageValidatorState::EndOfSect(6) => self.ensure_next_is_sect_num(7),
             Grib2SubmessageValidatorState::EndOfSect(7) => self.ensure_next_is_sect8(),
             Grib2SubmessageValidatorState::EndOfSect(_) => unreachable!(),
@@ -737,6 +783,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn submessage_stream_from_1_message_with_multiple_submessages_without_repeated_sect6() {
+        let sects = new_sect_vec_with_dummy_offset(vec![0, 1, 3, 4, 5, 6, 7, 4, 5, 7, 8]);
+
+        assert_eq!(
+            Grib2SubmessageStream::new(sects.into_iter())
+                .map(|result| result.map(digest_submessage_iter_item))
+                .collect::<Vec<_>>(),
+            vec![
+                Ok((0, 0, 0, 1, None, 2, 3, 4, 5, 6, 0)),
+                Ok((0, 1, 0, 1, None, 2, 7, 8, 5, 9, 10))
+            ],
+        );
+    }
+
+    #[test]
+    fn submessage_stream_from_multiple_messages_without_sect6_in_2nd_message() {
+        // Section 6 is not inherited across messages
+        let sects =
+            new_sect_vec_with_dummy_offset(vec![0, 1, 3, 4, 5, 6, 7, 8, 0, 1, 3, 4, 5, 7, 8]);
+
+        assert_eq!(
+            Grib2SubmessageStream::new(sects.into_iter())
+                .map(|result| result.map(digest_submessage_iter_item))
+                .collect::<Vec<_>>(),
+            vec![
+                Ok((0, 0, 0, 1, None, 2, 3, 4, 5, 6, 7)),
+                Err(ParseError::InvalidSectionOrder(13))
+            ],
+        );
+    }
+
     #[test]
     fn submessage_stream_from_multiple_messages_and_submessages_with_sect2_toggled() {
         // testing cache of submessage_count and sect2
@@ -1248,10 +1326,10 @@ mod tests {
         ];
         let sects = new_sect_vec_with_dummy_offset(sect_nums.clone());
 
-        let mut cacher = Vec::new();
-        let stream = Grib2SubmessageIndexStream::new(sects.into_iter()).with_cacher(&mut cacher);
+        let mut stream = Grib2SubmessageIndexStream::new(sects.into_iter()).with_cacher();
         assert_eq!(
             stream
+                .by_ref()
                 .map(|result| result.map(digest_submessage_index_iter_item))
                 .collect::<Vec<_>>(),
             vec![
@@ -1260,6 +1338,40 @@ mod tests {
                 Ok((1, 0, 15, 16, None, 17, 18, 19, 20, 21, 22))
             ],
         );
-        assert_eq!(cacher.iter().map(|s| s.num).collect::<Vec<_>>(), sect_nums,);
+        assert_eq!(
+            stream
+                .into_cached_sections()
+                .iter()
+                .map(|s| s.num)
+                .collect::<Vec<_>>(),
+            sect_nums,
+        );
+    }
+
+    #[test]
+    fn submessage_index_stream_from_multiple_messages_without_repeated_sect6() {
+        let sect_nums = vec![0, 1, 3, 4, 5, 6, 7, 4, 5, 7, 8, 0, 1, 3, 4, 5, 6, 7, 8];
+        let sects = new_sect_vec_with_dummy_offset(sect_nums.clone());
+
+        let mut stream = Grib2SubmessageIndexStream::new(sects.into_iter()).with_cacher();
+        assert_eq!(
+            stream
+                .by_ref()
+                .map(|result| result.map(digest_submessage_index_iter_item))
+                .collect::<Vec<_>>(),
+            vec![
+                Ok((0, 0, 0, 1, None, 2, 3, 4, 5, 6, 0)),
+                Ok((0, 1, 0, 1, None, 2, 7, 8, 5, 9, 10)),
+                Ok((1, 0, 11, 12, None, 13, 14, 15, 16, 17, 18))
+            ],
+        );
+        assert_eq!(
+            stream
+                .into_cached_sections()
+                .iter()
+                .map(|s| s.num)
+                .collect::<Vec<_>>(),
+            sect_nums,
+        );
     }
 }
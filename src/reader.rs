@@ -5,18 +5,22 @@ use std::result::Result;
 use crate::context::{SectionBody, SectionInfo};
 use crate::datatypes::*;
 use crate::error::*;
-use crate::utils::read_as;
+use crate::utils::{crc32, read_as};
 
 const SECT0_IS_MAGIC: &[u8] = b"GRIB";
 const SECT0_IS_MAGIC_SIZE: usize = SECT0_IS_MAGIC.len();
 const SECT0_IS_SIZE: usize = 16;
-const SECT_HEADER_SIZE: usize = 5;
+pub(crate) const SECT_HEADER_SIZE: usize = 5;
 const SECT8_ES_MAGIC: &[u8] = b"7777";
 pub(crate) const SECT8_ES_SIZE: usize = SECT8_ES_MAGIC.len();
 
+/// Default upper limit of the total length of a message declared in Section 0
+/// (1 TiB). Larger values are considered to come from corrupted headers.
+pub const DEFAULT_MAX_MESSAGE_SIZE: u64 = 1 << 40;
+
 /// # Example
 /// ```
-/// use grib::context::{SectionBody, SectionInfo};
+/// use grib::context::SectionBody;
 /// use grib::datatypes::Indicator;
 /// use grib::reader::{Grib2SectionStream, SeekableGrib2Reader};
 ///
@@ -28,16 +32,13 @@ pub(crate) const SECT8_ES_SIZE: usize = SECT8_ES_MAGIC.len();
 ///     let grib2_reader = SeekableGrib2Reader::new(f);
 ///
 ///     let mut sect_stream = Grib2SectionStream::new(grib2_reader);
+///     let sect = sect_stream.next().unwrap()?;
+///     assert_eq!((sect.num, sect.offset, sect.size), (0, 0, 16));
 ///     assert_eq!(
-///         sect_stream.next(),
-///         Some(Ok(SectionInfo {
-///             num: 0,
-///             offset: 0,
-///             size: 16,
-///             body: Some(SectionBody::Section0(Indicator {
-///                 discipline: 0,
-///                 total_length: 193,
-///             })),
+///         sect.body,
+///         Some(SectionBody::Section0(Indicator {
+///             discipline: 0,
+///             total_length: 193,
 ///         }))
 ///     );
 ///     Ok(())
@@ -47,6 +48,8 @@ pub struct Grib2SectionStream<R> {
     reader: R,
     whole_size: usize,
     rest_size: usize,
+    compute_checksums: bool,
+    max_message_size: u64,
 }
 
 impl<R> Grib2SectionStream<R> {
@@ -69,6 +72,35 @@ impl<R> Grib2SectionStream<R> {
             reader,
             whole_size: 0,
             rest_size: 0,
+            compute_checksums: false,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+        }
+    }
+
+    /// Sets whether to compute a CRC-32 of the raw bytes of each section.
+    pub fn with_checksums(self, compute_checksums: bool) -> Self {
+        Self {
+            compute_checksums,
+            ..self
+        }
+    }
+
+    /// Sets the upper limit of the total length of a message. Messages
+    /// declaring larger lengths in Section 0 are rejected with
+    /// [`ParseError::ImplausibleMessageSize`] before their contents are read.
+    pub fn with_max_message_size(self, max_message_size: u64) -> Self {
+        Self {
+            max_message_size,
+            ..self
+        }
+    }
+
+    /// Sets the position in the source where the reader is placed, so that
+    /// offsets of sections are reported relative to the start of the source.
+    pub(crate) fn with_start_offset(self, offset: usize) -> Self {
+        Self {
+            whole_size: offset,
+            ..self
         }
     }
 
@@ -84,17 +116,26 @@ where
     #[inline]
     fn next_sect0(&mut self) -> Option<Result<SectionInfo, ParseError>> {
         let offset = self.whole_size;
-        let result = self.reader.read_sect0().transpose()?.map(|indicator| {
-            let message_size = indicator.total_length as usize;
+        let result = self.reader.read_sect0().transpose()?.and_then(|indicator| {
+            if indicator.total_length > self.max_message_size {
+                return Err(ParseError::ImplausibleMessageSize {
+                    declared: indicator.total_length,
+                });
+            }
+            let message_size = usize::try_from(indicator.total_length)
+                .ok()
+                .filter(|size| *size >= SECT0_IS_SIZE + SECT8_ES_SIZE)
+                .ok_or(ParseError::UnexpectedEndOfData(offset))?;
             self.whole_size += message_size;
             let sect_info = SectionInfo {
                 num: 0,
                 offset,
                 size: SECT0_IS_SIZE,
                 body: Some(SectionBody::Section0(indicator)),
+                checksum: None,
             };
             self.rest_size = message_size - SECT0_IS_SIZE;
-            sect_info
+            Ok(sect_info)
         });
         Some(result)
     }
@@ -107,6 +148,7 @@ where
                 offset: self.whole_size - self.rest_size,
                 size: SECT8_ES_SIZE,
                 body: None,
+                checksum: None,
             };
             self.rest_size -= SECT8_ES_SIZE;
             sect_info
@@ -124,12 +166,16 @@ where
                     Ok(body) => {
                         let body = Some(body);
                         let (size, num) = header;
-                        self.rest_size -= size;
+                        self.rest_size = match self.rest_size.checked_sub(size) {
+                            Some(rest_size) => rest_size,
+                            None => return Some(Err(ParseError::UnexpectedEndOfData(offset))),
+                        };
                         Some(Ok(SectionInfo {
                             num,
                             offset,
                             size,
                             body,
+                            checksum: None,
                         }))
                     }
                     Err(e) => Some(Err(e)),
@@ -147,11 +193,21 @@ where
     type Item = Result<SectionInfo, ParseError>;
 
     fn next(&mut self) -> Option<Result<SectionInfo, ParseError>> {
-        match self.rest_size {
+        let result = match self.rest_size {
             0 => self.next_sect0(),
             SECT8_ES_SIZE => self.next_sect8(),
             _ => self.next_sect(),
+        };
+        if !self.compute_checksums {
+            return result;
         }
+        // The reader is placed at the end of the section after reading it
+        // again, so that the stream can continue as is.
+        Some(result?.and_then(|mut sect| {
+            let bytes = self.reader.read_raw_section(&sect)?;
+            sect.checksum = Some(crc32(&bytes));
+            Ok(sect)
+        }))
     }
 }
 
@@ -167,9 +223,24 @@ pub trait Grib2Read: Read + Seek {
     fn read_sect_header(&mut self) -> Result<Option<SectHeader>, ParseError>;
     fn read_sect_payload(&mut self, header: &SectHeader) -> Result<SectionBody, ParseError>;
     fn read_sect_payload_as_slice(&mut self, sect: &SectionInfo) -> Result<Box<[u8]>, ParseError>;
+
+    /// Reads the payload of a section into an owned buffer, which callers can
+    /// keep independently of the reader.
+    fn read_sect_payload_owned(&mut self, sect: &SectionInfo) -> Result<Vec<u8>, ParseError> {
+        self.read_sect_payload_as_slice(sect).map(Vec::from)
+    }
     fn read_sect6_payload(&mut self, size: usize) -> Result<SectionBody, ParseError>;
     fn skip_sect7_payload(&mut self, size: usize) -> Result<SectionBody, ParseError>;
     fn read_slice_without_offset_check(&mut self, size: usize) -> Result<Box<[u8]>, ParseError>;
+
+    /// Reads the whole section including the 5-octet common header, which is
+    /// useful for copying sections as they are.
+    fn read_raw_section(&mut self, info: &SectionInfo) -> io::Result<Vec<u8>> {
+        self.seek(SeekFrom::Start(info.offset as u64))?;
+        let mut buf = vec![0; info.size];
+        self.read_exact(&mut buf[..])?;
+        Ok(buf)
+    }
 }
 
 pub struct SeekableGrib2Reader<R> {
@@ -220,7 +291,9 @@ impl<R: Read + Seek> Grib2Read for SeekableGrib2Reader<R> {
         check_size!(size, buf.len());
 
         if &buf[0..SECT0_IS_MAGIC_SIZE] != SECT0_IS_MAGIC {
-            return Err(ParseError::NotGRIB);
+            return Err(ParseError::NotGRIB {
+                found: buf[0..SECT0_IS_MAGIC_SIZE].try_into().unwrap(),
+            });
         }
         let discipline = buf[6];
         let version = buf[7];
@@ -255,13 +328,22 @@ impl<R: Read + Seek> Grib2Read for SeekableGrib2Reader<R> {
 
         let sect_size = read_as!(u32, buf, 0) as usize;
         let sect_num = buf[4];
+        if sect_size < SECT_HEADER_SIZE {
+            return Err(ParseError::SectionTooShort {
+                section_num: sect_num,
+                minimum: SECT_HEADER_SIZE,
+                declared: sect_size,
+            });
+        }
 
         Ok(Some((sect_size, sect_num)))
     }
 
     fn read_sect_payload(&mut self, header: &SectHeader) -> Result<SectionBody, ParseError> {
         let (size, num) = header;
-        let body_size = size - SECT_HEADER_SIZE;
+        let body_size = size
+            .checked_sub(SECT_HEADER_SIZE)
+            .ok_or(BuildError::SectionSizeTooSmall(*size))?;
         let body = match num {
             1 => SectionBody::Section1(Identification::from_payload(
                 self.read_slice_without_offset_check(body_size)?,
@@ -287,6 +369,11 @@ impl<R: Read + Seek> Grib2Read for SeekableGrib2Reader<R> {
     }
 
     fn read_sect_payload_as_slice(&mut self, sect: &SectionInfo) -> Result<Box<[u8]>, ParseError> {
+        self.read_sect_payload_owned(sect)
+            .map(Vec::into_boxed_slice)
+    }
+
+    fn read_sect_payload_owned(&mut self, sect: &SectionInfo) -> Result<Vec<u8>, ParseError> {
         let body_offset = sect.offset + SECT_HEADER_SIZE;
         self.seek(SeekFrom::Start(body_offset as u64))?;
 
@@ -294,21 +381,30 @@ impl<R: Read + Seek> Grib2Read for SeekableGrib2Reader<R> {
         let mut buf = vec![0; body_size];
         self.read_exact(buf.as_mut_slice())?;
 
-        Ok(buf.into_boxed_slice())
+        Ok(buf)
     }
 
     fn read_sect6_payload(&mut self, body_size: usize) -> Result<SectionBody, ParseError> {
         let mut buf = [0; 1]; // octet 6
         self.read_exact(&mut buf[..])?;
 
-        let len_extra = body_size - buf.len();
-        if len_extra > 0 {
-            let mut buf = vec![0; len_extra];
-            self.read_exact(&mut buf[..])?;
-        }
+        let len_extra = body_size
+            .checked_sub(buf.len())
+            .ok_or(BuildError::SectionSizeTooSmall(body_size))?;
+        let extra = if len_extra > 0 {
+            Some(self.read_slice_without_offset_check(len_extra)?)
+        } else {
+            None
+        };
+        // the size of the section determines the length of the bit-map
+        let bitmap = match buf[0] {
+            0 => Some(extra.map(Vec::from).unwrap_or_default()),
+            _ => None,
+        };
 
         Ok(SectionBody::Section6(BitMap {
             bitmap_indicator: buf[0],
+            bitmap,
         }))
     }
 
@@ -319,20 +415,245 @@ impl<R: Read + Seek> Grib2Read for SeekableGrib2Reader<R> {
     }
 
     fn read_slice_without_offset_check(&mut self, size: usize) -> Result<Box<[u8]>, ParseError> {
-        let mut buf = vec![0; size];
-        self.read_exact(&mut buf[..])?;
+        // avoids allocating a huge buffer before reading when the size is broken
+        let mut buf = Vec::new();
+        self.by_ref().take(size as u64).read_to_end(&mut buf)?;
+        if buf.len() != size {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "failed to fill whole buffer",
+            )
+            .into());
+        }
         Ok(buf.into_boxed_slice())
     }
 }
 
 type SectHeader = (usize, u8);
 
+/// Source of byte ranges such as an HTTP server or a cloud object store
+/// supporting range requests.
+pub trait RangeFetch {
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Returns the total length of the content, e.g. by an HTTP HEAD request.
+    fn content_length(&mut self) -> Result<u64, Self::Error>;
+
+    /// Returns octets from `start` (inclusive) to `end` (exclusive).
+    fn fetch(&mut self, start: u64, end: u64) -> Result<Vec<u8>, Self::Error>;
+}
+
+/// [`Read`] + [`Seek`] adapter over a [`RangeFetch`], which allows GRIB2 data
+/// stored remotely to be parsed without downloading the whole content.
+///
+/// Seeking only updates the position. Reads are served from a block cached by
+/// the previous fetch where possible, so that reading small section headers
+/// does not issue one request each.
+pub struct RangeReader<F> {
+    fetcher: F,
+    len: u64,
+    pos: u64,
+    block_size: usize,
+    cache_start: u64,
+    cache: Vec<u8>,
+}
+
+impl<F: RangeFetch> RangeReader<F> {
+    const DEFAULT_BLOCK_SIZE: usize = 64 * 1024;
+
+    pub fn new(mut fetcher: F) -> Result<Self, F::Error> {
+        let len = fetcher.content_length()?;
+        Ok(Self {
+            fetcher,
+            len,
+            pos: 0,
+            block_size: Self::DEFAULT_BLOCK_SIZE,
+            cache_start: 0,
+            cache: Vec::new(),
+        })
+    }
+
+    /// Sets the minimum number of octets fetched at once.
+    pub fn with_block_size(self, block_size: usize) -> Self {
+        Self { block_size, ..self }
+    }
+
+    pub fn into_inner(self) -> F {
+        self.fetcher
+    }
+
+    fn cached(&self) -> &[u8] {
+        let cache_end = self.cache_start + self.cache.len() as u64;
+        if self.pos >= self.cache_start && self.pos < cache_end {
+            &self.cache[(self.pos - self.cache_start) as usize..]
+        } else {
+            &[]
+        }
+    }
+}
+
+impl<F: RangeFetch> Read for RangeReader<F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.pos >= self.len {
+            return Ok(0);
+        }
+        if self.cached().is_empty() {
+            let size = self.block_size.max(buf.len()) as u64;
+            let end = self.len.min(self.pos + size);
+            self.cache = self
+                .fetcher
+                .fetch(self.pos, end)
+                .map_err(io::Error::other)?;
+            self.cache_start = self.pos;
+            if self.cache.is_empty() {
+                return Ok(0);
+            }
+        }
+        let cached = self.cached();
+        let size = cached.len().min(buf.len());
+        buf[..size].copy_from_slice(&cached[..size]);
+        self.pos += size as u64;
+        Ok(size)
+    }
+}
+
+impl<F: RangeFetch> Seek for RangeReader<F> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new_pos = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+        };
+        let new_pos = new_pos.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        self.pos = new_pos;
+        Ok(new_pos)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     use std::io::Cursor;
 
+    struct CountingFetcher {
+        content: Vec<u8>,
+        ranges: Vec<(u64, u64)>,
+    }
+
+    impl RangeFetch for CountingFetcher {
+        type Error = io::Error;
+
+        fn content_length(&mut self) -> Result<u64, Self::Error> {
+            Ok(self.content.len() as u64)
+        }
+
+        fn fetch(&mut self, start: u64, end: u64) -> Result<Vec<u8>, Self::Error> {
+            self.ranges.push((start, end));
+            Ok(self.content[start as usize..end as usize].to_vec())
+        }
+    }
+
+    #[test]
+    fn read_sect_header_with_too_small_size() {
+        let mut reader = SeekableGrib2Reader::new(Cursor::new(vec![0, 0, 0, 3, 4]));
+        assert_eq!(
+            reader.read_sect_header(),
+            Err(ParseError::SectionTooShort {
+                section_num: 4,
+                minimum: 5,
+                declared: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn read_sect6_with_bitmap() {
+        let read = |bytes: &[u8]| {
+            let mut reader = SeekableGrib2Reader::new(Cursor::new(bytes.to_vec()));
+            let header = reader.read_sect_header().unwrap().unwrap();
+            reader.read_sect_payload(&header).unwrap()
+        };
+
+        assert_eq!(
+            read(&[0, 0, 0, 8, 6, 0, 0b1011_0000, 0b0100_0000]),
+            SectionBody::Section6(BitMap {
+                bitmap_indicator: 0,
+                bitmap: Some(vec![0b1011_0000, 0b0100_0000]),
+            })
+        );
+        assert_eq!(
+            read(&[0, 0, 0, 6, 6, 255]),
+            SectionBody::Section6(BitMap {
+                bitmap_indicator: 255,
+                bitmap: None,
+            })
+        );
+        assert_eq!(
+            read(&[0, 0, 0, 6, 6, 254]),
+            SectionBody::Section6(BitMap {
+                bitmap_indicator: 254,
+                bitmap: None,
+            })
+        );
+    }
+
+    #[cfg(feature = "jpeg2000")]
+    #[test]
+    fn parse_and_decode_through_range_reader() {
+        let content =
+            std::fs::read("testdata/CMC_glb_TMP_ISBL_1_latlon.24x.24_2021051800_P000.grib2")
+                .unwrap();
+        let expected = crate::from_slice(&content).unwrap().get_values(0).unwrap();
+        let len = content.len() as u64;
+
+        let fetcher = CountingFetcher {
+            content,
+            ranges: Vec::new(),
+        };
+        let reader = RangeReader::new(fetcher).unwrap().with_block_size(1024);
+        let grib2 = crate::from_reader(reader).unwrap();
+        let num_fetches = grib2.reader.borrow().reader.fetcher.ranges.len();
+        // headers of all sections fit in a few blocks, while Section 7 is
+        // skipped without being fetched
+        assert!(num_fetches <= 3);
+
+        let actual = grib2.get_values(0).unwrap();
+        assert_eq!(actual, expected);
+        let ranges = &grib2.reader.borrow().reader.fetcher.ranges;
+        assert!(ranges.iter().all(|(start, end)| start < end && *end <= len));
+    }
+
+    #[test]
+    fn seek_in_range_reader_without_fetching() {
+        let fetcher = CountingFetcher {
+            content: (0..100).collect(),
+            ranges: Vec::new(),
+        };
+        let mut reader = RangeReader::new(fetcher).unwrap().with_block_size(10);
+        assert_eq!(reader.seek(SeekFrom::End(-5)).unwrap(), 95);
+        assert_eq!(reader.seek(SeekFrom::Current(-45)).unwrap(), 50);
+        assert!(reader.seek(SeekFrom::Current(-51)).is_err());
+        assert!(reader.fetcher.ranges.is_empty());
+
+        let mut buf = [0; 4];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [50, 51, 52, 53]);
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [54, 55, 56, 57]);
+        assert_eq!(reader.fetcher.ranges, vec![(50, 60)]);
+
+        reader.seek(SeekFrom::Start(98)).unwrap();
+        assert_eq!(reader.read(&mut buf).unwrap(), 2);
+        assert_eq!(reader.read(&mut buf).unwrap(), 0);
+        assert_eq!(reader.fetcher.ranges, vec![(50, 60), (98, 100)]);
+    }
+
     #[test]
     fn read_one_grib2_message() -> Result<(), Box<dyn std::error::Error>> {
         let f = std::fs::File::open(
@@ -363,6 +684,68 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn read_raw_section_and_parse_again() -> Result<(), Box<dyn std::error::Error>> {
+        let f = std::fs::File::open(
+            "testdata/icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+        )?;
+        let f = std::io::BufReader::new(f);
+
+        let grib2_reader = SeekableGrib2Reader::new(f);
+        let mut sect_stream = Grib2SectionStream::new(grib2_reader);
+        let sects = sect_stream
+            .by_ref()
+            .take(9)
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut grib2_reader = sect_stream.into_reader();
+
+        for sect in sects.iter().filter(|s| (1..=7).contains(&s.num)) {
+            let raw = grib2_reader.read_raw_section(sect)?;
+            assert_eq!(raw.len(), sect.size);
+
+            let mut raw_reader = SeekableGrib2Reader::new(Cursor::new(raw));
+            let header = raw_reader.read_sect_header()?.unwrap();
+            let body = raw_reader.read_sect_payload(&header)?;
+            let (size, num) = header;
+            let actual = SectionInfo {
+                num,
+                offset: sect.offset,
+                size,
+                body: Some(body),
+                checksum: None,
+            };
+            assert_eq!(&actual, sect);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_sect7_payload_owned() -> Result<(), Box<dyn std::error::Error>> {
+        let f =
+            std::fs::File::open("testdata/CMC_glb_TMP_ISBL_1_latlon.24x.24_2021051800_P000.grib2")?;
+        let f = std::io::BufReader::new(f);
+
+        let grib2_reader = SeekableGrib2Reader::new(f);
+        let mut sect_stream = Grib2SectionStream::new(grib2_reader);
+        let sect7 = sect_stream
+            .by_ref()
+            .find(|result| matches!(result, Ok(sect) if sect.num == 7))
+            .unwrap()?;
+        let mut grib2_reader = sect_stream.into_reader();
+
+        let owned = grib2_reader.read_sect_payload_owned(&sect7)?;
+        assert_eq!(owned.len(), sect7.size - SECT_HEADER_SIZE);
+        // SOC marker of a JPEG 2000 code stream
+        assert_eq!(&owned[..2], &[0xff, 0x4f]);
+        assert_eq!(
+            owned,
+            grib2_reader.read_sect_payload_as_slice(&sect7)?.into_vec()
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn read_multiple_grib2_messages() -> Result<(), Box<dyn std::error::Error>> {
         let f = std::fs::File::open(
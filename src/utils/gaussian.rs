@@ -0,0 +1,79 @@
+use std::f64::consts::PI;
+
+/// Computes Gaussian latitudes in degrees of a Gaussian grid with `n`
+/// parallels between a pole and the equator.
+///
+/// The latitudes are the arcsines of the roots of the Legendre polynomial of
+/// degree `2n`, which are found using the Newton-Raphson method. `2n`
+/// latitudes symmetric about the equator are returned in order from north to
+/// south.
+pub fn gauss_legendre_latitudes(n: u32) -> Vec<f64> {
+    // The iteration usually converges in a few steps, but may not reach
+    // `f64::EPSILON` due to rounding errors.
+    const MAX_NEWTON_ITERATIONS: usize = 100;
+
+    let n = n as usize;
+    let degree = 2 * n;
+    let mut north = Vec::with_capacity(n);
+
+    for k in 1..=n {
+        // initial approximation of the k-th root
+        let mut x = (PI * (k as f64 - 0.25) / (degree as f64 + 0.5)).cos();
+        for _ in 0..MAX_NEWTON_ITERATIONS {
+            let (p, dp) = legendre_with_derivative(degree, x);
+            let dx = p / dp;
+            x -= dx;
+            if dx.abs() <= f64::EPSILON {
+                break;
+            }
+        }
+        north.push(x.asin().to_degrees());
+    }
+
+    let south = north.iter().rev().map(|lat| -lat);
+    let mut lats = north.clone();
+    lats.extend(south);
+    lats
+}
+
+/// Evaluates the Legendre polynomial of degree `degree` and its derivative at
+/// `x`.
+fn legendre_with_derivative(degree: usize, x: f64) -> (f64, f64) {
+    let mut p_prev = 1.;
+    let mut p = x;
+    for l in 2..=degree {
+        let l = l as f64;
+        let p_next = ((2. * l - 1.) * x * p - (l - 1.) * p_prev) / l;
+        p_prev = p;
+        p = p_next;
+    }
+    let dp = degree as f64 * (x * p - p_prev) / (x * x - 1.);
+    (p, dp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! test_gaussian_latitudes {
+        ($(($name:ident, $n:expr, $first:expr, $second:expr),)*) => ($(
+            #[test]
+            fn $name() {
+                let lats = gauss_legendre_latitudes($n);
+                assert_eq!(lats.len(), 2 * $n);
+                assert!((lats[0] - $first).abs() < 1e-10);
+                assert!((lats[1] - $second).abs() < 1e-10);
+                for i in 0..$n {
+                    assert_eq!(lats[i], -lats[2 * $n - 1 - i]);
+                }
+                assert!(lats.windows(2).all(|w| w[0] > w[1]));
+            }
+        )*);
+    }
+
+    test_gaussian_latitudes! {
+        (gaussian_latitudes_n32, 32, 87.863798839232, 85.096526988317),
+        (gaussian_latitudes_n64, 64, 88.927735352296, 87.538705213027),
+        (gaussian_latitudes_n128, 128, 89.462821568577, 88.766951352842),
+    }
+}
@@ -0,0 +1,459 @@
+use std::io::{self, Read};
+
+mod gaussian;
+
+pub use gaussian::gauss_legendre_latitudes;
+
+pub(crate) trait GribInt<I> {
+    fn as_grib_int(&self) -> I;
+}
+
+macro_rules! add_impl_for_ints {
+    ($(($ty_src:ty, $ty_dst:ty),)*) => ($(
+        impl GribInt<$ty_dst> for $ty_src {
+            fn as_grib_int(&self) -> $ty_dst {
+                if self.leading_zeros() == 0 {
+                    let abs = (self << 1 >> 1) as $ty_dst;
+                    -abs
+                } else {
+                    *self as $ty_dst
+                }
+            }
+        }
+    )*);
+}
+
+add_impl_for_ints! {
+    (u8, i8),
+    (u16, i16),
+    (u32, i32),
+    (u64, i64),
+}
+
+macro_rules! read_as {
+    ($ty:ty, $buf:ident, $start:expr) => {{
+        let end = $start + std::mem::size_of::<$ty>();
+        <$ty>::from_be_bytes($buf[$start..end].try_into().unwrap())
+    }};
+}
+pub(crate) use read_as;
+
+#[derive(Clone)]
+pub(crate) struct NBitwiseIterator<'a> {
+    slice: &'a [u8],
+    size: usize,
+    pos: usize,
+    offset: usize,
+}
+
+impl<'a> NBitwiseIterator<'a> {
+    pub(crate) fn new(slice: &'a [u8], size: usize) -> Self {
+        Self {
+            slice,
+            size,
+            pos: 0,
+            offset: 0,
+        }
+    }
+
+    pub(crate) fn with_offset(self, offset_bits: usize) -> Self {
+        Self {
+            offset: offset_bits,
+            ..self
+        }
+    }
+}
+
+impl<'a> Iterator for NBitwiseIterator<'a> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        let new_offset = self.offset + self.size;
+        let (new_pos, new_offset) = (self.pos + new_offset / 8, new_offset % 8);
+
+        if self.pos >= self.slice.len()
+            || new_pos > self.slice.len()
+            || (new_pos == self.slice.len() && new_offset > 0)
+        {
+            return None;
+        }
+
+        let val = self.slice[self.pos] << self.offset >> self.offset;
+        let mut val: u32 = u32::from(val);
+        if new_pos == self.pos {
+            val >>= 8 - new_offset;
+        } else {
+            let mut pos = self.pos + 1;
+            while pos < new_pos {
+                val = (val << 8) | u32::from(self.slice[pos]);
+                pos += 1;
+            }
+            if new_offset > 0 {
+                let shift = 8 - new_offset;
+                let last_val = u32::from(self.slice[pos]) >> shift;
+                val = (val << new_offset) | last_val;
+            }
+        }
+
+        self.pos = new_pos;
+        self.offset = new_offset;
+        Some(val)
+    }
+}
+
+/// Iterator over `size`-bit integers like [`NBitwiseIterator`] reading from
+/// `reader` in blocks of `chunk_size` octets, so that the whole bit stream is
+/// not held in memory.
+///
+/// Bits remaining at the end of a block are kept and joined with those of
+/// the next block, so values may straddle block boundaries. Iteration stops
+/// at the end of `reader` or at an I/O error, which is available with
+/// [`ChunkedNBitwiseIterator::take_error`].
+pub(crate) struct ChunkedNBitwiseIterator<R> {
+    reader: R,
+    chunk: Box<[u8]>,
+    chunk_len: usize,
+    chunk_pos: usize,
+    size: usize,
+    // pending bits taken from the chunk, right-aligned
+    bits: u64,
+    num_bits: usize,
+    error: Option<io::Error>,
+}
+
+impl<R: Read> ChunkedNBitwiseIterator<R> {
+    pub(crate) fn new(reader: R, size: usize, chunk_size: usize) -> Self {
+        Self {
+            reader,
+            chunk: vec![0; chunk_size.max(1)].into_boxed_slice(),
+            chunk_len: 0,
+            chunk_pos: 0,
+            size,
+            bits: 0,
+            num_bits: 0,
+            error: None,
+        }
+    }
+
+    pub(crate) fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    fn next_octet(&mut self) -> Option<u8> {
+        if self.chunk_pos == self.chunk_len {
+            self.chunk_len = loop {
+                match self.reader.read(&mut self.chunk) {
+                    Ok(len) => break len,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => {
+                        self.error = Some(e);
+                        break 0;
+                    }
+                }
+            };
+            self.chunk_pos = 0;
+            if self.chunk_len == 0 {
+                return None;
+            }
+        }
+        let octet = self.chunk[self.chunk_pos];
+        self.chunk_pos += 1;
+        Some(octet)
+    }
+}
+
+impl<R: Read> Iterator for ChunkedNBitwiseIterator<R> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        // `size` is at most 32, so pending bits fit in 64 bits.
+        while self.num_bits < self.size {
+            let octet = self.next_octet()?;
+            self.bits = (self.bits << 8) | u64::from(octet);
+            self.num_bits += 8;
+        }
+        self.num_bits -= self.size;
+        let val = self.bits >> self.num_bits;
+        self.bits &= (1 << self.num_bits) - 1;
+        Some(val as u32)
+    }
+}
+
+/// Appends `values` to `output` as a bit stream of `bits_per_value`-bit
+/// integers, most significant bit first and without padding between values.
+/// The last octet is padded with zeros if it is partially filled.
+///
+/// Bits of each value beyond `bits_per_value` are ignored.
+pub(crate) fn pack_bits(values: &[u32], bits_per_value: u8, output: &mut Vec<u8>) {
+    let nbit = u32::from(bits_per_value);
+    assert!(nbit <= 32, "bits_per_value should be 32 or less: {}", nbit);
+    let mask = u64::MAX >> (64 - nbit.max(1));
+    let mut buf: u64 = 0;
+    let mut buf_nbit = 0;
+    output.reserve((values.len() * nbit as usize).div_ceil(8));
+    for value in values.iter() {
+        if nbit == 0 {
+            break;
+        }
+        buf = (buf << nbit) | (u64::from(*value) & mask);
+        buf_nbit += nbit;
+        while buf_nbit >= 8 {
+            buf_nbit -= 8;
+            output.push((buf >> buf_nbit) as u8);
+        }
+    }
+    if buf_nbit > 0 {
+        output.push((buf << (8 - buf_nbit)) as u8);
+    }
+}
+
+/// Reads `count` integers of `bits_per_value` bits from `input`, which is the
+/// inverse of [`pack_bits`].
+#[cfg(test)]
+pub(crate) fn unpack_bits(input: &[u8], bits_per_value: u8, count: usize) -> Vec<u32> {
+    if bits_per_value == 0 {
+        return vec![0; count];
+    }
+    NBitwiseIterator::new(input, usize::from(bits_per_value))
+        .take(count)
+        .collect()
+}
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb88320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Computes a CRC-32 (IEEE 802.3) of `bytes`.
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, b| {
+        CRC32_TABLE[((crc ^ u32::from(*b)) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Reads the whole content of the xz-compressed file at `path`.
+#[cfg(test)]
+pub(crate) fn bytes_from_xz_file(path: &str) -> Vec<u8> {
+    let f = std::fs::File::open(path).unwrap();
+    let mut buf = Vec::new();
+    xz2::bufread::XzDecoder::new(std::io::BufReader::new(f))
+        .read_to_end(&mut buf)
+        .unwrap();
+    buf
+}
+
+/// Asserts that decoded values are equal within `tol`, treating NaNs at the
+/// same positions as equal, and reports the first differing index otherwise.
+#[cfg(test)]
+#[track_caller]
+pub(crate) fn assert_fields_close(actual: &[f32], expected: &[f32], tol: f32) {
+    assert_eq!(actual.len(), expected.len(), "lengths differ");
+    let diff = actual.iter().zip(expected.iter()).position(|(a, e)| {
+        if a.is_nan() || e.is_nan() {
+            a.is_nan() != e.is_nan()
+        } else {
+            (a - e).abs() > tol
+        }
+    });
+    if let Some(i) = diff {
+        panic!(
+            "values differ at index {}: {} != {} (tolerance: {})",
+            i, actual[i], expected[i], tol
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use proptest::prelude::*;
+    use std::convert::TryInto;
+
+    #[test]
+    fn crc32_of_check_string() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+    }
+
+    #[test]
+    fn into_grib_i8() {
+        let input: Vec<u8> = vec![0b01000000, 0b00000001, 0b10000001, 0b11000000];
+        let output: Vec<i8> = vec![64, 1, -1, -64];
+
+        let mut actual = Vec::new();
+        let mut pos = 0;
+        while pos < input.len() {
+            let val = u8::from_be_bytes(input[pos..pos + 1].try_into().unwrap());
+            pos += 1;
+            let val = val.as_grib_int();
+            actual.push(val);
+        }
+
+        assert_eq!(actual, output);
+    }
+
+    #[test]
+    fn into_grib_i16() {
+        let input: Vec<u8> = vec![
+            0b00000000, 0b01000000, 0b00000000, 0b00000001, 0b10000000, 0b00000001, 0b10000000,
+            0b01000000,
+        ];
+        let output: Vec<i16> = vec![64, 1, -1, -64];
+
+        let mut actual = Vec::new();
+        let mut pos = 0;
+        while pos < input.len() {
+            let val = u16::from_be_bytes(input[pos..pos + 2].try_into().unwrap());
+            pos += 2;
+            let val = val.as_grib_int();
+            actual.push(val);
+        }
+
+        assert_eq!(actual, output);
+    }
+
+    #[test]
+    fn pack_bits_with_padding() {
+        let mut output = Vec::new();
+        pack_bits(&[0b00000, 0b00011, 0b11111, 0b11111], 5, &mut output);
+        assert_eq!(output, vec![0b00000000, 0b11111111, 0b11110000]);
+        assert_eq!(
+            unpack_bits(&output, 5, 4),
+            vec![0b00000, 0b00011, 0b11111, 0b11111]
+        );
+    }
+
+    #[test]
+    fn fields_close_with_nans() {
+        assert_fields_close(&[1., f32::NAN, 3.], &[1.0005, f32::NAN, 3.], 1e-3);
+    }
+
+    #[test]
+    #[should_panic(expected = "values differ at index 1")]
+    fn fields_differing_in_nans() {
+        assert_fields_close(&[1., f32::NAN, 3.], &[1., 2., 3.], 1e-3);
+    }
+
+    #[test]
+    #[should_panic(expected = "values differ at index 2")]
+    fn fields_differing_beyond_tolerance() {
+        assert_fields_close(&[1., 2., 3.], &[1., 2., 3.1], 1e-3);
+    }
+
+    proptest! {
+        #[test]
+        fn pack_and_unpack_bits_roundtrip(
+            (bits_per_value, values) in (1u8..=32).prop_flat_map(|nbit| {
+                let max = u32::MAX >> (32 - u32::from(nbit));
+                (Just(nbit), prop::collection::vec(0..=max, 0..64))
+            })
+        ) {
+            let mut output = Vec::new();
+            pack_bits(&values, bits_per_value, &mut output);
+            prop_assert_eq!(
+                output.len(),
+                (values.len() * usize::from(bits_per_value)).div_ceil(8)
+            );
+            prop_assert_eq!(unpack_bits(&output, bits_per_value, values.len()), values);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn chunked_nbitwise_iterator_same_as_whole_slice(
+            size in 1usize..=32,
+            chunk_size in 1usize..=9,
+            slice in prop::collection::vec(any::<u8>(), 0..64),
+        ) {
+            let expected = NBitwiseIterator::new(&slice, size).collect::<Vec<_>>();
+            let actual =
+                ChunkedNBitwiseIterator::new(slice.as_slice(), size, chunk_size).collect::<Vec<_>>();
+            prop_assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn nbitwise_iterator_u2() {
+        let slice: [u8; 5] = [0, 255, 255, 0, 0];
+
+        let mut iter = NBitwiseIterator::new(&slice, 2);
+        assert_eq!(iter.next(), Some(0b00));
+        assert_eq!(iter.next(), Some(0b00));
+        assert_eq!(iter.next(), Some(0b00));
+        assert_eq!(iter.next(), Some(0b00));
+        assert_eq!(iter.next(), Some(0b11));
+        assert_eq!(iter.next(), Some(0b11));
+    }
+
+    #[test]
+    fn nbitwise_iterator_u5() {
+        let slice: [u8; 5] = [0, 255, 255, 0, 0];
+
+        let mut iter = NBitwiseIterator::new(&slice, 5);
+        assert_eq!(iter.next(), Some(0b00000));
+        assert_eq!(iter.next(), Some(0b00011));
+        assert_eq!(iter.next(), Some(0b11111));
+        assert_eq!(iter.next(), Some(0b11111));
+        assert_eq!(iter.next(), Some(0b11110));
+        assert_eq!(iter.next(), Some(0b00000));
+        assert_eq!(iter.next(), Some(0b00000));
+        assert_eq!(iter.next(), Some(0b00000));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn nbitwise_iterator_u9() {
+        let slice: [u8; 5] = [0, 255, 255, 0, 0];
+
+        let mut iter = NBitwiseIterator::new(&slice, 9);
+        assert_eq!(iter.next(), Some(0b000000001));
+        assert_eq!(iter.next(), Some(0b111111111));
+        assert_eq!(iter.next(), Some(0b111111000));
+        assert_eq!(iter.next(), Some(0b000000000));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn nbitwise_iterator_u13() {
+        let slice: [u8; 5] = [0, 255, 255, 0, 0];
+
+        let mut iter = NBitwiseIterator::new(&slice, 13);
+        assert_eq!(iter.next(), Some(0b0000000011111));
+        assert_eq!(iter.next(), Some(0b1111111111100));
+        assert_eq!(iter.next(), Some(0b0000000000000));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn nbitwise_iterator_with_offset() {
+        let slice: [u8; 5] = [0, 255, 255, 0, 0];
+
+        let mut iter = NBitwiseIterator::new(&slice, 2).with_offset(7);
+        assert_eq!(iter.next(), Some(0b01));
+    }
+
+    #[test]
+    fn nbitwise_iterator_empty() {
+        let slice: [u8; 0] = [];
+
+        let mut iter = NBitwiseIterator::new(&slice, 0);
+        assert_eq!(iter.next(), None);
+    }
+}
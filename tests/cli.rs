@@ -240,6 +240,149 @@ Data Representation:                    Run length packing with level values
     Ok(())
 }
 
+#[test]
+fn list_with_filters() -> Result<(), Box<dyn std::error::Error>> {
+    let tempfile = utils::jma_meps_file()?;
+    let arg_path = tempfile.path();
+
+    let out_str = "   id │ Parameter                       Generating process  Forecast time 1st fixed surface 2nd fixed surface
+    0 │ u-component of wind             Ensemble forecast           0 [h]             97500               NaN
+    3 │ u-component of wind             Ensemble forecast           0 [h]             95000               NaN
+    6 │ u-component of wind             Ensemble forecast           0 [h]             92500               NaN
+   10 │ u-component of wind             Ensemble forecast           0 [h]             85000               NaN
+   18 │ u-component of wind             Ensemble forecast           0 [h]             30000               NaN
+";
+
+    let mut cmd = Command::cargo_bin(CMD_NAME)?;
+    cmd.arg("list").arg("--param").arg("2.2").arg(arg_path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff(out_str))
+        .stderr(predicate::str::is_empty());
+
+    let out_str = "   id │ Parameter                       Generating process  Forecast time 1st fixed surface 2nd fixed surface
+   12 │ Temperature                     Ensemble forecast           0 [h]             85000               NaN
+";
+
+    let mut cmd = Command::cargo_bin(CMD_NAME)?;
+    cmd.arg("list")
+        .arg("--param")
+        .arg("temperature")
+        .arg("--level")
+        .arg("85000")
+        .arg(arg_path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff(out_str))
+        .stderr(predicate::str::is_empty());
+
+    // levels with units are converted into those of fixed surface values
+    for level in ["850 hPa", "850hPa", "85000 Pa"] {
+        let mut cmd = Command::cargo_bin(CMD_NAME)?;
+        cmd.arg("list")
+            .arg("--param")
+            .arg("temperature")
+            .arg("--level")
+            .arg(level)
+            .arg(arg_path);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::diff(out_str))
+            .stderr(predicate::str::is_empty());
+    }
+
+    let mut cmd = Command::cargo_bin(CMD_NAME)?;
+    cmd.arg("list").arg("--level").arg("850 mb").arg(arg_path);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown unit of level: mb"));
+
+    Ok(())
+}
+
+#[test]
+fn point() -> Result<(), Box<dyn std::error::Error>> {
+    let tempfile = utils::jma_tornado_nowcast_file()?;
+    let arg_path = tempfile.path();
+
+    let out_str = "   id │ Parameter                       1st fixed surface Valid time               Latitude Longitude          Value
+    0 │ code '0' is not implemented                   NaN 2016-08-22 02:00:00 UTC  35.70838  139.6875              3
+    1 │ code '0' is not implemented                   NaN 2016-08-22 02:10:00 UTC  35.70838  139.6875              3
+    2 │ code '0' is not implemented                   NaN 2016-08-22 02:20:00 UTC  35.70838  139.6875              3
+    3 │ code '0' is not implemented                   NaN 2016-08-22 02:30:00 UTC  35.70838  139.6875              3
+    4 │ code '0' is not implemented                   NaN 2016-08-22 02:40:00 UTC  35.70838  139.6875              1
+    5 │ code '0' is not implemented                   NaN 2016-08-22 02:50:00 UTC  35.70838  139.6875              1
+    6 │ code '0' is not implemented                   NaN 2016-08-22 03:00:00 UTC  35.70838  139.6875              1
+";
+
+    let mut cmd = Command::cargo_bin(CMD_NAME)?;
+    cmd.arg("point")
+        .arg("--lat")
+        .arg("35.7")
+        .arg("--lon")
+        .arg("139.7")
+        .arg(arg_path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff(out_str))
+        .stderr(predicate::str::is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn point_on_unsupported_grid() -> Result<(), Box<dyn std::error::Error>> {
+    let out_str = "   id │ Parameter                       1st fixed surface Valid time               Latitude Longitude          Value
+    0 │ Total precipitation rate                        0 2021-11-20 18:00:00 UTC                        unsupported
+";
+
+    let mut cmd = Command::cargo_bin(CMD_NAME)?;
+    cmd.arg("point")
+        .arg("--lat")
+        .arg("35.7")
+        .arg("--lon")
+        .arg("139.7")
+        .arg(utils::dwd_icon_file_path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff(out_str))
+        .stderr(predicate::str::is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn point_with_undecodable_values() -> Result<(), Box<dyn std::error::Error>> {
+    let mut buf = utils::jma_kousa_bytes()?;
+    // makes the submessage 1 use Template 5.4, which is not supported
+    let grib2 = grib::from_slice(&buf)?;
+    let sect5_offset = grib2.submessages().nth(1).unwrap().section_offsets()[&5].start;
+    buf[sect5_offset as usize + 9..][..2].copy_from_slice(&[0, 4]);
+    let mut tempfile = tempfile::NamedTempFile::new()?;
+    std::io::Write::write_all(&mut tempfile, &buf)?;
+
+    let mut cmd = Command::cargo_bin(CMD_NAME)?;
+    cmd.arg("point")
+        .arg("--lat")
+        .arg("35.7")
+        .arg("--lon")
+        .arg("139.7")
+        .arg(tempfile.path());
+    cmd.assert()
+        .success()
+        .stdout(
+            predicate::str::contains(
+                "    1 │ code '193' is not implemented                 NaN 2017-02-21 15:00:00 UTC      35.5     139.5    unsupported\n",
+            )
+            .and(predicate::str::contains(
+                "   15 │ code '193' is not implemented",
+            )),
+        )
+        .stderr(predicate::str::is_empty());
+
+    Ok(())
+}
+
 #[test]
 fn inspect() -> Result<(), Box<dyn std::error::Error>> {
     let tempfile = utils::jma_tornado_nowcast_file()?;
@@ -484,12 +627,10 @@ fn decode_run_length_packing_as_big_endian() -> Result<(), Box<dyn std::error::E
     let expected = utils::tornado_nowcast_be_bin_bytes()?;
     let expected: Vec<_> = expected
         .chunks(4)
-        .into_iter()
-        .map(|b| match b {
+        .flat_map(|b| match b {
             [0x62, 0x58, 0xd1, 0x9a] => vec![0x7f, 0xc0, 0x00, 0x00],
             b => b.to_vec(),
         })
-        .flatten()
         .collect();
     let actual = utils::cat_as_bytes(&out_path)?;
     assert_eq!(actual, expected);
@@ -520,12 +661,10 @@ fn decode_run_length_packing_as_little_endian() -> Result<(), Box<dyn std::error
     let expected = utils::tornado_nowcast_le_bin_bytes()?;
     let expected: Vec<_> = expected
         .chunks(4)
-        .into_iter()
-        .map(|b| match b {
+        .flat_map(|b| match b {
             [0x9a, 0xd1, 0x58, 0x62] => vec![0x00, 0x00, 0xc0, 0x7f],
             b => b.to_vec(),
         })
-        .flatten()
         .collect();
     let actual = utils::cat_as_bytes(&out_path)?;
     assert_eq!(actual, expected);
@@ -587,6 +726,55 @@ fn decode_simple_packing_as_little_endian() -> Result<(), Box<dyn std::error::Er
     Ok(())
 }
 
+#[test]
+fn decode_simple_packing_as_little_endian_to_stdout() -> Result<(), Box<dyn std::error::Error>> {
+    let tempfile = utils::jma_kousa_file()?;
+    let arg_path = tempfile.path();
+
+    let mut cmd = Command::cargo_bin(CMD_NAME)?;
+    cmd.arg("decode").arg(arg_path).arg("3").arg("-l").arg("-");
+    let output = cmd.output()?;
+    assert!(output.status.success());
+    assert_eq!(output.stdout.len(), 4941 * 4);
+    assert!(output.stderr.is_empty());
+
+    let expected = utils::kousa_le_bin_bytes()?;
+    assert_eq!(output.stdout, expected);
+
+    Ok(())
+}
+
+#[test]
+fn decode_simple_packing_with_opt_m() -> Result<(), Box<dyn std::error::Error>> {
+    let tempfile = utils::jma_kousa_file()?;
+    let arg_path = tempfile.path();
+
+    let dir = TempDir::new()?;
+    let out_path = dir.path().join("out.bin");
+    let out_path = format!("{}", out_path.display());
+
+    let err_str = "\
+Ni:                                     81
+Nj:                                     61
+Latitude range:                         20 - 50
+Longitude range:                        110 - 150
+";
+
+    let mut cmd = Command::cargo_bin(CMD_NAME)?;
+    cmd.arg("decode")
+        .arg(arg_path)
+        .arg("3")
+        .arg("-m")
+        .arg("-l")
+        .arg(&out_path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::diff(err_str));
+
+    Ok(())
+}
+
 #[test]
 fn decode_complex_packing_as_little_endian() -> Result<(), Box<dyn std::error::Error>> {
     let tempfile = utils::jma_meps_file()?;
@@ -614,6 +802,7 @@ fn decode_complex_packing_as_little_endian() -> Result<(), Box<dyn std::error::E
     Ok(())
 }
 
+#[cfg(feature = "jpeg2000")]
 #[test]
 fn decode_jpeg2000_code_stream_as_little_endian() -> Result<(), Box<dyn std::error::Error>> {
     let arg_path = utils::cmc_glb_file_path();
@@ -712,7 +901,7 @@ macro_rules! test_subcommands_with_non_grib {
             cmd.assert()
                 .failure()
                 .stdout(predicate::str::is_empty())
-                .stderr(predicate::str::diff("Not GRIB data\n"));
+                .stderr(predicate::str::diff("Not GRIB data (found \"foo \")\n"));
 
             Ok(())
         }
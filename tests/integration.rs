@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::Cursor;
+
+// Only some of the helpers shared with the CLI tests are used here.
+#[allow(dead_code)]
+mod utils;
+
+const GOLDEN_FILE: &str = "tests/golden/expected_hashes.json";
+const UPDATE_ENV_VAR: &str = "UPDATE_GOLDENS";
+
+struct Case {
+    name: &'static str,
+    file_name: &'static str,
+    submessage: usize,
+}
+
+const CASES: &[Case] = &[
+    Case {
+        name: "icon-simple-packing-0",
+        file_name: "icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2",
+        submessage: 0,
+    },
+    Case {
+        name: "kousa-simple-packing-0",
+        file_name: "Z__C_RJTD_20170221120000_MSG_GPV_Gll0p5deg_Pys_B20170221120000_F2017022115-2017022212_grib2.bin.xz",
+        submessage: 0,
+    },
+    Case {
+        name: "kousa-simple-packing-15",
+        file_name: "Z__C_RJTD_20170221120000_MSG_GPV_Gll0p5deg_Pys_B20170221120000_F2017022115-2017022212_grib2.bin.xz",
+        submessage: 15,
+    },
+    Case {
+        name: "meps-complex-packing-0",
+        file_name: "Z__C_RJTD_20190605000000_MEPS_GPV_Rjp_L-pall_FH00-15_grib2.bin.0-20.xz",
+        submessage: 0,
+    },
+    Case {
+        name: "tornado-run-length-0",
+        file_name: "Z__C_RJTD_20160822020000_NOWC_GPV_Ggis10km_Pphw10_FH0000-0100_grib2.bin.xz",
+        submessage: 0,
+    },
+    #[cfg(feature = "jpeg2000")]
+    Case {
+        name: "cmc-jpeg2000-0",
+        file_name: "CMC_glb_TMP_ISBL_1_latlon.24x.24_2021051800_P000.grib2",
+        submessage: 0,
+    },
+];
+
+fn read_testdata(file_name: &str) -> Vec<u8> {
+    let path = utils::testdata_dir().join(file_name);
+    if file_name.ends_with(".xz") {
+        utils::unxz_as_bytes(path).unwrap()
+    } else {
+        fs::read(path).unwrap()
+    }
+}
+
+// 64-bit FNV-1a, which is stable across platforms and Rust versions unlike
+// `DefaultHasher`.
+fn fnv1a(values: &[f32]) -> String {
+    let hash = values
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .fold(0xcbf29ce484222325_u64, |hash, b| {
+            (hash ^ u64::from(b)).wrapping_mul(0x100000001b3)
+        });
+    format!("{:016x}", hash)
+}
+
+fn decoded_hash(case: &Case) -> String {
+    let grib2 = grib::from_reader(Cursor::new(read_testdata(case.file_name))).unwrap();
+    let values = grib2.get_values(case.submessage).unwrap();
+    fnv1a(&values)
+}
+
+// Decoded values are compared with hashes stored in the golden file. Run with
+// `UPDATE_GOLDENS=1` to regenerate the file after intended changes; the file
+// is rewritten with the cases compiled in, so keep `jpeg2000` enabled.
+#[test]
+fn decoded_values_match_golden_hashes() {
+    let actual = CASES
+        .iter()
+        .map(|case| (case.name.to_owned(), decoded_hash(case)))
+        .collect::<BTreeMap<_, _>>();
+
+    if env::var_os(UPDATE_ENV_VAR).is_some() {
+        let json = serde_json::to_string_pretty(&actual).unwrap();
+        fs::write(GOLDEN_FILE, json + "\n").unwrap();
+        return;
+    }
+
+    let expected: BTreeMap<String, String> = match fs::read_to_string(GOLDEN_FILE) {
+        Ok(s) => serde_json::from_str(&s).unwrap(),
+        Err(_) => BTreeMap::new(),
+    };
+
+    let mismatches = actual
+        .iter()
+        .filter(|(name, hash)| expected.get(*name) != Some(hash))
+        .map(|(name, hash)| {
+            format!(
+                "{}: expected {}, got {}",
+                name,
+                expected.get(name).map(String::as_str).unwrap_or("none"),
+                hash
+            )
+        })
+        .collect::<Vec<_>>();
+    assert!(
+        mismatches.is_empty(),
+        "decoded values differ from {} (set {} to update):\n{}",
+        GOLDEN_FILE,
+        UPDATE_ENV_VAR,
+        mismatches.join("\n")
+    );
+}
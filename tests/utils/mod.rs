@@ -1,14 +1,18 @@
-use std::convert::TryInto;
 use std::fs::File;
 use std::io::{self, BufReader, Read, Write};
 use std::path::{Path, PathBuf};
 use tempfile::NamedTempFile;
 use xz2::bufread::XzDecoder;
 
+#[cfg(feature = "jpeg2000")]
 pub(crate) fn cmc_glb_file_path() -> PathBuf {
     testdata_dir().join("CMC_glb_TMP_ISBL_1_latlon.24x.24_2021051800_P000.grib2")
 }
 
+pub(crate) fn dwd_icon_file_path() -> PathBuf {
+    testdata_dir().join("icon_global_icosahedral_single-level_2021112018_000_TOT_PREC.grib2")
+}
+
 pub(crate) fn jma_tornado_nowcast_file() -> Result<NamedTempFile, io::Error> {
     unxz_to_tempfile(
         testdata_dir()
@@ -30,6 +34,12 @@ pub(crate) fn jma_meps_file() -> Result<NamedTempFile, io::Error> {
     )
 }
 
+pub(crate) fn jma_kousa_bytes() -> Result<Vec<u8>, io::Error> {
+    unxz_as_bytes(testdata_dir().join(
+        "Z__C_RJTD_20170221120000_MSG_GPV_Gll0p5deg_Pys_B20170221120000_F2017022115-2017022212_grib2.bin.xz",
+    ))
+}
+
 fn unxz_to_tempfile(file_path: PathBuf) -> Result<NamedTempFile, io::Error> {
     let mut buf = Vec::new();
     let mut out = NamedTempFile::new()?;
@@ -43,6 +53,7 @@ fn unxz_to_tempfile(file_path: PathBuf) -> Result<NamedTempFile, io::Error> {
     Ok(out)
 }
 
+#[cfg(feature = "jpeg2000")]
 pub(crate) fn cmc_glb_le_bin_bytes() -> Result<Vec<u8>, io::Error> {
     unxz_as_bytes(testdata_dir().join("gen").join("cmc-glb-wgrib2-le.bin.xz"))
 }
@@ -67,7 +78,7 @@ pub(crate) fn meps_le_bin_bytes() -> Result<Vec<u8>, io::Error> {
     unxz_as_bytes(testdata_dir().join("gen").join("meps-wgrib2-le.bin.xz"))
 }
 
-fn unxz_as_bytes(file_path: PathBuf) -> Result<Vec<u8>, io::Error> {
+pub(crate) fn unxz_as_bytes(file_path: PathBuf) -> Result<Vec<u8>, io::Error> {
     let mut buf = Vec::new();
 
     let f = File::open(&file_path)?;
@@ -102,6 +113,7 @@ pub(crate) fn cat_as_bytes(file_name: &str) -> Result<Vec<u8>, io::Error> {
     Ok(buf)
 }
 
+#[cfg(feature = "jpeg2000")]
 pub(crate) fn encode_le_bytes_using_simple_packing(
     input: Vec<u8>,
     ref_val: f32,
@@ -117,12 +129,11 @@ pub(crate) fn encode_le_bytes_using_simple_packing(
 
     input
         .chunks(4)
-        .into_iter()
         .map(|quad| f32::from_le_bytes(quad.try_into().unwrap())) // should be safely unwrapped
         .map(encode)
         .collect::<Vec<_>>()
 }
 
-fn testdata_dir() -> &'static Path {
+pub(crate) fn testdata_dir() -> &'static Path {
     Path::new("testdata")
 }